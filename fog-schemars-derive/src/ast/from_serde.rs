@@ -21,14 +21,34 @@ impl<'a> FromSerde for Container<'a> {
     type SerdeType = serde_ast::Container<'a>;
 
     fn from_serde(errors: &Ctxt, serde: Self::SerdeType) -> Result<Self, ()> {
+        // FIXME this allows with/schema_with attribute on containers
+        let attrs = Attrs::new(&serde.original.attrs, errors);
+        let mut data = Data::from_serde(errors, serde.data)?;
+
+        if let Some(extra_values) = &attrs.extra_values {
+            if serde.attrs.deny_unknown_fields() {
+                errors.error_spanned_by(
+                    &serde.ident,
+                    "`deny_unknown_fields` cannot be used with `allow_unknown_fields` or `extra_values`",
+                );
+            }
+            // Like `deny_unknown_fields`, this carries over to the struct
+            // variants of an enum.
+            if let Data::Enum(variants) = &mut data {
+                for variant in variants.iter_mut() {
+                    if variant.attrs.extra_values.is_none() {
+                        variant.attrs.extra_values = Some(extra_values.clone());
+                    }
+                }
+            }
+        }
+
         Ok(Self {
             ident: serde.ident,
             serde_attrs: serde.attrs,
-            data: Data::from_serde(errors, serde.data)?,
+            data,
             generics: serde.generics.clone(),
-            original: serde.original,
-            // FIXME this allows with/schema_with attribute on containers
-            attrs: Attrs::new(&serde.original.attrs, errors),
+            attrs,
         })
     }
 }
@@ -53,7 +73,6 @@ impl<'a> FromSerde for Variant<'a> {
 
     fn from_serde(errors: &Ctxt, serde: Self::SerdeType) -> Result<Self, ()> {
         Ok(Self {
-            ident: serde.ident,
             serde_attrs: serde.attrs,
             style: serde.style,
             fields: Field::vec_from_serde(errors, serde.fields)?,
@@ -68,7 +87,6 @@ impl<'a> FromSerde for Field<'a> {
 
     fn from_serde(errors: &Ctxt, serde: Self::SerdeType) -> Result<Self, ()> {
         Ok(Self {
-            member: serde.member,
             serde_attrs: serde.attrs,
             ty: serde.ty,
             original: serde.original,
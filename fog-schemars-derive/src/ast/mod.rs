@@ -10,7 +10,6 @@ pub struct Container<'a> {
     pub serde_attrs: serde_derive_internals::attr::Container,
     pub data: Data<'a>,
     pub generics: syn::Generics,
-    pub original: &'a syn::DeriveInput,
     pub attrs: Attrs,
 }
 
@@ -20,7 +19,6 @@ pub enum Data<'a> {
 }
 
 pub struct Variant<'a> {
-    pub ident: syn::Ident,
     pub serde_attrs: serde_derive_internals::attr::Variant,
     pub style: serde_ast::Style,
     pub fields: Vec<Field<'a>>,
@@ -29,7 +27,6 @@ pub struct Variant<'a> {
 }
 
 pub struct Field<'a> {
-    pub member: syn::Member,
     pub serde_attrs: serde_derive_internals::attr::Field,
     pub ty: &'a syn::Type,
     pub original: &'a syn::Field,
@@ -49,10 +46,10 @@ impl<'a> Container<'a> {
     }
 
     pub fn name(&self) -> String {
-        self.serde_attrs.name().deserialize_name()
+        self.serde_attrs.name().serialize_name()
     }
 
-    pub fn transparent_field(&'a self) -> Option<&'a Field> {
+    pub fn transparent_field(&'a self) -> Option<&'a Field<'a>> {
         if self.serde_attrs.transparent() {
             if let Data::Struct(_, fields) = &self.data {
                 return Some(&fields[0]);
@@ -65,19 +62,38 @@ impl<'a> Container<'a> {
 
 impl<'a> Variant<'a> {
     pub fn name(&self) -> String {
-        self.serde_attrs.name().deserialize_name()
+        self.serde_attrs.name().serialize_name()
+    }
+
+    /// Names other than [`Variant::name`] that are accepted when deserializing
+    /// this variant.
+    pub fn aliases(&self) -> Vec<String> {
+        let name = self.name();
+        self.serde_attrs
+            .aliases()
+            .into_iter()
+            .filter(|alias| *alias != name)
+            .collect()
     }
 
     pub fn is_unit(&self) -> bool {
-        match self.style {
-            serde_ast::Style::Unit => true,
-            _ => false,
-        }
+        matches!(self.style, serde_ast::Style::Unit)
     }
 }
 
 impl<'a> Field<'a> {
     pub fn name(&self) -> String {
-        self.serde_attrs.name().deserialize_name()
+        self.serde_attrs.name().serialize_name()
+    }
+
+    /// Names other than [`Field::name`] that are accepted when deserializing
+    /// this field.
+    pub fn aliases(&self) -> Vec<String> {
+        let name = self.name();
+        self.serde_attrs
+            .aliases()
+            .into_iter()
+            .filter(|alias| *alias != name)
+            .collect()
     }
 }
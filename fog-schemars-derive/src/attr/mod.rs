@@ -20,10 +20,11 @@ use syn::NestedMeta::{Lit, Meta};
 pub struct Attrs {
     pub with: Option<WithAttr>,
     pub deprecated: bool,
-    pub examples: Vec<syn::Path>,
     pub repr: Option<syn::Type>,
     pub crate_name: Option<syn::Path>,
     pub is_renamed: bool,
+    pub allow_missing_skip: bool,
+    pub extra_values: Option<ExtraValues>,
 }
 
 #[derive(Debug)]
@@ -32,6 +33,13 @@ pub enum WithAttr {
     Function(syn::Path),
 }
 
+/// What to check map keys against when they aren't fields of the struct.
+#[derive(Debug, Clone)]
+pub enum ExtraValues {
+    Any,
+    Type(syn::Type),
+}
+
 impl Attrs {
     pub fn new(attrs: &[syn::Attribute], errors: &Ctxt) -> Self {
         let mut result = Attrs::default()
@@ -102,7 +110,7 @@ impl Attrs {
 
                 Meta(NameValue(m)) if m.path.is_ident("rename") => self.is_renamed = true,
 
-                Meta(NameValue(m)) if m.path.is_ident("crate") && attr_type == "schemars" => {
+                Meta(NameValue(m)) if m.path.is_ident("crate") && attr_type == "fog" => {
                     if let Ok(p) = parse_lit_into_path(errors, attr_type, "crate", &m.lit) {
                         if self.crate_name.is_some() {
                             duplicate_error(m)
@@ -112,6 +120,40 @@ impl Attrs {
                     }
                 }
 
+                Meta(syn::Meta::Path(p))
+                    if p.is_ident("allow_missing_skip") && attr_type == "fog" =>
+                {
+                    self.allow_missing_skip = true
+                }
+
+                Meta(syn::Meta::Path(p))
+                    if p.is_ident("allow_unknown_fields") && attr_type == "fog" =>
+                {
+                    match self.extra_values {
+                        Some(ExtraValues::Any) => errors.error_spanned_by(
+                            p,
+                            "duplicate fog attribute `allow_unknown_fields`",
+                        ),
+                        Some(ExtraValues::Type(_)) => errors.error_spanned_by(
+                            p,
+                            "fog attribute cannot contain both `allow_unknown_fields` and `extra_values`",
+                        ),
+                        None => self.extra_values = Some(ExtraValues::Any),
+                    }
+                }
+
+                Meta(NameValue(m)) if m.path.is_ident("extra_values") && attr_type == "fog" => {
+                    if let Ok(ty) = parse_lit_into_ty(errors, attr_type, "extra_values", &m.lit) {
+                        match self.extra_values {
+                            Some(ExtraValues::Type(_)) => duplicate_error(m),
+                            Some(ExtraValues::Any) => {
+                                mutual_exclusive_error(m, "allow_unknown_fields")
+                            }
+                            None => self.extra_values = Some(ExtraValues::Type(ty)),
+                        }
+                    }
+                }
+
                 _ if ignore_errors => {}
 
                 Meta(meta_item) => {
@@ -135,29 +177,16 @@ impl Attrs {
         }
         self
     }
-
-    pub fn is_default(&self) -> bool {
-        match self {
-            Self {
-                with: None,
-                deprecated: false,
-                examples,
-                repr: None,
-                crate_name: None,
-                is_renamed: _,
-            } if examples.is_empty() => true,
-            _ => false,
-        }
-    }
 }
 
 fn is_known_serde_or_validation_keyword(meta: &syn::Meta) -> bool {
     let mut known_keywords = schemars_to_serde::SERDE_KEYWORDS
         .iter()
         .chain(validation::VALIDATION_KEYWORDS);
+    let mut unsupported_keywords = validation::UNSUPPORTED_KEYWORDS.iter().map(|(k, _)| k);
     meta.path()
         .get_ident()
-        .map(|i| known_keywords.any(|k| i == k))
+        .map(|i| known_keywords.any(|k| i == k) || unsupported_keywords.any(|k| i == k))
         .unwrap_or(false)
 }
 
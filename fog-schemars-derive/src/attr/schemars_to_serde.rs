@@ -4,10 +4,12 @@ use std::collections::HashSet;
 use syn::parse::Parser;
 use syn::{Attribute, Data, Field, Meta, NestedMeta, Variant};
 
-// List of keywords that can appear in #[serde(...)]/#[schemars(...)] attributes which we want serde_derive_internals to parse for us.
+// List of keywords that can appear in #[serde(...)]/#[fog(...)] attributes which we want serde_derive_internals to parse for us.
 pub(crate) static SERDE_KEYWORDS: &[&str] = &[
     "rename",
     "rename_all",
+    "alias",
+    "other",
     "deny_unknown_fields",
     "tag",
     "content",
@@ -20,17 +22,17 @@ pub(crate) static SERDE_KEYWORDS: &[&str] = &[
     "flatten",
     "remote",
     "transparent",
-    // Special case - `bound` is removed from serde attrs, so is only respected when present in schemars attr.
+    // Special case - `bound` is removed from serde attrs, so is only respected when present in fog attr.
     "bound",
-    // Special cases - `with`/`serialize_with` are passed to serde but not copied from schemars attrs to serde attrs.
+    // Special cases - `with`/`serialize_with` are passed to serde but not copied from fog attrs to serde attrs.
     // This is because we want to preserve any serde attribute's `serialize_with` value to determine whether the field's
-    // default value should be serialized. We also check the `with` value on schemars/serde attrs e.g. to support deriving
-    // JsonSchema on remote types, but we parse that ourselves rather than using serde_derive_internals.
+    // default value should be serialized. We also check the `with` value on fog/serde attrs e.g. to support deriving
+    // FogValidate on remote types, but we parse that ourselves rather than using serde_derive_internals.
     "serialize_with",
     "with",
 ];
 
-// If a struct/variant/field has any #[schemars] attributes, then create copies of them
+// If a struct/variant/field has any #[fog] attributes, then create copies of them
 // as #[serde] attributes so that serde_derive_internals will parse them for us.
 pub fn process_serde_attrs(input: &mut syn::DeriveInput) -> Result<(), Vec<syn::Error>> {
     let ctxt = Ctxt::new();
@@ -63,13 +65,13 @@ fn process_attrs(ctxt: &Ctxt, attrs: &mut Vec<Attribute>) {
         attrs.drain(..).partition(|at| at.path.is_ident("serde"));
     *attrs = other_attrs;
 
-    let schemars_attrs: Vec<_> = attrs
+    let fog_attrs: Vec<_> = attrs
         .iter()
-        .filter(|at| at.path.is_ident("schemars"))
+        .filter(|at| at.path.is_ident("fog"))
         .collect();
 
-    // Copy appropriate #[schemars(...)] attributes to #[serde(...)] attributes
-    let (mut serde_meta, mut schemars_meta_names): (Vec<_>, HashSet<_>) = schemars_attrs
+    // Copy appropriate #[fog(...)] attributes to #[serde(...)] attributes
+    let (mut serde_meta, mut fog_meta_names): (Vec<_>, HashSet<_>) = fog_attrs
         .iter()
         .flat_map(|at| get_meta_items(ctxt, at))
         .flatten()
@@ -83,19 +85,19 @@ fn process_attrs(ctxt: &Ctxt, attrs: &mut Vec<Attribute>) {
         })
         .unzip();
 
-    if schemars_meta_names.contains("skip") {
-        schemars_meta_names.insert("skip_serializing".to_string());
-        schemars_meta_names.insert("skip_deserializing".to_string());
+    if fog_meta_names.contains("skip") {
+        fog_meta_names.insert("skip_serializing".to_string());
+        fog_meta_names.insert("skip_deserializing".to_string());
     }
 
-    // Re-add #[serde(...)] attributes that weren't overridden by #[schemars(...)] attributes
+    // Re-add #[serde(...)] attributes that weren't overridden by #[fog(...)] attributes
     for meta in serde_attrs
         .into_iter()
         .flat_map(|at| get_meta_items(ctxt, &at))
         .flatten()
     {
         if let Ok(i) = get_meta_ident(ctxt, &meta) {
-            if !schemars_meta_names.contains(&i)
+            if !fog_meta_names.contains(&i)
                 && SERDE_KEYWORDS.contains(&i.as_ref())
                 && i != "bound"
             {
@@ -129,7 +131,7 @@ fn get_meta_items(ctxt: &Ctxt, attr: &Attribute) -> Result<Vec<NestedMeta>, ()>
     match attr.parse_meta() {
         Ok(Meta::List(meta)) => Ok(meta.nested.into_iter().collect()),
         Ok(_) => {
-            ctxt.error_spanned_by(attr, "expected #[schemars(...)] or #[serde(...)]");
+            ctxt.error_spanned_by(attr, "expected #[fog(...)] or #[serde(...)]");
             Err(())
         }
         Err(err) => {
@@ -166,32 +168,32 @@ mod tests {
         let mut input: DeriveInput = parse_quote! {
             #[serde(rename(serialize = "ser_name"), rename_all = "camelCase")]
             #[serde(default, unknown_word)]
-            #[schemars(rename = "overriden", another_unknown_word)]
+            #[fog(rename = "overriden", another_unknown_word)]
             #[misc]
             struct MyStruct {
                 /// blah blah blah
                 #[serde(skip_serializing_if = "some_fn", bound = "removed")]
                 field1: i32,
                 #[serde(serialize_with = "se", deserialize_with = "de")]
-                #[schemars(with = "with", bound = "bound")]
+                #[fog(with = "with", bound = "bound")]
                 field2: i32,
-                #[schemars(skip)]
+                #[fog(skip)]
                 #[serde(skip_serializing)]
                 field3: i32,
             }
         };
         let expected: DeriveInput = parse_quote! {
-            #[schemars(rename = "overriden", another_unknown_word)]
+            #[fog(rename = "overriden", another_unknown_word)]
             #[misc]
             #[serde(rename = "overriden", rename_all = "camelCase", default)]
             struct MyStruct {
                 #[doc = r" blah blah blah"]
                 #[serde(skip_serializing_if = "some_fn")]
                 field1: i32,
-                #[schemars(with = "with", bound = "bound")]
+                #[fog(with = "with", bound = "bound")]
                 #[serde(bound = "bound", serialize_with = "se")]
                 field2: i32,
-                #[schemars(skip)]
+                #[fog(skip)]
                 #[serde(skip)]
                 field3: i32,
             }
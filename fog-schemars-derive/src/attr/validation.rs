@@ -1,37 +1,25 @@
-use super::{get_lit_str, get_meta_items, parse_lit_into_path, parse_lit_str};
+use super::{get_lit_str, get_meta_items, parse_lit_str};
 use proc_macro2::TokenStream;
 use serde_derive_internals::Ctxt;
 use syn::{Expr, ExprLit, ExprPath, Lit, Meta, MetaNameValue, NestedMeta, Path};
 
-pub(crate) static VALIDATION_KEYWORDS: &[&str] = &[
-    "range", "regex", "contains", "length", "required",
+pub(crate) static VALIDATION_KEYWORDS: &[&str] = &["range", "regex", "length", "required"];
+
+/// Validation attributes from schemars that fog-pack has no equivalent for,
+/// along with what to use instead.
+pub(crate) static UNSUPPORTED_KEYWORDS: &[(&str, &str)] = &[
+    ("contains", "use `regex` instead"),
+    (
+        "email",
+        "use `regex` with a pattern for email addresses instead",
+    ),
+    ("url", "use `regex` with a pattern for URLs instead"),
+    (
+        "phone",
+        "use `regex` with a pattern for phone numbers instead",
+    ),
 ];
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Format {
-    Email,
-    Uri,
-    Phone,
-}
-
-impl Format {
-    fn attr_str(self) -> &'static str {
-        match self {
-            Format::Email => "email",
-            Format::Uri => "url",
-            Format::Phone => "phone",
-        }
-    }
-
-    fn schema_str(self) -> &'static str {
-        match self {
-            Format::Email => "email",
-            Format::Uri => "uri",
-            Format::Phone => "phone",
-        }
-    }
-}
-
 #[derive(Debug, Default)]
 pub struct ValidationAttrs {
     length_min: Option<Expr>,
@@ -40,9 +28,7 @@ pub struct ValidationAttrs {
     range_min: Option<Expr>,
     range_max: Option<Expr>,
     regex: Option<Expr>,
-    contains: Option<String>,
     required: bool,
-    format: Option<Format>,
 }
 
 impl ValidationAttrs {
@@ -81,21 +67,6 @@ impl ValidationAttrs {
                 errors.error_spanned_by(path, msg)
             }
         };
-        let duplicate_format_error = |existing: Format, new: Format, path: &syn::Path| {
-            if !ignore_errors {
-                let msg = if existing == new {
-                    format!("duplicate fog attribute `{}`", existing.attr_str())
-                } else {
-                    format!(
-                        "fog attribute cannot contain both `{}` and `{}`",
-                        existing.attr_str(),
-                        new.attr_str(),
-                    )
-                };
-                errors.error_spanned_by(path, msg)
-            }
-        };
-
         for meta_item in attrs
             .iter()
             .flat_map(|attr| get_meta_items(attr, attr_type, errors, ignore_errors))
@@ -182,122 +153,70 @@ impl ValidationAttrs {
                     self.required = true;
                 }
 
-                NestedMeta::Meta(Meta::Path(p)) if p.is_ident(Format::Email.attr_str()) => {
-                    match self.format {
-                        Some(f) => duplicate_format_error(f, Format::Email, p),
-                        None => self.format = Some(Format::Email),
-                    }
-                }
-                NestedMeta::Meta(Meta::Path(p)) if p.is_ident(Format::Uri.attr_str()) => {
-                    match self.format {
-                        Some(f) => duplicate_format_error(f, Format::Uri, p),
-                        None => self.format = Some(Format::Uri),
-                    }
-                }
-                NestedMeta::Meta(Meta::Path(p)) if p.is_ident(Format::Phone.attr_str()) => {
-                    match self.format {
-                        Some(f) => duplicate_format_error(f, Format::Phone, p),
-                        None => self.format = Some(Format::Phone),
-                    }
-                }
-
                 NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("regex") => {
-                    match (&self.regex, &self.contains) {
-                        (Some(_), _) => duplicate_error(&nv.path),
-                        (None, Some(_)) => mutual_exclusive_error(&nv.path, "contains"),
-                        (None, None) => {
-                            self.regex =
-                                parse_lit_into_expr_path(errors, attr_type, "regex", &nv.lit).ok()
+                    if self.regex.is_some() {
+                        duplicate_error(&nv.path)
+                    } else if let Lit::Str(s) = &nv.lit {
+                        match parse_lit_str::<ExprPath>(s) {
+                            Ok(path) => self.regex = Some(Expr::Path(path)),
+                            Err(_) => errors.error_spanned_by(
+                                s,
+                                "expected `regex` to be a path to a string, or use \
+                                `regex(pattern = \"...\")` for a pattern",
+                            ),
                         }
+                    } else {
+                        errors.error_spanned_by(&nv.lit, "expected `regex` to be a string");
                     }
                 }
 
                 NestedMeta::Meta(Meta::List(meta_list)) if meta_list.path.is_ident("regex") => {
-                    match (&self.regex, &self.contains) {
-                        (Some(_), _) => duplicate_error(&meta_list.path),
-                        (None, Some(_)) => mutual_exclusive_error(&meta_list.path, "contains"),
-                        (None, None) => {
-                            for x in meta_list.nested.iter() {
-                                match x {
-                                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                                        path,
-                                        lit,
-                                        ..
-                                    })) if path.is_ident("path") => {
-                                        self.regex =
-                                            parse_lit_into_expr_path(errors, attr_type, "path", lit)
-                                                .ok()
-                                    }
-                                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                                        path,
-                                        lit,
-                                        ..
-                                    })) if path.is_ident("pattern") => {
-                                        self.regex = get_lit_str(errors, attr_type, "pattern", lit)
-                                            .ok()
-                                            .map(|litstr| {
-                                                Expr::Lit(syn::ExprLit {
-                                                    attrs: Vec::new(),
-                                                    lit: Lit::Str(litstr.clone()),
-                                                })
-                                            })
-                                    }
-                                    meta => {
-                                        if !ignore_errors {
-                                            errors.error_spanned_by(
-                                                meta,
-                                                "unknown item in fog regex attribute".to_string(),
-                                            );
-                                        }
-                                    }
+                    if self.regex.is_some() {
+                        duplicate_error(&meta_list.path);
+                        continue;
+                    }
+                    for nested in meta_list.nested.iter() {
+                        match nested {
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path, lit, ..
+                            })) if path.is_ident("pattern") => {
+                                if let Ok(s) = get_lit_str(errors, attr_type, "pattern", lit) {
+                                    self.regex = Some(Expr::Lit(ExprLit {
+                                        attrs: Vec::new(),
+                                        lit: Lit::Str(s.clone()),
+                                    }));
+                                }
+                            }
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path, lit, ..
+                            })) if path.is_ident("path") => {
+                                self.regex = str_or_num_to_expr(errors, "path", lit);
+                            }
+                            meta => {
+                                if !ignore_errors {
+                                    errors.error_spanned_by(
+                                        meta,
+                                        "unknown item in fog regex attribute".to_string(),
+                                    );
                                 }
                             }
                         }
                     }
                 }
 
-                NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
-                    if path.is_ident("contains") =>
-                {
-                    match (&self.contains, &self.regex) {
-                        (Some(_), _) => duplicate_error(path),
-                        (None, Some(_)) => mutual_exclusive_error(path, "regex"),
-                        (None, None) => {
-                            self.contains = get_lit_str(errors, attr_type, "contains", lit)
-                                .map(|litstr| litstr.value())
-                                .ok()
-                        }
-                    }
-                }
-
-                NestedMeta::Meta(Meta::List(meta_list)) if meta_list.path.is_ident("contains") => {
-                    match (&self.contains, &self.regex) {
-                        (Some(_), _) => duplicate_error(&meta_list.path),
-                        (None, Some(_)) => mutual_exclusive_error(&meta_list.path, "regex"),
-                        (None, None) => {
-                            for x in meta_list.nested.iter() {
-                                match x {
-                                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                                        path,
-                                        lit,
-                                        ..
-                                    })) if path.is_ident("pattern") => {
-                                        self.contains =
-                                            get_lit_str(errors, attr_type, "contains", lit)
-                                                .ok()
-                                                .map(|litstr| litstr.value())
-                                    }
-                                    meta => {
-                                        if !ignore_errors {
-                                            errors.error_spanned_by(
-                                                meta,
-                                                "unknown item in fog contains attribute".to_string(),
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                NestedMeta::Meta(meta) if !ignore_errors => {
+                    let unsupported = UNSUPPORTED_KEYWORDS
+                        .iter()
+                        .find(|(keyword, _)| meta.path().is_ident(keyword));
+                    if let Some((keyword, instead)) = unsupported {
+                        errors.error_spanned_by(
+                            meta.path(),
+                            format!(
+                                "fog attribute `{}` isn't supported, as fog-pack has no \
+                                equivalent; {}",
+                                keyword, instead
+                            ),
+                        );
                     }
                 }
 
@@ -307,156 +226,127 @@ impl ValidationAttrs {
         self
     }
 
-    pub fn apply_to_schema(&self, schema_expr: &mut TokenStream) {
-        let mut array_validation = Vec::new();
-        let mut number_validation = Vec::new();
-        let mut object_validation = Vec::new();
-        let mut string_validation = Vec::new();
+    /// Wrap a validator expression so the validation attributes are applied
+    /// to its result.
+    pub fn apply_to_validator(&self, validator_expr: &mut TokenStream) {
+        if let Some(refine) = self.refine_fn() {
+            *validator_expr = quote! {
+                (#refine)(#validator_expr)
+            }
+        }
+    }
 
-        if let Some(length_min) = self
-            .length_min
-            .as_ref()
-            .or(self.length_equal.as_ref())
-        {
-            string_validation.push(quote! {
-                validation.min_length = Some(#length_min as u32);
-            });
-            array_validation.push(quote! {
-                validation.min_items = Some(#length_min as u32);
+    /// A closure that applies the validation attributes to a validator, if
+    /// there are any that would change it. This uses `gen`, so it has to be
+    /// made before any call that borrows it.
+    pub fn refine_fn(&self) -> Option<TokenStream> {
+        let mut len_validation = Vec::new();
+        let mut int_validation = Vec::new();
+        let mut float_validation = Vec::new();
+
+        if let Some(length_min) = self.length_min.as_ref().or(self.length_equal.as_ref()) {
+            len_validation.push(quote! {
+                v.min_len = #length_min as u32;
             });
         }
 
-        if let Some(length_max) = self
-            .length_max
-            .as_ref()
-            .or(self.length_equal.as_ref())
-        {
-            string_validation.push(quote! {
-                validation.max_length = Some(#length_max as u32);
-            });
-            array_validation.push(quote! {
-                validation.max_items = Some(#length_max as u32);
+        if let Some(length_max) = self.length_max.as_ref().or(self.length_equal.as_ref()) {
+            len_validation.push(quote! {
+                v.max_len = #length_max as u32;
             });
         }
 
         if let Some(range_min) = &self.range_min {
-            number_validation.push(quote! {
-                validation.minimum = Some(#range_min as f64);
+            int_validation.push(quote! {
+                v.min = fog_schemars::_fog_pack::types::Integer::from(#range_min);
+            });
+            float_validation.push(quote! {
+                v.min = #range_min as _;
             });
         }
 
         if let Some(range_max) = &self.range_max {
-            number_validation.push(quote! {
-                validation.maximum = Some(#range_max as f64);
+            int_validation.push(quote! {
+                v.max = fog_schemars::_fog_pack::types::Integer::from(#range_max);
             });
-        }
-
-        if let Some(regex) = &self.regex {
-            string_validation.push(quote! {
-                validation.pattern = Some(#regex.to_string());
+            float_validation.push(quote! {
+                v.max = #range_max as _;
             });
         }
 
-        let format = self.format.as_ref().map(|f| {
-            let f = f.schema_str();
-            quote! {
-                schema_object.format = Some(#f.to_string());
-            }
-        });
-
-        let array_validation = wrap_array_validation(array_validation);
-        let number_validation = wrap_number_validation(number_validation);
-        let object_validation = wrap_object_validation(object_validation);
-        let string_validation = wrap_string_validation(string_validation);
-
-        if array_validation.is_some()
-            || number_validation.is_some()
-            || object_validation.is_some()
-            || string_validation.is_some()
-            || format.is_some()
-        {
-            *schema_expr = quote! {
-                {
-                    let mut schema = #schema_expr;
-                    if let schemars::schema::Schema::Object(schema_object) = &mut schema
-                    {
-                        #array_validation
-                        #number_validation
-                        #object_validation
-                        #string_validation
-                        #format
-                    }
-                    schema
-                }
-            }
+        // A float range can only be for a float, and `Integer` can't be made
+        // from one.
+        let is_float = |e: &Option<Expr>| {
+            matches!(
+                e,
+                Some(Expr::Lit(ExprLit {
+                    lit: Lit::Float(_),
+                    ..
+                }))
+            )
+        };
+        if is_float(&self.range_min) || is_float(&self.range_max) {
+            int_validation.clear();
         }
-    }
-}
 
-fn parse_lit_into_expr_path(
-    cx: &Ctxt,
-    attr_type: &'static str,
-    meta_item_name: &'static str,
-    lit: &syn::Lit,
-) -> Result<Expr, ()> {
-    parse_lit_into_path(cx, attr_type, meta_item_name, lit).map(|path| {
-        Expr::Path(ExprPath {
-            attrs: Vec::new(),
-            qself: None,
-            path,
-        })
-    })
-}
-
-fn wrap_array_validation(v: Vec<TokenStream>) -> Option<TokenStream> {
-    if v.is_empty() {
-        None
-    } else {
-        Some(quote! {
-            if schema_object.has_type(schemars::schema::InstanceType::Array) {
-                let validation = schema_object.array();
-                #(#v)*
+        let mut arms = Vec::new();
+        let mut str_validation = len_validation.clone();
+        if self.regex.is_some() {
+            str_validation.push(quote! {
+                v.matches = matches;
+            });
+        }
+        if !str_validation.is_empty() {
+            arms.push(quote! {
+                Validator::Str(v) => { #(#str_validation)* }
+            });
+        }
+        if !len_validation.is_empty() {
+            for variant in ["Bin", "Array", "Map"] {
+                let variant = format_ident!("{}", variant);
+                arms.push(quote! {
+                    Validator::#variant(v) => { #(#len_validation)* }
+                });
             }
-        })
-    }
-}
-
-fn wrap_number_validation(v: Vec<TokenStream>) -> Option<TokenStream> {
-    if v.is_empty() {
-        None
-    } else {
-        Some(quote! {
-            if schema_object.has_type(schemars::schema::InstanceType::Integer)
-                || schema_object.has_type(schemars::schema::InstanceType::Number) {
-                let validation = schema_object.number();
-                #(#v)*
+        }
+        if !int_validation.is_empty() {
+            arms.push(quote! {
+                Validator::Int(v) => { #(#int_validation)* }
+            });
+        }
+        if !float_validation.is_empty() {
+            for variant in ["F32", "F64"] {
+                let variant = format_ident!("{}", variant);
+                arms.push(quote! {
+                    Validator::#variant(v) => { #(#float_validation)* }
+                });
             }
-        })
-    }
-}
+        }
 
-fn wrap_object_validation(v: Vec<TokenStream>) -> Option<TokenStream> {
-    if v.is_empty() {
-        None
-    } else {
-        Some(quote! {
-            if schema_object.has_type(schemars::schema::InstanceType::Object) {
-                let validation = schema_object.object();
-                #(#v)*
-            }
-        })
-    }
-}
+        if arms.is_empty() {
+            return None;
+        }
 
-fn wrap_string_validation(v: Vec<TokenStream>) -> Option<TokenStream> {
-    if v.is_empty() {
-        None
-    } else {
-        Some(quote! {
-            if schema_object.has_type(schemars::schema::InstanceType::String) {
-                let validation = schema_object.string();
-                #(#v)*
+        let refine = quote! {
+            move |mut validator: fog_schemars::_fog_pack::validator::Validator| {
+                use fog_schemars::_fog_pack::validator::Validator;
+                match &mut validator {
+                    #(#arms)*
+                    _ => (),
+                }
+                validator
             }
+        };
+        // The regex is compiled up front, so an invalid one can be reported
+        // to the generator.
+        Some(match &self.regex {
+            Some(regex) => quote! {
+                {
+                    let matches = fog_schemars::_private::regex(gen, #regex);
+                    #refine
+                }
+            },
+            None => refine,
         })
     }
 }
@@ -8,6 +8,8 @@ extern crate proc_macro;
 
 mod ast;
 mod attr;
+mod lints;
+mod schema;
 mod validator_exprs;
 
 use ast::*;
@@ -27,6 +29,19 @@ pub fn derive_fog_validate_wrapper(input: proc_macro::TokenStream) -> proc_macro
         .into()
 }
 
+#[proc_macro_derive(FogSchema, attributes(fog_schema))]
+pub fn derive_fog_schema_wrapper(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    schema::derive_fog_schema(input)
+        .unwrap_or_else(|errs| {
+            let compile_errors = errs.iter().map(syn::Error::to_compile_error);
+            quote! {
+                #(#compile_errors)*
+            }
+        })
+        .into()
+}
+
 fn derive_fog_validate(mut input: syn::DeriveInput) -> Result<TokenStream, Vec<syn::Error>> {
     attr::process_serde_attrs(&mut input)?;
 
@@ -54,19 +69,18 @@ fn derive_fog_validate(mut input: syn::DeriveInput) -> Result<TokenStream, Vec<s
                     }
 
                     fn has_opt() -> bool {
-                        <#ty as fog_schemars::FogValidate>::has_default_opt()
+                        <#ty as fog_schemars::FogValidate>::has_opt()
                     }
 
-                    fn validator_name(opt: bool) -> String {
+                    fn validator_name(opt: bool) -> fog_schemars::Name {
                         <#ty as fog_schemars::FogValidate>::validator_name(opt)
                     }
 
-                    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
-                        <#ty as fog_schemars::FogValidate>::validator(gen)
-                    }
-
-                    fn validator_type_id() -> std::any::TypeId {
-                        <#ty as fog_schemars::FogValidate>::validator_type_id()
+                    fn validator(
+                        gen: &mut fog_schemars::SchemaGenerator,
+                        opt: bool
+                    ) -> fog_schemars::_fog_pack::validator::Validator {
+                        gen.type_validator::<#ty>(opt)
                     }
                 }
             };
@@ -82,47 +96,46 @@ fn derive_fog_validate(mut input: syn::DeriveInput) -> Result<TokenStream, Vec<s
         }
     }
 
+    // Renamed types are taken to be using exactly the name the user wants,
+    // otherwise we make it unique by prepending the module path.
+    let module_path = if cont.attrs.is_renamed {
+        quote!("")
+    } else {
+        quote!(module_path!())
+    };
     let type_params: Vec<_> = cont.generics.type_params().map(|ty| &ty.ident).collect();
-    let validator_name = if type_params.is_empty() || (cont.attrs.is_renamed && !base_name.contains('{')) {
-        quote! { #base_name.to_owned() }
-    }
-    else if cont.attrs.is_renamed {
-        let mut name_fmt = base_name;
-        for typ in &type_params {
-            name_fmt.push_str(&format!("{{{}:.0}}", typ));
-        }
-        quote! {
-            format!(#name_fmt #(,#type_params=#type_params::validator_name())*)
-        }
-    }
-    else {
-        let mut name_fmt = base_name;
-        name_fmt.push_str(&"_{}".repeat(type_params.len()));
-        quote! {
-            format!(#name_fmt #(,#type_params::validator_name())*)
-        }
+    let validator_name = quote! {
+        fog_schemars::Name::with_types(
+            #module_path,
+            #base_name,
+            vec![#(<#type_params as fog_schemars::FogValidate>::validator_name(false)),*],
+        )
     };
 
     let validator_expr = validator_exprs::expr_for_container(&cont);
+    let lints = lints::unbalanced_default_lints(&cont);
 
     Ok(quote! {
         const _: () = {
             #crate_alias
+            #lints
 
             #[automatically_derived]
             #[allow(unused_braces)]
-            impl #impl_generics fog_schemars::FogSchema for #type_name #ty_generics #where_clause {
-                fn validator_name(_: bool) -> std::string::String {
+            impl #impl_generics fog_schemars::FogValidate for #type_name #ty_generics #where_clause {
+                fn validator_name(_: bool) -> fog_schemars::Name {
                     #validator_name
                 }
 
-                fn validator(gen: &mut fog_schemars::SchemaGenerator, _:bool) -> _fog_pack::validator::Validator {
+                fn validator(
+                    gen: &mut fog_schemars::SchemaGenerator,
+                    _: bool
+                ) -> fog_schemars::_fog_pack::validator::Validator {
                     #validator_expr
                 }
             }
-        }
+        };
     })
-
 }
 
 fn add_trait_bounds(cont: &mut Container) {
@@ -134,7 +147,7 @@ fn add_trait_bounds(cont: &mut Container) {
         // TODO consider also adding trait bound to associated types when used as fields - I think Serde does this?
         for param in &mut cont.generics.params {
             if let syn::GenericParam::Type(ref mut type_param) = *param {
-                type_param.bounds.push(parse_quote!(schemars::JsonSchema));
+                type_param.bounds.push(parse_quote!(fog_schemars::FogValidate));
             }
         }
     }
@@ -0,0 +1,181 @@
+use crate::ast::*;
+use crate::validator_exprs::FieldPresence;
+use proc_macro2::TokenStream;
+use serde_derive_internals::ast::Style;
+use serde_derive_internals::attr::Default as SerdeDefault;
+use syn::spanned::Spanned;
+
+/// Warn about fields where `default` and `skip_serializing_if` aren't used
+/// together.
+///
+/// Stable Rust has no way for a derive macro to emit a warning, so instead we
+/// emit a use of a deprecated constant spanned to the offending field. The
+/// deprecation note then shows up as a regular compiler warning. Warnings can
+/// be silenced with `#[fog(allow_missing_skip)]` on either the field or the
+/// container.
+pub fn unbalanced_default_lints(cont: &Container) -> TokenStream {
+    if cont.attrs.allow_missing_skip {
+        return TokenStream::new();
+    }
+
+    let mut lints = Vec::new();
+    match &cont.data {
+        Data::Struct(Style::Struct, fields) => {
+            lints.extend(fields_lints(fields, cont.serde_attrs.default()));
+        }
+        Data::Enum(variants) => {
+            for variant in variants {
+                if matches!(variant.style, Style::Struct) && !variant.attrs.allow_missing_skip {
+                    lints.extend(fields_lints(&variant.fields, &SerdeDefault::None));
+                }
+            }
+        }
+        _ => (),
+    }
+
+    quote! { #(#lints)* }
+}
+
+fn fields_lints<'a>(
+    fields: &'a [Field],
+    container_default: &'a SerdeDefault,
+) -> impl Iterator<Item = TokenStream> + 'a {
+    fields
+        .iter()
+        .filter(|f| {
+            !f.attrs.allow_missing_skip
+                && !f.attrs.deprecated
+                && !f.validation_attrs.required()
+                && !f.serde_attrs.flatten()
+                && !f.serde_attrs.skip_serializing()
+                && !f.serde_attrs.skip_deserializing()
+        })
+        .filter_map(move |field| {
+            let presence = FieldPresence::of(field, container_default);
+            let has_skip_if = field.serde_attrs.skip_serializing_if().is_some();
+            let name = field.name();
+            let note = match (presence, has_skip_if) {
+                (FieldPresence::Optional, _) => format!(
+                    "field `{}` has a serde `default` but no `skip_serializing_if`, so the \
+                    default value is always serialized and can't be checked against the \
+                    type's optional validator. Add `skip_serializing_if` or \
+                    `#[fog(allow_missing_skip)]` to the field.",
+                    name
+                ),
+                (FieldPresence::Required, true) => format!(
+                    "field `{}` has a serde `skip_serializing_if` but no `default`, so \
+                    documents that skip it will fail validation. Add `#[serde(default)]` \
+                    or `#[fog(allow_missing_skip)]` to the field.",
+                    name
+                ),
+                _ => return None,
+            };
+
+            // Span to the field's name, as its attributes may have been
+            // regenerated by us, and rustc won't report deprecations spanned
+            // to a derive expansion.
+            let span = match &field.original.ident {
+                Some(ident) => ident.span(),
+                None => field.ty.span(),
+            };
+            Some(quote_spanned! {span=>
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    #[allow(non_upper_case_globals)]
+                    const unbalanced_default_and_skip_serializing_if: () = ();
+                    unbalanced_default_and_skip_serializing_if
+                };
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attr::process_serde_attrs;
+    use syn::DeriveInput;
+
+    fn lints(mut input: DeriveInput) -> String {
+        process_serde_attrs(&mut input).unwrap();
+        let cont = Container::from_ast(&input).unwrap();
+        unbalanced_default_lints(&cont).to_string()
+    }
+
+    fn warned_fields(input: DeriveInput) -> Vec<String> {
+        let lints = lints(input);
+        let mut fields = Vec::new();
+        for part in lints.split("field `").skip(1) {
+            fields.push(part.split('`').next().unwrap().to_owned());
+        }
+        fields
+    }
+
+    #[test]
+    fn test_unbalanced_fields_warn() {
+        let input = parse_quote! {
+            struct Unbalanced {
+                #[serde(default)]
+                only_default: u8,
+                #[serde(skip_serializing_if = "is_zero")]
+                only_skip: u8,
+                #[serde(default, skip_serializing_if = "is_zero")]
+                both: u8,
+                neither: u8,
+            }
+        };
+        let lints = lints(input);
+        assert_eq!(lints.matches("# [deprecated").count(), 2);
+        assert!(lints.contains("const unbalanced_default_and_skip_serializing_if"));
+        assert!(lints
+            .contains("field `only_default` has a serde `default` but no `skip_serializing_if`"));
+        assert!(
+            lints.contains("field `only_skip` has a serde `skip_serializing_if` but no `default`")
+        );
+    }
+
+    #[test]
+    fn test_allow_missing_skip_on_field() {
+        let input = parse_quote! {
+            struct Allowed {
+                #[serde(default)]
+                #[fog(allow_missing_skip)]
+                allowed: u8,
+                #[serde(default)]
+                warned: u8,
+            }
+        };
+        assert_eq!(warned_fields(input), ["warned"]);
+    }
+
+    #[test]
+    fn test_allow_missing_skip_on_variant() {
+        let input = parse_quote! {
+            enum Allowed {
+                #[fog(allow_missing_skip)]
+                Allowed {
+                    #[serde(default)]
+                    a: u8,
+                },
+                Warned {
+                    #[serde(default)]
+                    b: u8,
+                },
+            }
+        };
+        assert_eq!(warned_fields(input), ["b"]);
+    }
+
+    #[test]
+    fn test_allow_missing_skip_on_container() {
+        let input = parse_quote! {
+            #[fog(allow_missing_skip)]
+            struct Allowed {
+                #[serde(default)]
+                a: u8,
+                #[serde(skip_serializing_if = "is_zero")]
+                b: u8,
+            }
+        };
+        assert_eq!(lints(input), "");
+    }
+}
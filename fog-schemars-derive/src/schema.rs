@@ -0,0 +1,255 @@
+use proc_macro2::TokenStream;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+/// Everything set by the `#[fog_schema(...)]` attributes on a type.
+#[derive(Default)]
+struct SchemaAttrs {
+    crate_name: Option<syn::Path>,
+    name: Option<syn::LitStr>,
+    description: Option<syn::LitStr>,
+    version: Option<syn::LitInt>,
+    doc_compress: Option<CompressAttr>,
+    regexes: Option<syn::LitInt>,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    key: syn::LitStr,
+    ty: syn::Type,
+    compress: Option<CompressAttr>,
+}
+
+enum CompressAttr {
+    None,
+    Zstd(Option<syn::LitInt>),
+}
+
+enum Arg {
+    Crate(syn::Ident, syn::Path),
+    Name(syn::Ident, syn::LitStr),
+    Description(syn::Ident, syn::LitStr),
+    Version(syn::Ident, syn::LitInt),
+    DocCompress(syn::Ident, CompressAttr),
+    Regexes(syn::Ident, syn::LitInt),
+    Entry(Entry),
+}
+
+impl CompressAttr {
+    /// Parse a compression setting of `"none"`, `"zstd"`, or `"zstd:<level>"`.
+    fn parse_lit(lit: &syn::LitStr) -> syn::Result<Self> {
+        let value = lit.value();
+        match value.split_once(':') {
+            None if value == "none" => Ok(CompressAttr::None),
+            None if value == "zstd" => Ok(CompressAttr::Zstd(None)),
+            Some(("zstd", level)) => match level.parse::<u8>() {
+                Ok(_) => Ok(CompressAttr::Zstd(Some(syn::LitInt::new(
+                    level,
+                    lit.span(),
+                )))),
+                Err(_) => Err(syn::Error::new(
+                    lit.span(),
+                    format!("invalid zstd compression level `{}`", level),
+                )),
+            },
+            _ => Err(syn::Error::new(
+                lit.span(),
+                "expected compression to be one of \"none\", \"zstd\", or \"zstd:<level>\"",
+            )),
+        }
+    }
+
+    fn expr(&self) -> TokenStream {
+        match self {
+            CompressAttr::None => quote!(fog_schemars::_fog_pack::schema::Compress::None),
+            CompressAttr::Zstd(None) => quote!(
+                <fog_schemars::_fog_pack::schema::Compress as ::core::default::Default>::default()
+            ),
+            CompressAttr::Zstd(Some(level)) => quote!(
+                fog_schemars::_fog_pack::schema::Compress::new_zstd_general(#level)
+            ),
+        }
+    }
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let ty = input.parse()?;
+        let mut compress = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let ident: syn::Ident = input.parse()?;
+            if ident != "compress" {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("unknown fog_schema entry attribute `{}`", ident),
+                ));
+            }
+            if compress.is_some() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "duplicate fog_schema entry attribute `compress`",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            compress = Some(CompressAttr::parse_lit(&input.parse()?)?);
+        }
+        Ok(Entry { key, ty, compress })
+    }
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.call(syn::Ident::parse_any)?;
+        if ident == "entry" {
+            let content;
+            parenthesized!(content in input);
+            return Ok(Arg::Entry(content.parse()?));
+        }
+
+        input.parse::<Token![=]>()?;
+        Ok(match ident.to_string().as_str() {
+            "crate" => {
+                let path: syn::LitStr = input.parse()?;
+                let path = path.parse()?;
+                Arg::Crate(ident, path)
+            }
+            "name" => Arg::Name(ident, input.parse()?),
+            "description" => Arg::Description(ident, input.parse()?),
+            "version" => Arg::Version(ident, input.parse()?),
+            "doc_compress" => {
+                let compress = CompressAttr::parse_lit(&input.parse()?)?;
+                Arg::DocCompress(ident, compress)
+            }
+            "regexes" => Arg::Regexes(ident, input.parse()?),
+            _ => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("unknown fog_schema attribute `{}`", ident),
+                ))
+            }
+        })
+    }
+}
+
+impl SchemaAttrs {
+    fn new(attrs: &[syn::Attribute]) -> Result<Self, Vec<syn::Error>> {
+        let mut result = SchemaAttrs::default();
+        let mut errors = Vec::new();
+
+        fn set<T>(errors: &mut Vec<syn::Error>, slot: &mut Option<T>, ident: syn::Ident, value: T) {
+            if slot.is_some() {
+                errors.push(syn::Error::new(
+                    ident.span(),
+                    format!("duplicate fog_schema attribute `{}`", ident),
+                ));
+            } else {
+                *slot = Some(value);
+            }
+        }
+
+        for attr in attrs.iter().filter(|a| a.path.is_ident("fog_schema")) {
+            let args = match attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated) {
+                Ok(args) => args,
+                Err(err) => {
+                    errors.push(err);
+                    continue;
+                }
+            };
+            for arg in args {
+                match arg {
+                    Arg::Crate(i, v) => set(&mut errors, &mut result.crate_name, i, v),
+                    Arg::Name(i, v) => set(&mut errors, &mut result.name, i, v),
+                    Arg::Description(i, v) => set(&mut errors, &mut result.description, i, v),
+                    Arg::Version(i, v) => set(&mut errors, &mut result.version, i, v),
+                    Arg::DocCompress(i, v) => set(&mut errors, &mut result.doc_compress, i, v),
+                    Arg::Regexes(i, v) => set(&mut errors, &mut result.regexes, i, v),
+                    Arg::Entry(entry) => {
+                        if result
+                            .entries
+                            .iter()
+                            .any(|e| e.key.value() == entry.key.value())
+                        {
+                            errors.push(syn::Error::new(
+                                entry.key.span(),
+                                format!("duplicate fog_schema entry {:?}", entry.key.value()),
+                            ));
+                        } else {
+                            result.entries.push(entry);
+                        }
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(result)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+pub fn derive_fog_schema(input: syn::DeriveInput) -> Result<TokenStream, Vec<syn::Error>> {
+    let attrs = SchemaAttrs::new(&input.attrs)?;
+
+    let crate_alias = attrs.crate_name.as_ref().map(|path| {
+        quote_spanned! { path.span() => use #path as fog_schemars; }
+    });
+
+    let type_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = attrs.name.map(|v| quote!(.name(#v)));
+    let description = attrs.description.map(|v| quote!(.description(#v)));
+    let version = attrs.version.map(|v| quote!(.version(#v)));
+    let regexes = attrs.regexes.map(|v| quote!(.regexes(#v)));
+    let doc_compress = attrs.doc_compress.map(|v| {
+        let compress = v.expr();
+        quote!(.doc_compress(#compress))
+    });
+
+    let keys: Vec<_> = attrs.entries.iter().map(|e| &e.key).collect();
+    let entries = attrs.entries.iter().map(|entry| {
+        let key = &entry.key;
+        let ty = &entry.ty;
+        let compress = match &entry.compress {
+            Some(compress) => {
+                let compress = compress.expr();
+                quote!(::core::option::Option::Some(#compress))
+            }
+            None => quote!(::core::option::Option::None),
+        };
+        quote_spanned! {ty.span()=>
+            .entry_add::<#ty>(#key, #compress)
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            #crate_alias
+
+            #[automatically_derived]
+            impl #impl_generics fog_schemars::FogSchema for #type_name #ty_generics #where_clause {
+                const ENTRIES: &'static [&'static str] = &[#(#keys),*];
+
+                fn schema_generator() -> fog_schemars::SchemaGenerator {
+                    fog_schemars::SchemaGenerator::new::<Self>()
+                        #name
+                        #description
+                        #version
+                        #regexes
+                        #doc_compress
+                        #(#entries)*
+                }
+            }
+        };
+    })
+}
@@ -1,84 +1,56 @@
-use std::collections::HashSet;
-
-use crate::{ast::*, attr::WithAttr};
+use crate::{
+    ast::*,
+    attr::{ExtraValues, WithAttr},
+};
 use proc_macro2::{Span, TokenStream};
 use serde_derive_internals::ast::Style;
 use serde_derive_internals::attr::{self as serde_attr, Default as SerdeDefault, TagType};
 use syn::spanned::Spanned;
 
 pub fn expr_for_container(cont: &Container) -> TokenStream {
-    let mut schema_expr = match &cont.data {
+    match &cont.data {
         Data::Struct(Style::Unit, _) => expr_for_unit_struct(),
         Data::Struct(Style::Newtype, fields) => expr_for_newtype_struct(&fields[0]),
         Data::Struct(Style::Tuple, fields) => expr_for_tuple_struct(fields),
         Data::Struct(Style::Struct, fields) => expr_for_struct(
             fields,
             cont.serde_attrs.default(),
-            cont.serde_attrs.deny_unknown_fields(),
+            cont.attrs.extra_values.as_ref(),
         ),
         Data::Enum(variants) => expr_for_enum(variants, &cont.serde_attrs),
-    };
-
-    schema_expr
-}
-
-pub fn expr_for_repr(cont: &Container) -> Result<TokenStream, syn::Error> {
-    let repr_type = cont.attrs.repr.as_ref().ok_or_else(|| {
-        syn::Error::new(
-            Span::call_site(),
-            "JsonSchema_repr: missing #[repr(...)] attribute",
-        )
-    })?;
-
-    let variants = match &cont.data {
-        Data::Enum(variants) => variants,
-        _ => return Err(syn::Error::new(Span::call_site(), "oh no!")),
-    };
-
-    if let Some(non_unit_error) = variants.iter().find_map(|v| match v.style {
-        Style::Unit => None,
-        _ => Some(syn::Error::new(
-            v.original.span(),
-            "JsonSchema_repr: must be a unit variant",
-        )),
-    }) {
-        return Err(non_unit_error);
-    };
-
-    let enum_ident = &cont.ident;
-    let variant_idents = variants.iter().map(|v| &v.ident);
-
-    let mut schema_expr = schema_object(quote! {
-        instance_type: Some(schemars::schema::InstanceType::Integer.into()),
-        enum_values: Some(vec![#((#enum_ident::#variant_idents as #repr_type).into()),*]),
-    });
-
-    Ok(schema_expr)
+    }
 }
 
-fn expr_for_field(field: &Field, allow_ref: bool) -> TokenStream {
+fn expr_for_field(field: &Field, opt: bool) -> TokenStream {
     let (ty, type_def) = type_for_field_validator(field);
     let span = field.original.span();
     let gen = quote!(gen);
 
-    let mut schema_expr = if field.validation_attrs.required() {
-        quote_spanned! {span=>
-            <#ty as schemars::JsonSchema>::_schemars_private_non_optional_json_schema(#gen)
-        }
-    } else if allow_ref {
-        quote_spanned! {span=>
-            #gen.subschema_for::<#ty>()
-        }
-    } else {
-        quote_spanned! {span=>
-            <#ty as schemars::JsonSchema>::json_schema(#gen)
-        }
+    // Validation attributes refine the type's validator, which goes in the
+    // type list under its own name if the type's validator would.
+    let mut validator_expr = match (field.validation_attrs.refine_fn(), opt) {
+        (None, false) => quote_spanned! {span=>
+            #gen.type_add::<#ty>()
+        },
+        (None, true) => quote_spanned! {span=>
+            #gen.type_add_opt::<#ty>()
+        },
+        (Some(refine), false) => quote_spanned! {span=>
+            {
+                let refine = #refine;
+                #gen.type_add_with::<#ty>(refine)
+            }
+        },
+        (Some(refine), true) => quote_spanned! {span=>
+            {
+                let refine = #refine;
+                #gen.type_add_opt_with::<#ty>(refine)
+            }
+        },
     };
 
-    prepend_type_def(type_def, &mut schema_expr);
-    field.validation_attrs.apply_to_schema(&mut schema_expr);
-
-    schema_expr
+    prepend_type_def(type_def, &mut validator_expr);
+    validator_expr
 }
 
 pub fn type_for_field_validator(field: &Field) -> (syn::Type, Option<TokenStream>) {
@@ -92,22 +64,25 @@ fn type_for_validator(with_attr: &WithAttr) -> (syn::Type, Option<TokenStream>)
     match with_attr {
         WithAttr::Type(ty) => (ty.to_owned(), None),
         WithAttr::Function(fun) => {
-            let ty_name = syn::Ident::new("_SchemarsSchemaWithFunction", Span::call_site());
+            let ty_name = syn::Ident::new("_FogValidateWithFunction", Span::call_site());
             let fn_name = fun.segments.last().unwrap().ident.to_string();
 
             let type_def = quote_spanned! {fun.span()=>
                 struct #ty_name;
 
-                impl schemars::JsonSchema for #ty_name {
-                    fn is_referenceable() -> bool {
+                impl fog_schemars::FogValidate for #ty_name {
+                    fn should_reference(_: bool) -> bool {
                         false
                     }
 
-                    fn schema_name() -> std::string::String {
-                        #fn_name.to_string()
+                    fn validator_name(_: bool) -> fog_schemars::Name {
+                        fog_schemars::Name::new("", #fn_name)
                     }
 
-                    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+                    fn validator(
+                        gen: &mut fog_schemars::SchemaGenerator,
+                        _: bool
+                    ) -> fog_schemars::_fog_pack::validator::Validator {
                         #fun(gen)
                     }
                 }
@@ -119,506 +94,610 @@ fn type_for_validator(with_attr: &WithAttr) -> (syn::Type, Option<TokenStream>)
 }
 
 fn expr_for_enum(variants: &[Variant], cattrs: &serde_attr::Container) -> TokenStream {
-    let deny_unknown_fields = cattrs.deny_unknown_fields();
-    let variants = variants
+    // We describe the serialized form, so variants that can only be
+    // deserialized are left out.
+    let variants: Vec<_> = variants
+        .iter()
+        .filter(|v| !v.serde_attrs.skip_serializing())
+        .collect();
+
+    // Every tag that maps onto a variant, other than a `#[serde(other)]` one.
+    let known_names: Vec<_> = variants
         .iter()
-        .filter(|v| !v.serde_attrs.skip_deserializing());
+        .filter(|v| !v.serde_attrs.other())
+        .flat_map(|v| variant_names(v))
+        .collect();
 
     match cattrs.tag() {
-        TagType::External => expr_for_external_tagged_enum(variants, deny_unknown_fields),
-        TagType::None => expr_for_untagged_enum(variants, deny_unknown_fields),
-        TagType::Internal { tag } => {
-            expr_for_internal_tagged_enum(variants, tag, deny_unknown_fields)
-        }
+        TagType::External => expr_for_external_tagged_enum(&variants, &known_names),
+        TagType::None => expr_for_untagged_enum(&variants),
+        TagType::Internal { tag } => expr_for_internal_tagged_enum(&variants, &known_names, tag),
         TagType::Adjacent { tag, content } => {
-            expr_for_adjacent_tagged_enum(variants, tag, content, deny_unknown_fields)
+            expr_for_adjacent_tagged_enum(&variants, &known_names, tag, content)
         }
     }
 }
 
-fn expr_for_external_tagged_enum<'a>(
-    variants: impl Iterator<Item = &'a Variant<'a>>,
-    deny_unknown_fields: bool,
-) -> TokenStream {
-    let mut unique_names = HashSet::<String>::new();
-    let mut count = 0;
-    let (unit_variants, complex_variants): (Vec<_>, Vec<_>) = variants
-        .inspect(|v| {
-            unique_names.insert(v.name());
-            count += 1;
-        })
-        .partition(|v| v.is_unit() && v.attrs.is_default());
-    let unit_names = unit_variants.iter().map(|v| v.name());
-    let unit_schema = schema_object(quote! {
-        instance_type: Some(schemars::schema::InstanceType::String.into()),
-        enum_values: Some(vec![#(#unit_names.into()),*]),
-    });
-
-    if complex_variants.is_empty() {
-        return unit_schema;
-    }
-
-    let mut schemas = Vec::new();
-    if !unit_variants.is_empty() {
-        schemas.push(unit_schema);
-    }
-
-    schemas.extend(complex_variants.into_iter().map(|variant| {
-        let name = variant.name();
+/// The name of a variant, followed by all of its aliases.
+fn variant_names(variant: &Variant) -> Vec<String> {
+    let mut names = vec![variant.name()];
+    names.extend(variant.aliases());
+    names
+}
 
-        let mut schema_expr = if variant.is_unit() && variant.attrs.with.is_none() {
-            schema_object(quote! {
-                instance_type: Some(schemars::schema::InstanceType::String.into()),
-                enum_values: Some(vec![#name.into()]),
-            })
+fn expr_for_external_tagged_enum(variants: &[&Variant], known_names: &[String]) -> TokenStream {
+    // This maps exactly onto how fog-pack's EnumValidator works: unit variants
+    // are a bare string, everything else is a single-entry map.
+    let inserts = variants.iter().map(|variant| {
+        let names = variant_names(variant);
+        let insert = if variant.is_unit() && variant.attrs.with.is_none() {
+            quote! { #(.insert(#names, None))* }
         } else {
-            let sub_schema = expr_for_untagged_enum_variant(variant, deny_unknown_fields);
-            schema_object(quote! {
-                instance_type: Some(schemars::schema::InstanceType::Object.into()),
-                object: Some(Box::new(schemars::schema::ObjectValidation {
-                    properties: {
-                        let mut props = schemars::Map::new();
-                        props.insert(#name.to_owned(), #sub_schema);
-                        props
-                    },
-                    required: {
-                        let mut required = schemars::Set::new();
-                        required.insert(#name.to_owned());
-                        required
-                    },
-                    // Externally tagged variants must prohibit additional
-                    // properties irrespective of the disposition of
-                    // `deny_unknown_fields`. If additional properties were allowed
-                    // one could easily construct an object that validated against
-                    // multiple variants since here it's the properties rather than
-                    // the values of a property that distingish between variants.
-                    additional_properties: Some(Box::new(false.into())),
-                    ..Default::default()
-                })),
-            })
+            let validator_expr = expr_for_untagged_enum_variant(variant);
+            quote! { #(.insert(#names, Some(#validator_expr)))* }
         };
+        (variant.attrs.deprecated, insert)
+    });
 
-        schema_expr
-    }));
-
-    variant_subschemas(unique_names.len() == count, schemas)
+    let enum_validator = build_unless_strict(
+        quote!(fog_schemars::_fog_pack::validator::EnumValidator::new()),
+        inserts,
+    );
+
+    // EnumValidator only accepts the variants it knows about, so a
+    // `#[serde(other)]` variant needs a second validator for unknown ones.
+    if variants.iter().any(|v| v.serde_attrs.other()) {
+        multi_validator(
+            vec![
+                (false, enum_validator),
+                (
+                    false,
+                    quote! {
+                        fog_schemars::_fog_pack::validator::StrValidator::new()
+                            #(.nin_add(#known_names))*
+                            .build()
+                    },
+                ),
+            ]
+            .into_iter(),
+        )
+    } else {
+        enum_validator
+    }
 }
 
-fn expr_for_internal_tagged_enum<'a>(
-    variants: impl Iterator<Item = &'a Variant<'a>>,
+fn expr_for_internal_tagged_enum(
+    variants: &[&Variant],
+    known_names: &[String],
     tag_name: &str,
-    deny_unknown_fields: bool,
 ) -> TokenStream {
-    let mut unique_names = HashSet::new();
-    let mut count = 0;
-    let set_additional_properties = if deny_unknown_fields {
-        quote! {
-            additional_properties: Some(Box::new(false.into())),
-        }
-    } else {
-        TokenStream::new()
-    };
-    let variant_schemas = variants
-        .map(|variant| {
-            unique_names.insert(variant.name());
-            count += 1;
-
-            let name = variant.name();
-            let type_schema = schema_object(quote! {
-                instance_type: Some(schemars::schema::InstanceType::String.into()),
-                enum_values: Some(vec![#name.into()]),
-            });
+    let variant_validators = variants.iter().map(|variant| {
+        let tag_validator = tag_validator(variant, known_names);
 
-            let mut tag_schema = schema_object(quote! {
-                instance_type: Some(schemars::schema::InstanceType::Object.into()),
-                object: Some(Box::new(schemars::schema::ObjectValidation {
-                    properties: {
-                        let mut props = schemars::Map::new();
-                        props.insert(#tag_name.to_owned(), #type_schema);
-                        props
-                    },
-                    required: {
-                        let mut required = schemars::Set::new();
-                        required.insert(#tag_name.to_owned());
-                        required
-                    },
-                    // As we're creating a "wrapper" object, we can honor the
-                    // disposition of deny_unknown_fields.
-                    #set_additional_properties
-                    ..Default::default()
-                })),
+        let flatten =
+            expr_for_untagged_enum_variant_for_flatten(variant).map(|variant_validator| {
+                quote! {
+                    let flattened = #variant_validator;
+                    let validator = fog_schemars::_private::flatten(gen, validator, flattened);
+                }
             });
 
-            if let Some(variant_schema) =
-                expr_for_untagged_enum_variant_for_flatten(variant, deny_unknown_fields)
+        let validator = quote! {
             {
-                tag_schema.extend(quote!(.flatten(#variant_schema)))
+                let validator = fog_schemars::_fog_pack::validator::MapValidator::new()
+                    .req_add(#tag_name, #tag_validator);
+                #flatten
+                validator.build()
             }
+        };
+        (variant.attrs.deprecated, validator)
+    });
 
-            tag_schema
-        })
-        .collect();
-
-    variant_subschemas(unique_names.len() == count, variant_schemas)
+    multi_validator(variant_validators)
 }
 
-fn expr_for_untagged_enum<'a>(
-    variants: impl Iterator<Item = &'a Variant<'a>>,
-    deny_unknown_fields: bool,
-) -> TokenStream {
-    let schemas = variants
-        .map(|variant| {
-            let mut schema_expr = expr_for_untagged_enum_variant(variant, deny_unknown_fields);
-
-            schema_expr
-        })
-        .collect();
-
-    // Untagged enums can easily have variants whose schemas overlap; rather
-    // that checking the exclusivity of each subschema we simply us `any_of`.
-    variant_subschemas(false, schemas)
+fn expr_for_untagged_enum(variants: &[&Variant]) -> TokenStream {
+    multi_validator(
+        variants
+            .iter()
+            .map(|v| (v.attrs.deprecated, expr_for_untagged_enum_variant(v))),
+    )
 }
 
-fn expr_for_adjacent_tagged_enum<'a>(
-    variants: impl Iterator<Item = &'a Variant<'a>>,
+fn expr_for_adjacent_tagged_enum(
+    variants: &[&Variant],
+    known_names: &[String],
     tag_name: &str,
     content_name: &str,
-    deny_unknown_fields: bool,
 ) -> TokenStream {
-    let mut unique_names = HashSet::new();
-    let mut count = 0;
-    let schemas = variants
-        .map(|variant| {
-            unique_names.insert(variant.name());
-            count += 1;
-
-            let content_schema = if variant.is_unit() && variant.attrs.with.is_none() {
-                None
-            } else {
-                Some(expr_for_untagged_enum_variant(variant, deny_unknown_fields))
-            };
+    let variant_validators = variants.iter().map(|variant| {
+        let add_content = if variant.is_unit() && variant.attrs.with.is_none() {
+            None
+        } else {
+            let content_validator = expr_for_untagged_enum_variant(variant);
+            Some(quote!(.req_add(#content_name, #content_validator)))
+        };
 
-            let (add_content_to_props, add_content_to_required) = content_schema
-                .map(|content_schema| {
-                    (
-                        quote!(props.insert(#content_name.to_owned(), #content_schema);),
-                        quote!(required.insert(#content_name.to_owned());),
-                    )
-                })
-                .unwrap_or_default();
-
-            let name = variant.name();
-            let tag_schema = schema_object(quote! {
-                instance_type: Some(schemars::schema::InstanceType::String.into()),
-                enum_values: Some(vec![#name.into()]),
-            });
+        let tag_validator = tag_validator(variant, known_names);
 
-            let set_additional_properties = if deny_unknown_fields {
-                quote! {
-                    additional_properties: Some(Box::new(false.into())),
-                }
-            } else {
-                TokenStream::new()
-            };
+        let validator = quote! {
+            fog_schemars::_fog_pack::validator::MapValidator::new()
+                .req_add(#tag_name, #tag_validator)
+                #add_content
+                .build()
+        };
+        (variant.attrs.deprecated, validator)
+    });
 
-            let mut outer_schema = schema_object(quote! {
-                instance_type: Some(schemars::schema::InstanceType::Object.into()),
-                object: Some(Box::new(schemars::schema::ObjectValidation {
-                    properties: {
-                        let mut props = schemars::Map::new();
-                        props.insert(#tag_name.to_owned(), #tag_schema);
-                        #add_content_to_props
-                        props
-                    },
-                    required: {
-                        let mut required = schemars::Set::new();
-                        required.insert(#tag_name.to_owned());
-                        #add_content_to_required
-                        required
-                    },
-                    // As we're creating a "wrapper" object, we can honor the
-                    // disposition of deny_unknown_fields.
-                    #set_additional_properties
-                    ..Default::default()
-                })),
-            });
+    multi_validator(variant_validators)
+}
 
-            outer_schema
-        })
-        .collect();
+/// A validator for the string tag identifying an enum variant. A
+/// `#[serde(other)]` variant is identified by any tag that isn't known.
+fn tag_validator(variant: &Variant, known_names: &[String]) -> TokenStream {
+    let comment = variant
+        .attrs
+        .deprecated
+        .then(|| quote!(.comment("deprecated")));
+    if variant.serde_attrs.other() {
+        quote! {
+            fog_schemars::_fog_pack::validator::StrValidator::new()
+                #(.nin_add(#known_names))*
+                #comment
+                .build()
+        }
+    } else {
+        let names = variant_names(variant);
+        quote! {
+            fog_schemars::_fog_pack::validator::StrValidator::new()
+                #(.in_add(#names))*
+                #comment
+                .build()
+        }
+    }
+}
 
-    variant_subschemas(unique_names.len() == count, schemas)
+/// Combine validators into a [`MultiValidator`], leaving out the deprecated
+/// ones when making a strict schema.
+fn multi_validator(validators: impl Iterator<Item = (bool, TokenStream)>) -> TokenStream {
+    let pushes = validators.map(|(deprecated, validator)| (deprecated, quote!(.push(#validator))));
+    build_unless_strict(
+        quote!(fog_schemars::_fog_pack::validator::MultiValidator::new()),
+        pushes,
+    )
 }
 
-/// Callers must determine if all subschemas are mutually exclusive. This can
-/// be done for most tagging regimes by checking that all tag names are unique.
-fn variant_subschemas(unique: bool, schemas: Vec<TokenStream>) -> TokenStream {
-    if unique {
-        schema_object(quote! {
-            subschemas: Some(Box::new(schemars::schema::SubschemaValidation {
-                one_of: Some(vec![#(#schemas),*]),
-                ..Default::default()
-            })),
-        })
-    } else {
-        schema_object(quote! {
-            subschemas: Some(Box::new(schemars::schema::SubschemaValidation {
-                any_of: Some(vec![#(#schemas),*]),
-                ..Default::default()
-            })),
-        })
+/// Build a validator from a builder expression and a list of builder method
+/// calls, each flagged with whether it comes from something deprecated. The
+/// deprecated calls are only made when the generator isn't strict.
+fn build_unless_strict(
+    builder: TokenStream,
+    calls: impl Iterator<Item = (bool, TokenStream)>,
+) -> TokenStream {
+    let (deprecated, calls): (Vec<_>, Vec<_>) = calls.partition(|(deprecated, _)| *deprecated);
+    let calls = calls.into_iter().map(|(_, call)| call);
+    match unless_strict(deprecated.into_iter().map(|(_, call)| call)) {
+        None => quote! {
+            #builder
+                #(#calls)*
+                .build()
+        },
+        Some(unless_strict) => quote! {
+            {
+                let validator = #builder
+                    #(#calls)*;
+                #unless_strict
+                validator.build()
+            }
+        },
+    }
+}
+
+/// A statement making the given builder method calls on `validator`, but only
+/// when the generator isn't strict.
+fn unless_strict(calls: impl Iterator<Item = TokenStream>) -> Option<TokenStream> {
+    let calls: Vec<_> = calls.collect();
+    if calls.is_empty() {
+        return None;
     }
+    Some(quote! {
+        let validator = if gen.is_strict() {
+            validator
+        } else {
+            validator #(#calls)*
+        };
+    })
 }
 
-fn expr_for_untagged_enum_variant(variant: &Variant, deny_unknown_fields: bool) -> TokenStream {
+fn expr_for_untagged_enum_variant(variant: &Variant) -> TokenStream {
     if let Some(with_attr) = &variant.attrs.with {
         let (ty, type_def) = type_for_validator(with_attr);
         let gen = quote!(gen);
-        let mut schema_expr = quote_spanned! {variant.original.span()=>
-            #gen.subschema_for::<#ty>()
+        let mut validator_expr = quote_spanned! {variant.original.span()=>
+            #gen.type_add::<#ty>()
         };
 
-        prepend_type_def(type_def, &mut schema_expr);
-        return schema_expr;
+        prepend_type_def(type_def, &mut validator_expr);
+        return validator_expr;
     }
 
     match variant.style {
         Style::Unit => expr_for_unit_struct(),
-        Style::Newtype => expr_for_field(&variant.fields[0], true),
+        Style::Newtype => expr_for_field(&variant.fields[0], false),
         Style::Tuple => expr_for_tuple_struct(&variant.fields),
-        Style::Struct => expr_for_struct(&variant.fields, &SerdeDefault::None, deny_unknown_fields),
+        Style::Struct => expr_for_struct(
+            &variant.fields,
+            &SerdeDefault::None,
+            variant.attrs.extra_values.as_ref(),
+        ),
     }
 }
 
-fn expr_for_untagged_enum_variant_for_flatten(
-    variant: &Variant,
-    deny_unknown_fields: bool,
-) -> Option<TokenStream> {
+fn expr_for_untagged_enum_variant_for_flatten(variant: &Variant) -> Option<TokenStream> {
     if let Some(with_attr) = &variant.attrs.with {
         let (ty, type_def) = type_for_validator(with_attr);
         let gen = quote!(gen);
-        let mut schema_expr = quote_spanned! {variant.original.span()=>
-            <#ty as schemars::JsonSchema>::json_schema(#gen)
+        let mut validator_expr = quote_spanned! {variant.original.span()=>
+            #gen.type_validator::<#ty>(false)
         };
 
-        prepend_type_def(type_def, &mut schema_expr);
-        return Some(schema_expr);
+        prepend_type_def(type_def, &mut validator_expr);
+        return Some(validator_expr);
     }
 
     Some(match variant.style {
         Style::Unit => return None,
         Style::Newtype => expr_for_field(&variant.fields[0], false),
         Style::Tuple => expr_for_tuple_struct(&variant.fields),
-        Style::Struct => expr_for_struct(&variant.fields, &SerdeDefault::None, deny_unknown_fields),
+        Style::Struct => expr_for_struct(
+            &variant.fields,
+            &SerdeDefault::None,
+            variant.attrs.extra_values.as_ref(),
+        ),
     })
 }
 
 fn expr_for_unit_struct() -> TokenStream {
     quote! {
-        gen.subschema_for::<()>()
+        fog_schemars::_fog_pack::validator::Validator::Null
     }
 }
 
 fn expr_for_newtype_struct(field: &Field) -> TokenStream {
-    expr_for_field(field, true)
+    // The validator for a newtype is the validator of its inner type, and
+    // `FogValidate::validator` should never return a reference, so we can't
+    // just use `type_add` here.
+    let (ty, type_def) = type_for_field_validator(field);
+    let mut validator_expr = quote_spanned! {field.original.span()=>
+        gen.type_validator::<#ty>(false)
+    };
+
+    prepend_type_def(type_def, &mut validator_expr);
+    field
+        .validation_attrs
+        .apply_to_validator(&mut validator_expr);
+
+    validator_expr
 }
 
 fn expr_for_tuple_struct(fields: &[Field]) -> TokenStream {
+    // Unlike in `expr_for_struct`, `skip_serializing` fields can't be kept as
+    // optional, as leaving one out shifts the position of every later field.
     let fields: Vec<_> = fields
         .iter()
-        .filter(|f| !f.serde_attrs.skip_deserializing())
-        .map(|f| expr_for_field(f, true))
+        .filter(|f| !f.serde_attrs.skip_serializing())
+        .map(|f| expr_for_field(f, false))
         .collect();
     let len = fields.len() as u32;
 
     quote! {
-        schemars::schema::Schema::Object(
-            schemars::schema::SchemaObject {
-            instance_type: Some(schemars::schema::InstanceType::Array.into()),
-            array: Some(Box::new(schemars::schema::ArrayValidation {
-                items: Some(vec![#(#fields),*].into()),
-                max_items: Some(#len),
-                min_items: Some(#len),
-                ..Default::default()
-            })),
-            ..Default::default()
-        })
+        fog_schemars::_fog_pack::validator::ArrayValidator::new()
+            #(.prefix_add(#fields))*
+            .min_len(#len)
+            .max_len(#len)
+            .build()
+    }
+}
+
+/// How a struct field shows up in the generated [`MapValidator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldPresence {
+    /// The field is always serialized, and must always be present.
+    Required,
+    /// The field may be missing, and is skipped when it holds its default
+    /// value, so the `opt` validator of the field's type is used.
+    OptionalSkipped,
+    /// The field may be missing, but is never skipped for holding its default
+    /// value, so the full validator of the field's type is used. This is also
+    /// used for fields that are never serialized, as older documents may still
+    /// hold them.
+    Optional,
+}
+
+impl FieldPresence {
+    pub fn of(field: &Field, container_default: &SerdeDefault) -> Self {
+        let has_default = !field.serde_attrs.default().is_none() || !container_default.is_none();
+        let has_skip_if = field.serde_attrs.skip_serializing_if().is_some();
+        match (has_default, has_skip_if) {
+            _ if field.serde_attrs.skip_serializing() => FieldPresence::Optional,
+            _ if field.validation_attrs.required() => FieldPresence::Required,
+            // Serde gives these an implicit default, but that only matters
+            // when reading, not for whether the field gets written.
+            (_, true) if field.serde_attrs.skip_deserializing() => FieldPresence::OptionalSkipped,
+            (_, false) if field.serde_attrs.skip_deserializing() => FieldPresence::Required,
+            (true, true) => FieldPresence::OptionalSkipped,
+            (true, false) => FieldPresence::Optional,
+            (false, _) => FieldPresence::Required,
+        }
     }
 }
 
 fn expr_for_struct(
     fields: &[Field],
     default: &SerdeDefault,
-    deny_unknown_fields: bool,
+    extra_values: Option<&ExtraValues>,
 ) -> TokenStream {
+    // Fields skipped entirely are never seen by serde, so leave them out.
     let (flattened_fields, property_fields): (Vec<_>, Vec<_>) = fields
         .iter()
         .filter(|f| !f.serde_attrs.skip_deserializing() || !f.serde_attrs.skip_serializing())
         .partition(|f| f.serde_attrs.flatten());
 
-    let set_container_default = match default {
-        SerdeDefault::None => None,
-        SerdeDefault::Default => Some(quote!(let container_default = Self::default();)),
-        SerdeDefault::Path(path) => Some(quote!(let container_default = #path();)),
+    // Without a `values` validator, fog-pack rejects any key that isn't a
+    // field, same as `deny_unknown_fields`.
+    let mut properties = match extra_values {
+        None => Vec::new(),
+        Some(ExtraValues::Any) => {
+            vec![quote!(.values(fog_schemars::_fog_pack::validator::Validator::Any))]
+        }
+        Some(ExtraValues::Type(ty)) => vec![quote_spanned! {ty.span()=>
+            .values(gen.type_add::<#ty>())
+        }],
     };
+    let mut alias_branches = Vec::new();
+    let mut deprecated = Vec::new();
+    let mut deprecated_names = Vec::new();
+    for field in property_fields {
+        let mut names = vec![field.name()];
+        names.extend(field.aliases());
+        let presence = FieldPresence::of(field, default);
+        let validator_expr = expr_for_field(field, presence == FieldPresence::OptionalSkipped);
+        if field.attrs.deprecated {
+            // Deprecated fields are always optional, so old documents that
+            // still have them keep passing, and new ones can leave them out.
+            deprecated.extend(
+                names
+                    .iter()
+                    .map(|name| quote!(.opt_add(#name, #validator_expr))),
+            );
+            deprecated_names.push(names.swap_remove(0));
+            continue;
+        }
+        match presence {
+            // A required field with aliases must be present under exactly one
+            // of its names, which a single map validator can't express.
+            FieldPresence::Required if names.len() > 1 => alias_branches.push(quote! {
+                let field_validator = #validator_expr;
+                let branches = fog_schemars::_private::alias_branches(
+                    gen,
+                    branches,
+                    &[#(#names),*],
+                    field_validator
+                );
+            }),
+            FieldPresence::Required => {
+                let name = &names[0];
+                properties.push(quote!(.req_add(#name, #validator_expr)))
+            }
+            FieldPresence::OptionalSkipped | FieldPresence::Optional => properties.extend(
+                names
+                    .iter()
+                    .map(|name| quote!(.opt_add(#name, #validator_expr))),
+            ),
+        }
+    }
 
-    let properties: Vec<_> = property_fields
-        .into_iter()
-        .map(|field| {
-            let name = field.name();
-            let default = field_default_expr(field, set_container_default.is_some());
-
-            let (ty, type_def) = type_for_field_validator(field);
-
-            let maybe_insert_required = match (&default, field.validation_attrs.required()) {
-                (Some(_), _) => TokenStream::new(),
-                (None, false) => {
-                    quote! {
-                        if !<#ty as schemars::JsonSchema>::_schemars_private_is_option() {
-                            object_validation.required.insert(#name.to_owned());
-                        }
-                    }
-                }
-                (None, true) => quote! {
-                    object_validation.required.insert(#name.to_owned());
-                },
-            };
+    if !deprecated_names.is_empty() {
+        let comment = format!("deprecated fields: {}", deprecated_names.join(", "));
+        deprecated.push(quote!(.comment(#comment)));
+    }
+    let deprecated = unless_strict(deprecated.into_iter());
 
-            let gen = quote!(gen);
-            let mut schema_expr = if field.validation_attrs.required() {
-                quote_spanned! {ty.span()=>
-                    <#ty as schemars::JsonSchema>::_schemars_private_non_optional_json_schema(#gen)
-                }
-            } else {
-                quote_spanned! {ty.span()=>
-                    #gen.subschema_for::<#ty>()
-                }
-            };
+    let flattens = flattened_fields.into_iter().map(|field| {
+        let (ty, type_def) = type_for_field_validator(field);
 
-            field.validation_attrs.apply_to_schema(&mut schema_expr);
+        let mut validator_expr = quote_spanned! {ty.span()=>
+            gen.type_validator::<#ty>(false)
+        };
 
+        prepend_type_def(type_def, &mut validator_expr);
+        let flatten = quote! {
+            let flattened = #validator_expr;
+            let validator = fog_schemars::_private::flatten(gen, validator, flattened);
+        };
+        if field.attrs.deprecated {
             quote! {
-                {
-                    #type_def
-                    object_validation.properties.insert(#name.to_owned(), #schema_expr);
-                    #maybe_insert_required
-                }
+                let validator = if gen.is_strict() {
+                    validator
+                } else {
+                    #flatten
+                    validator
+                };
             }
-        })
-        .collect();
-
-    let flattens: Vec<_> = flattened_fields
-        .into_iter()
-        .map(|field| {
-            let (ty, type_def) = type_for_field_validator(field);
-
-            let required = field.validation_attrs.required();
-
-            let args = quote!(gen, #required);
-            let mut schema_expr = quote_spanned! {ty.span()=>
-                schemars::_private::json_schema_for_flatten::<#ty>(#args)
-            };
-
-            prepend_type_def(type_def, &mut schema_expr);
-            schema_expr
-        })
-        .collect();
+        } else {
+            flatten
+        }
+    });
 
-    let set_additional_properties = if deny_unknown_fields {
+    let build = if alias_branches.is_empty() {
+        quote!(validator.build())
+    } else {
         quote! {
-            object_validation.additional_properties = Some(Box::new(false.into()));
+            let branches = vec![validator];
+            #(#alias_branches)*
+            fog_schemars::_private::one_of(branches)
         }
-    } else {
-        TokenStream::new()
     };
+
     quote! {
         {
-            #set_container_default
-            let mut schema_object = schemars::schema::SchemaObject {
-                instance_type: Some(schemars::schema::InstanceType::Object.into()),
-                ..Default::default()
-            };
-            let object_validation = schema_object.object();
-            #set_additional_properties
-            #(#properties)*
-            schemars::schema::Schema::Object(schema_object)
-            #(.flatten(#flattens))*
+            let validator = fog_schemars::_fog_pack::validator::MapValidator::new()
+                #(#properties)*;
+            #deprecated
+            #(#flattens)*
+            #build
         }
     }
 }
 
-fn field_default_expr(field: &Field, container_has_default: bool) -> Option<TokenStream> {
-    let field_default = field.serde_attrs.default();
-    if field.serde_attrs.skip_serializing() || (field_default.is_none() && !container_has_default) {
-        return None;
+fn prepend_type_def(type_def: Option<TokenStream>, validator_expr: &mut TokenStream) {
+    if let Some(type_def) = type_def {
+        *validator_expr = quote! {
+            {
+                #type_def
+                #validator_expr
+            }
+        }
     }
+}
 
-    let ty = field.ty;
-    let default_expr = match field_default {
-        SerdeDefault::None => {
-            let member = &field.member;
-            quote!(container_default.#member)
-        }
-        SerdeDefault::Default => quote!(<#ty>::default()),
-        SerdeDefault::Path(path) => quote!(#path()),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attr::process_serde_attrs;
+    use pretty_assertions::assert_eq;
+    use syn::DeriveInput;
+
+    fn expand(mut input: DeriveInput) -> String {
+        process_serde_attrs(&mut input).unwrap();
+        let cont = Container::from_ast(&input).unwrap();
+        expr_for_container(&cont).to_string()
+    }
 
-    let default_expr = match field.serde_attrs.skip_serializing_if() {
-        Some(skip_if) => {
-            quote! {
-                {
-                    let default = #default_expr;
-                    if #skip_if(&default) {
-                        None
-                    } else {
-                        Some(default)
-                    }
-                }
+    #[test]
+    fn test_struct_skips() {
+        let actual = expand(parse_quote! {
+            struct Skips {
+                #[serde(skip)]
+                skipped: u8,
+                #[serde(skip_serializing)]
+                never_written: u8,
+                #[serde(skip_deserializing)]
+                never_read: u8,
+                #[serde(skip_deserializing, skip_serializing_if = "is_zero")]
+                sometimes_written: u8,
             }
-        }
-        None => quote!(Some(#default_expr)),
-    };
-
-    Some(if let Some(ser_with) = field.serde_attrs.serialize_with() {
-        quote! {
+        });
+        let expected = quote! {
             {
-                struct _SchemarsDefaultSerialize<T>(T);
-
-                impl serde::Serialize for _SchemarsDefaultSerialize<#ty>
-                {
-                    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-                    where
-                        S: serde::Serializer
-                    {
-                        #ser_with(&self.0, serializer)
-                    }
-                }
+                let validator = fog_schemars::_fog_pack::validator::MapValidator::new()
+                    .opt_add("never_written", gen.type_add::<u8>())
+                    .req_add("never_read", gen.type_add::<u8>())
+                    .opt_add("sometimes_written", gen.type_add_opt::<u8>());
+                validator.build()
+            }
+        };
+        assert_eq!(actual, expected.to_string());
+    }
 
-                #default_expr.map(|d| _SchemarsDefaultSerialize(d))
+    #[test]
+    fn test_struct_rename_serialize() {
+        let actual = expand(parse_quote! {
+            struct Renamed {
+                #[serde(rename(serialize = "written", deserialize = "read"))]
+                field: u8,
             }
-        }
-    } else {
-        default_expr
-    })
-}
+        });
+        let expected = quote! {
+            {
+                let validator = fog_schemars::_fog_pack::validator::MapValidator::new();
+                let branches = vec![validator];
+                let field_validator = gen.type_add::<u8>();
+                let branches = fog_schemars::_private::alias_branches(
+                    gen,
+                    branches,
+                    &["written", "read"],
+                    field_validator
+                );
+                fog_schemars::_private::one_of(branches)
+            }
+        };
+        assert_eq!(actual, expected.to_string());
+    }
 
-fn schema_object(properties: TokenStream) -> TokenStream {
-    quote! {
-        schemars::schema::Schema::Object(
-            schemars::schema::SchemaObject {
-            #properties
-            ..Default::default()
-        })
+    #[test]
+    fn test_tuple_skips() {
+        let actual = expand(parse_quote! {
+            struct Tuple(u8, #[serde(skip_serializing)] u16, #[serde(skip_deserializing)] u32);
+        });
+        let expected = quote! {
+            fog_schemars::_fog_pack::validator::ArrayValidator::new()
+                .prefix_add(gen.type_add::<u8>())
+                .prefix_add(gen.type_add::<u32>())
+                .min_len(2u32)
+                .max_len(2u32)
+                .build()
+        };
+        assert_eq!(actual, expected.to_string());
     }
-}
 
-fn prepend_type_def(type_def: Option<TokenStream>, schema_expr: &mut TokenStream) {
-    if let Some(type_def) = type_def {
-        *schema_expr = quote! {
+    #[test]
+    fn test_enum_skips() {
+        let actual = expand(parse_quote! {
+            enum Skips {
+                #[serde(skip_serializing)]
+                NeverWritten,
+                #[serde(skip_deserializing)]
+                NeverRead,
+                #[serde(skip)]
+                Skipped,
+                #[serde(other)]
+                Unknown,
+            }
+        });
+        let expected = quote! {
+            fog_schemars::_fog_pack::validator::MultiValidator::new()
+                .push(
+                    fog_schemars::_fog_pack::validator::EnumValidator::new()
+                        .insert("NeverRead", None)
+                        .insert("Unknown", None)
+                        .build()
+                )
+                .push(
+                    fog_schemars::_fog_pack::validator::StrValidator::new()
+                        .nin_add("NeverRead")
+                        .build()
+                )
+                .build()
+        };
+        assert_eq!(actual, expected.to_string());
+    }
+
+    #[test]
+    fn test_struct_deprecated() {
+        let actual = expand(parse_quote! {
+            struct Deprecated {
+                current: u8,
+                #[deprecated]
+                #[serde(alias = "older")]
+                old: u8,
+            }
+        });
+        let expected = quote! {
             {
-                #type_def
-                #schema_expr
+                let validator = fog_schemars::_fog_pack::validator::MapValidator::new()
+                    .req_add("current", gen.type_add::<u8>());
+                let validator = if gen.is_strict() {
+                    validator
+                } else {
+                    validator
+                        .opt_add("old", gen.type_add::<u8>())
+                        .opt_add("older", gen.type_add::<u8>())
+                        .comment("deprecated fields: old")
+                };
+                validator.build()
             }
-        }
+        };
+        assert_eq!(actual, expected.to_string());
     }
 }
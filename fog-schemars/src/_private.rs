@@ -0,0 +1,98 @@
+use fog_pack::validator::*;
+
+use crate::SchemaGenerator;
+
+/// Merge the validator for a `#[serde(flatten)]` field into the map validator
+/// of the containing struct.
+///
+/// Flattened maps contribute all of their keys, while flattened optional maps
+/// (eg. `Option<T>`) contribute their keys as optional ones. Flattened maps
+/// that accept arbitrary values (eg. `HashMap<String, T>`) make the containing
+/// map accept them as well.
+pub fn flatten(gen: &SchemaGenerator, mut map: MapValidator, validator: Validator) -> MapValidator {
+    match gen.resolve(&validator).clone() {
+        Validator::Map(v) => {
+            map.req.extend(v.req);
+            map.opt.extend(v.opt);
+            if v.values.is_some() {
+                map.values = v.values;
+            }
+        }
+        Validator::Multi(v) => {
+            for v in v.0 {
+                if let Validator::Map(v) = gen.resolve(&v).clone() {
+                    map.opt.extend(v.req);
+                    map.opt.extend(v.opt);
+                    if v.values.is_some() {
+                        map.values = v.values;
+                    }
+                }
+            }
+        }
+        Validator::Any => map.values = Some(Box::new(Validator::Any)),
+        _ => (),
+    }
+    map
+}
+
+/// Compile the regex for a `#[fog(regex = "...")]` attribute, which can be a
+/// pattern or a path to a string. An invalid pattern is reported as an error,
+/// leaving the validator without one.
+pub fn regex(gen: &mut SchemaGenerator, pattern: impl AsRef<str>) -> Option<Box<regex::Regex>> {
+    let pattern = pattern.as_ref();
+    match regex::Regex::new(pattern) {
+        Ok(regex) => Some(Box::new(regex)),
+        Err(err) => {
+            gen.report_error(format!(
+                "invalid regex `{}` in fog attribute: {}",
+                pattern, err
+            ));
+            None
+        }
+    }
+}
+
+/// The most alternatives that aliased, required fields can split a map
+/// validator into.
+const MAX_ALIAS_BRANCHES: usize = 64;
+
+/// Split each map validator into one branch per name an aliased, required
+/// field can be present under.
+///
+/// The number of branches multiplies with each aliased field, so past
+/// `MAX_ALIAS_BRANCHES` this reports an error instead.
+pub fn alias_branches(
+    gen: &mut SchemaGenerator,
+    branches: Vec<MapValidator>,
+    names: &[&str],
+    validator: Validator,
+) -> Vec<MapValidator> {
+    let count = branches.len() * names.len();
+    if count > MAX_ALIAS_BRANCHES {
+        gen.report_error(format!(
+            "aliases for the required field `{}` would split its map validator into {} \
+            alternatives, more than the limit of {}; make some aliased fields optional \
+            or drop some aliases",
+            names[0], count, MAX_ALIAS_BRANCHES
+        ));
+        return branches;
+    }
+    let mut new_branches = Vec::with_capacity(count);
+    for map in branches {
+        for &name in names {
+            new_branches.push(map.clone().req_add(name, validator.clone()));
+        }
+    }
+    new_branches
+}
+
+/// Build a validator that passes if any one of the map validators does.
+pub fn one_of(mut branches: Vec<MapValidator>) -> Validator {
+    if branches.len() == 1 {
+        return branches.pop().unwrap().build();
+    }
+    branches
+        .into_iter()
+        .fold(MultiValidator::new(), |multi, map| multi.push(map.build()))
+        .build()
+}
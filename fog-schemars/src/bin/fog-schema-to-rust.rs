@@ -0,0 +1,37 @@
+//! Print Rust types for an encoded fog-pack schema document.
+//!
+//! Usage: `fog-schema-to-rust <schema file>`, or `-` to read the schema from
+//! standard input. See [`fog_schemars::rust`] for what gets generated.
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use fog_pack::schema::NoSchema;
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: fog-schema-to-rust <schema file | ->");
+        return ExitCode::FAILURE;
+    };
+    let mut encoded = Vec::new();
+    let read = if path == "-" {
+        std::io::stdin().read_to_end(&mut encoded).map(|_| ())
+    } else {
+        std::fs::read(&path).map(|data| encoded = data)
+    };
+    if let Err(err) = read {
+        eprintln!("couldn't read {}: {}", path, err);
+        return ExitCode::FAILURE;
+    }
+    let code = NoSchema::decode_doc(encoded).and_then(|doc| fog_schemars::rust::export(&doc));
+    match code {
+        Ok(code) => {
+            print!("{}", code);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("couldn't generate types from {}: {}", path, err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::OnceLock;
+
+use fog_pack::{document::Document, schema::Schema, types::Hash};
+
+use crate::{FogSchema, SchemaGenerator};
+
+/// A schema that is generated once, the first time it's used, and can then be
+/// used anywhere from a `static`.
+///
+/// ```
+/// use fog_schemars::{FogValidate, SchemaCell, SchemaGenerator};
+///
+/// #[derive(FogValidate)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// static CONFIG_SCHEMA: SchemaCell =
+///     SchemaCell::new(|| SchemaGenerator::new::<Config>().name("Config"));
+///
+/// let schema = CONFIG_SCHEMA.schema();
+/// assert_eq!(schema.hash(), CONFIG_SCHEMA.hash());
+/// ```
+///
+/// Types implementing [`FogSchema`] can use [`SchemaCell::of`] instead.
+pub struct SchemaCell {
+    init: fn() -> SchemaGenerator,
+    inner: OnceLock<(Document, Schema)>,
+}
+
+impl SchemaCell {
+    /// Create a new cell, which will build the schema from the generator
+    /// returned by `init` on first use.
+    pub const fn new(init: fn() -> SchemaGenerator) -> Self {
+        Self {
+            init,
+            inner: OnceLock::new(),
+        }
+    }
+
+    /// Create a new cell for the schema described by `T`.
+    pub const fn of<T: FogSchema>() -> Self {
+        Self::new(T::schema_generator)
+    }
+
+    /// Get the schema, generating it if this is the first use.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics if fog-pack fails to build the schema, or to load
+    /// the schema document it built.
+    pub fn schema(&self) -> &Schema {
+        &self.get().1
+    }
+
+    /// Get the schema document, generating it if this is the first use.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics under the same conditions as
+    /// [`SchemaCell::schema`].
+    pub fn document(&self) -> &Document {
+        &self.get().0
+    }
+
+    /// Get the schema's hash, generating it if this is the first use.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics under the same conditions as
+    /// [`SchemaCell::schema`].
+    pub fn hash(&self) -> &Hash {
+        self.get().0.hash()
+    }
+
+    fn get(&self) -> &(Document, Schema) {
+        self.inner.get_or_init(|| {
+            let doc = (self.init)().build().expect("Schema should be buildable");
+            let schema = Schema::from_doc(&doc).expect("Generated schema should be loadable");
+            (doc, schema)
+        })
+    }
+}
+
+impl std::fmt::Debug for SchemaCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaCell")
+            .field("hash", &self.inner.get().map(|(doc, _)| doc.hash()))
+            .finish()
+    }
+}
@@ -0,0 +1,896 @@
+//! Structural comparison of schemas.
+//!
+//! Any change to a schema changes its hash, which on its own says nothing
+//! about whether documents made for the old schema still pass the new one.
+//! [`SchemaDiff`] walks two schemas validator by validator, following
+//! references through their type lists, and classifies each difference it
+//! finds.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug};
+
+use fog_pack::{document::Document, validator::*};
+
+use crate::schema_doc::{compress_eq, SchemaDoc};
+use crate::visit::children;
+use crate::SchemaGenerator;
+
+/// How a change affects which documents a schema accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeKind {
+    /// Doesn't change which documents pass, eg. a changed comment, or a
+    /// change to which queries are allowed.
+    Neutral,
+    /// Everything that passed before still passes, and some more may pass too.
+    Widening,
+    /// Everything that passes now also passed before, but some documents that
+    /// passed before no longer do. This is a breaking change.
+    Narrowing,
+    /// Some documents that passed before no longer pass, and some documents
+    /// that pass now wouldn't have passed before. This is a breaking change.
+    Incompatible,
+}
+
+impl ChangeKind {
+    /// Whether documents that passed the old schema may fail the new one.
+    pub fn is_breaking(self) -> bool {
+        matches!(self, ChangeKind::Narrowing | ChangeKind::Incompatible)
+    }
+
+    /// The kind of the combination of two changes.
+    pub fn combine(self, other: ChangeKind) -> ChangeKind {
+        use ChangeKind::*;
+        match (self, other) {
+            (Neutral, k) | (k, Neutral) => k,
+            (Widening, Widening) => Widening,
+            (Narrowing, Narrowing) => Narrowing,
+            _ => Incompatible,
+        }
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeKind::Neutral => "neutral",
+            ChangeKind::Widening => "widening",
+            ChangeKind::Narrowing => "narrowing",
+            ChangeKind::Incompatible => "incompatible",
+        })
+    }
+}
+
+/// A single difference between two schemas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Where the change is, eg. `doc.title.max_len` or `type "Post".tags.items`.
+    pub path: String,
+    /// How the change affects which documents pass.
+    pub kind: ChangeKind,
+    /// What changed.
+    pub description: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.kind, self.path, self.description)
+    }
+}
+
+/// Every difference between two schemas.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaDiff {
+    changes: Vec<Change>,
+}
+
+impl SchemaDiff {
+    /// Compare two schema documents.
+    pub fn new(old: &Document, new: &Document) -> fog_pack::error::Result<Self> {
+        Ok(Self::from_schema_docs(
+            &SchemaDoc::from_doc(old)?,
+            &SchemaDoc::from_doc(new)?,
+        ))
+    }
+
+    /// Build and compare the schemas from two generators.
+    pub fn from_generators(
+        old: SchemaGenerator,
+        new: SchemaGenerator,
+    ) -> fog_pack::error::Result<Self> {
+        Self::new(&old.build()?, &new.build()?)
+    }
+
+    pub(crate) fn from_schema_docs(old: &SchemaDoc, new: &SchemaDoc) -> Self {
+        let mut differ = Differ {
+            old_types: &old.types,
+            new_types: &new.types,
+            visited: BTreeSet::new(),
+            pending: Vec::new(),
+            changes: Vec::new(),
+        };
+        differ.schema(old, new);
+        Self {
+            changes: differ.changes,
+        }
+    }
+
+    /// All changes, in the order they were found.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Whether the schemas are identical.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The combined kind of all changes. Identical schemas are
+    /// [`ChangeKind::Neutral`].
+    pub fn kind(&self) -> ChangeKind {
+        self.changes
+            .iter()
+            .fold(ChangeKind::Neutral, |kind, change| {
+                kind.combine(change.kind)
+            })
+    }
+
+    /// Whether any change may make documents that passed the old schema fail
+    /// the new one.
+    pub fn is_breaking(&self) -> bool {
+        self.kind().is_breaking()
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of a key in a map validator.
+enum Key<'a> {
+    Req(&'a Validator),
+    Opt(&'a Validator),
+    /// Not a listed key, but checked by the `values` validator.
+    Extra(&'a Validator),
+    Absent,
+}
+
+impl<'a> Key<'a> {
+    fn of(map: &'a MapValidator, key: &str) -> Self {
+        if let Some(v) = map.req.get(key) {
+            Key::Req(v)
+        } else if let Some(v) = map.opt.get(key) {
+            Key::Opt(v)
+        } else if let Some(v) = &map.values {
+            Key::Extra(v)
+        } else {
+            Key::Absent
+        }
+    }
+}
+
+struct Differ<'a> {
+    old_types: &'a BTreeMap<String, Validator>,
+    new_types: &'a BTreeMap<String, Validator>,
+    // Pairs of referenced types that have been, or will be, compared.
+    visited: BTreeSet<(String, String)>,
+    pending: Vec<(String, String)>,
+    changes: Vec<Change>,
+}
+
+impl<'a> Differ<'a> {
+    fn push(&mut self, path: &str, kind: ChangeKind, description: impl Into<String>) {
+        self.changes.push(Change {
+            path: path.to_owned(),
+            kind,
+            description: description.into(),
+        });
+    }
+
+    fn schema(&mut self, old: &SchemaDoc, new: &SchemaDoc) {
+        use ChangeKind::*;
+        if old.name != new.name {
+            self.push("name", Neutral, format!("{:?} -> {:?}", old.name, new.name));
+        }
+        if old.description != new.description {
+            let description = format!("{:?} -> {:?}", old.description, new.description);
+            self.push("description", Neutral, description);
+        }
+        if old.version != new.version {
+            let description = format!("{} -> {}", old.version, new.version);
+            self.push("version", Neutral, description);
+        }
+        if old.max_regex != new.max_regex {
+            let description = format!("{} -> {}", old.max_regex, new.max_regex);
+            self.push("max_regex", Neutral, description);
+        }
+        if !compress_eq(&old.doc_compress, &new.doc_compress) {
+            let description = format!("{:?} -> {:?}", old.doc_compress, new.doc_compress);
+            self.push("doc_compress", Neutral, description);
+        }
+
+        self.validator("doc", &old.doc, &new.doc);
+
+        for (key, old_entry) in old.entries.iter() {
+            let path = format!("entry {:?}", key);
+            match new.entries.get(key) {
+                None => self.push(&path, Narrowing, "removed"),
+                Some(new_entry) => {
+                    self.validator(&path, &old_entry.entry, &new_entry.entry);
+                    if !compress_eq(&old_entry.compress, &new_entry.compress) {
+                        let description = format!(
+                            "compress {:?} -> {:?}",
+                            old_entry.compress, new_entry.compress
+                        );
+                        self.push(&path, Neutral, description);
+                    }
+                }
+            }
+        }
+        for key in new.entries.keys() {
+            if !old.entries.contains_key(key) {
+                self.push(&format!("entry {:?}", key), Widening, "added");
+            }
+        }
+
+        // Compare every pair of types that were referenced in the same spot.
+        // Comparing them here, instead of where they're referenced, means each
+        // change to a type is only reported once.
+        while let Some((old_name, new_name)) = self.pending.pop() {
+            let path = if old_name == new_name {
+                format!("type {:?}", old_name)
+            } else {
+                format!("type {:?} -> {:?}", old_name, new_name)
+            };
+            match (self.old_types.get(&old_name), self.new_types.get(&new_name)) {
+                (Some(old), Some(new)) => self.validator(&path, old, new),
+                _ => self.push(&path, Incompatible, "referenced type is missing"),
+            }
+        }
+    }
+
+    fn validator(&mut self, path: &str, old: &Validator, new: &Validator) {
+        use ChangeKind::*;
+        match (old, new) {
+            (Validator::Ref(old_name), Validator::Ref(new_name)) => {
+                if old_name != new_name {
+                    let description =
+                        format!("refers to type {:?} instead of {:?}", new_name, old_name);
+                    self.push(path, Neutral, description);
+                }
+                let pair = (old_name.clone(), new_name.clone());
+                if self.visited.insert(pair.clone()) {
+                    self.pending.push(pair);
+                }
+            }
+            (Validator::Ref(name), _) => match self.old_types.get(name) {
+                Some(old) => self.validator(path, old, new),
+                None => self.push(path, Incompatible, format!("unknown type {:?}", name)),
+            },
+            (_, Validator::Ref(name)) => match self.new_types.get(name) {
+                Some(new) => self.validator(path, old, new),
+                None => self.push(path, Incompatible, format!("unknown type {:?}", name)),
+            },
+            // Identical validators can still refer to types that changed.
+            (old, new) if old == new => self.same_refs(old),
+            (_, Validator::Any) => self.push(path, Widening, "now accepts anything"),
+            (Validator::Any, _) => {
+                let description = format!("no longer accepts anything, only {}", kind_name(new));
+                self.push(path, Narrowing, description)
+            }
+            (Validator::Multi(old), Validator::Multi(new)) => self.multi(path, old, new),
+            (_, Validator::Multi(multi)) if multi.0.contains(old) => {
+                self.push(path, Widening, "now also accepts other alternatives")
+            }
+            (Validator::Multi(multi), _) if multi.0.contains(new) => {
+                self.push(path, Narrowing, "no longer accepts other alternatives")
+            }
+            (Validator::Bool(old), Validator::Bool(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+            }
+            (Validator::Int(old), Validator::Int(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.bits(
+                    path,
+                    "bits_clr",
+                    &old.bits_clr.to_le_bytes(),
+                    &new.bits_clr.to_le_bytes(),
+                );
+                self.bits(
+                    path,
+                    "bits_set",
+                    &old.bits_set.to_le_bytes(),
+                    &new.bits_set.to_le_bytes(),
+                );
+                self.min(path, "min", old.min, old.ex_min, new.min, new.ex_min);
+                self.max(path, "max", old.max, old.ex_max, new.max, new.ex_max);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+                self.permission(path, "bit", old.bit, new.bit);
+                self.permission(path, "ord", old.ord, new.ord);
+            }
+            (Validator::F32(old), Validator::F32(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.min(path, "min", old.min, old.ex_min, new.min, new.ex_min);
+                self.max(path, "max", old.max, old.ex_max, new.max, new.ex_max);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+                self.permission(path, "ord", old.ord, new.ord);
+            }
+            (Validator::F64(old), Validator::F64(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.min(path, "min", old.min, old.ex_min, new.min, new.ex_min);
+                self.max(path, "max", old.max, old.ex_max, new.max, new.ex_max);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+                self.permission(path, "ord", old.ord, new.ord);
+            }
+            (Validator::Bin(old), Validator::Bin(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.bits(path, "bits_clr", &old.bits_clr, &new.bits_clr);
+                self.bits(path, "bits_set", &old.bits_set, &new.bits_set);
+                // Binary bounds are compared as unsigned little-endian
+                // numbers, which we don't attempt to order here.
+                if old.min != new.min || old.ex_min != new.ex_min {
+                    self.push(path, Incompatible, "min changed");
+                }
+                if old.max != new.max || old.ex_max != new.ex_max {
+                    self.push(path, Incompatible, "max changed");
+                }
+                self.min_len(path, "min_len", old.min_len, new.min_len);
+                self.max_len(path, "max_len", old.max_len, new.max_len);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+                self.permission(path, "bit", old.bit, new.bit);
+                self.permission(path, "ord", old.ord, new.ord);
+                self.permission(path, "size", old.size, new.size);
+            }
+            (Validator::Str(old), Validator::Str(new)) => self.str(path, old, new),
+            (Validator::Array(old), Validator::Array(new)) => self.array(path, old, new),
+            (Validator::Map(old), Validator::Map(new)) => self.map(path, old, new),
+            (Validator::Time(old), Validator::Time(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.min(path, "min", old.min, old.ex_min, new.min, new.ex_min);
+                self.max(path, "max", old.max, old.ex_max, new.max, new.ex_max);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+                self.permission(path, "ord", old.ord, new.ord);
+            }
+            (Validator::Hash(old), Validator::Hash(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                let link_path = format!("{}.link", path);
+                match (&old.link, &new.link) {
+                    (None, None) => (),
+                    (None, Some(_)) => {
+                        self.push(&link_path, Narrowing, "linked documents are now checked")
+                    }
+                    (Some(_), None) => self.push(
+                        &link_path,
+                        Widening,
+                        "linked documents are no longer checked",
+                    ),
+                    (Some(old), Some(new)) => self.validator(&link_path, old, new),
+                }
+                self.allowed(&format!("{}.schema", path), &old.schema, &new.schema);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+                self.permission(path, "link_ok", old.link_ok, new.link_ok);
+                self.permission(path, "schema_ok", old.schema_ok, new.schema_ok);
+            }
+            (Validator::Identity(old), Validator::Identity(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+            }
+            (Validator::StreamId(old), Validator::StreamId(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+            }
+            (Validator::LockId(old), Validator::LockId(new)) => {
+                self.comment(path, &old.comment, &new.comment);
+                self.in_list(path, &old.in_list, &new.in_list);
+                self.nin_list(path, &old.nin_list, &new.nin_list);
+                self.permission(path, "query", old.query, new.query);
+            }
+            (Validator::DataLockbox(old), Validator::DataLockbox(new)) => self.lockbox(
+                path,
+                (&old.comment, old.min_len, old.max_len, old.size),
+                (&new.comment, new.min_len, new.max_len, new.size),
+            ),
+            (Validator::IdentityLockbox(old), Validator::IdentityLockbox(new)) => self.lockbox(
+                path,
+                (&old.comment, old.min_len, old.max_len, old.size),
+                (&new.comment, new.min_len, new.max_len, new.size),
+            ),
+            (Validator::StreamLockbox(old), Validator::StreamLockbox(new)) => self.lockbox(
+                path,
+                (&old.comment, old.min_len, old.max_len, old.size),
+                (&new.comment, new.min_len, new.max_len, new.size),
+            ),
+            (Validator::LockLockbox(old), Validator::LockLockbox(new)) => self.lockbox(
+                path,
+                (&old.comment, old.min_len, old.max_len, old.size),
+                (&new.comment, new.min_len, new.max_len, new.size),
+            ),
+            (Validator::Enum(old), Validator::Enum(new)) => self.enum_set(path, old, new),
+            _ => {
+                let description = format!("changed from {} to {}", kind_name(old), kind_name(new));
+                self.push(path, Incompatible, description)
+            }
+        }
+    }
+
+    /// Queue up every type referenced by a validator that's the same in both
+    /// schemas.
+    fn same_refs(&mut self, v: &Validator) {
+        match v {
+            Validator::Ref(name) => {
+                let pair = (name.clone(), name.clone());
+                if self.visited.insert(pair.clone()) {
+                    self.pending.push(pair);
+                }
+            }
+            v => children(v).into_iter().for_each(|v| self.same_refs(v)),
+        }
+    }
+
+    fn str(&mut self, path: &str, old: &StrValidator, new: &StrValidator) {
+        use ChangeKind::*;
+        self.comment(path, &old.comment, &new.comment);
+        self.in_list(path, &old.in_list, &new.in_list);
+        self.nin_list(path, &old.nin_list, &new.nin_list);
+        match (&old.matches, &new.matches) {
+            (None, None) => (),
+            (None, Some(_)) => self.push(path, Narrowing, "now has to match a regex"),
+            (Some(_), None) => self.push(path, Widening, "no longer has to match a regex"),
+            (Some(old), Some(new)) => {
+                if old.as_str() != new.as_str() {
+                    let description = format!(
+                        "regex changed from {:?} to {:?}",
+                        old.as_str(),
+                        new.as_str()
+                    );
+                    self.push(path, Incompatible, description);
+                }
+            }
+        }
+        self.min_len(path, "min_len", old.min_len, new.min_len);
+        self.max_len(path, "max_len", old.max_len, new.max_len);
+        self.min_len(path, "min_char", old.min_char, new.min_char);
+        self.max_len(path, "max_char", old.max_char, new.max_char);
+        if old.normalize != new.normalize {
+            let description = format!(
+                "normalize changed from {:?} to {:?}",
+                old.normalize, new.normalize
+            );
+            self.push(path, Incompatible, description);
+        }
+        self.banned(path, "ban_prefix", &old.ban_prefix, &new.ban_prefix);
+        self.banned(path, "ban_suffix", &old.ban_suffix, &new.ban_suffix);
+        let old_chars: Vec<char> = old.ban_char.chars().collect();
+        let new_chars: Vec<char> = new.ban_char.chars().collect();
+        self.banned(path, "ban_char", &old_chars, &new_chars);
+        self.permission(path, "query", old.query, new.query);
+        self.permission(path, "regex", old.regex, new.regex);
+        self.permission(path, "ban", old.ban, new.ban);
+        self.permission(path, "size", old.size, new.size);
+    }
+
+    fn array(&mut self, path: &str, old: &ArrayValidator, new: &ArrayValidator) {
+        use ChangeKind::*;
+        self.comment(path, &old.comment, &new.comment);
+        // Items past the prefix are checked by `items`, so compare each spot
+        // in the array with whatever validator applies to it.
+        for i in 0..old.prefix.len().max(new.prefix.len()) {
+            let old_item = old.prefix.get(i).unwrap_or(&old.items);
+            let new_item = new.prefix.get(i).unwrap_or(&new.items);
+            self.validator(&format!("{}.prefix[{}]", path, i), old_item, new_item);
+        }
+        self.validator(&format!("{}.items", path), &old.items, &new.items);
+        for (i, contains) in new.contains.iter().enumerate() {
+            if !old.contains.contains(contains) {
+                self.push(&format!("{}.contains[{}]", path, i), Narrowing, "added");
+            }
+        }
+        for (i, contains) in old.contains.iter().enumerate() {
+            if !new.contains.contains(contains) {
+                self.push(&format!("{}.contains[{}]", path, i), Widening, "removed");
+            }
+        }
+        self.min_len(path, "min_len", old.min_len, new.min_len);
+        self.max_len(path, "max_len", old.max_len, new.max_len);
+        self.in_list(path, &old.in_list, &new.in_list);
+        self.nin_list(path, &old.nin_list, &new.nin_list);
+        match (old.unique, new.unique) {
+            (false, true) => self.push(path, Narrowing, "items now have to be unique"),
+            (true, false) => self.push(path, Widening, "items no longer have to be unique"),
+            _ => (),
+        }
+        self.permission(path, "query", old.query, new.query);
+        self.permission(path, "array", old.array, new.array);
+        self.permission(path, "contains_ok", old.contains_ok, new.contains_ok);
+        self.permission(path, "unique_ok", old.unique_ok, new.unique_ok);
+        self.permission(path, "size", old.size, new.size);
+    }
+
+    fn map(&mut self, path: &str, old: &MapValidator, new: &MapValidator) {
+        use ChangeKind::*;
+        self.comment(path, &old.comment, &new.comment);
+
+        let keys: BTreeSet<&String> = old
+            .req
+            .keys()
+            .chain(old.opt.keys())
+            .chain(new.req.keys())
+            .chain(new.opt.keys())
+            .collect();
+        for key in keys {
+            let key_path = format!("{}.{}", path, key);
+            match (Key::of(old, key), Key::of(new, key)) {
+                (Key::Req(old), Key::Req(new)) | (Key::Opt(old), Key::Opt(new)) => {
+                    self.validator(&key_path, old, new)
+                }
+                (Key::Opt(old), Key::Req(new)) | (Key::Extra(old), Key::Req(new)) => {
+                    self.push(&key_path, Narrowing, "now required");
+                    self.validator(&key_path, old, new);
+                }
+                (Key::Req(old), Key::Opt(new)) | (Key::Req(old), Key::Extra(new)) => {
+                    self.push(&key_path, Widening, "now optional");
+                    self.validator(&key_path, old, new);
+                }
+                (Key::Extra(old), Key::Opt(new)) | (Key::Opt(old), Key::Extra(new)) => {
+                    self.validator(&key_path, old, new)
+                }
+                (Key::Absent, Key::Opt(_)) => {
+                    self.push(&key_path, Widening, "optional field added")
+                }
+                (Key::Absent, Key::Req(_)) => {
+                    self.push(&key_path, Incompatible, "required field added")
+                }
+                (Key::Opt(_), Key::Absent) => {
+                    self.push(&key_path, Narrowing, "optional field removed")
+                }
+                (Key::Req(_), Key::Absent) => {
+                    self.push(&key_path, Incompatible, "required field removed")
+                }
+                // Keys that are only checked by `values` are compared below.
+                (Key::Extra(_), Key::Extra(_))
+                | (Key::Extra(_), Key::Absent)
+                | (Key::Absent, Key::Extra(_))
+                | (Key::Absent, Key::Absent) => (),
+            }
+        }
+
+        let values_path = format!("{}.values", path);
+        match (&old.values, &new.values) {
+            (None, None) => (),
+            (None, Some(_)) => self.push(&values_path, Widening, "now accepts unknown fields"),
+            (Some(_), None) => {
+                self.push(&values_path, Narrowing, "no longer accepts unknown fields")
+            }
+            (Some(old), Some(new)) => self.validator(&values_path, old, new),
+        }
+        let keys_path = format!("{}.keys", path);
+        match (&old.keys, &new.keys) {
+            (None, None) => (),
+            (None, Some(_)) => self.push(&keys_path, Narrowing, "keys are now checked"),
+            (Some(_), None) => self.push(&keys_path, Widening, "keys are no longer checked"),
+            (Some(old), Some(new)) => self.str(&keys_path, old, new),
+        }
+
+        self.min_len(path, "min_len", old.min_len, new.min_len);
+        self.max_len(path, "max_len", old.max_len, new.max_len);
+        self.in_list(path, &old.in_list, &new.in_list);
+        self.nin_list(path, &old.nin_list, &new.nin_list);
+        if old.same_len != new.same_len {
+            let description = format!(
+                "same_len changed from {:?} to {:?}",
+                old.same_len, new.same_len
+            );
+            self.push(path, Incompatible, description);
+        }
+        self.permission(path, "query", old.query, new.query);
+        self.permission(path, "size", old.size, new.size);
+        self.permission(path, "map_ok", old.map_ok, new.map_ok);
+        self.permission(path, "same_len_ok", old.same_len_ok, new.same_len_ok);
+    }
+
+    fn enum_set(&mut self, path: &str, old: &EnumValidator, new: &EnumValidator) {
+        use ChangeKind::*;
+        for (name, old_variant) in old.0.iter() {
+            let variant_path = format!("{}.{}", path, name);
+            match (old_variant, new.0.get(name)) {
+                (_, None) => self.push(&variant_path, Narrowing, "variant removed"),
+                (None, Some(None)) => (),
+                (Some(old), Some(Some(new))) => self.validator(&variant_path, old, new),
+                (None, Some(Some(_))) => {
+                    self.push(&variant_path, Incompatible, "variant now has content")
+                }
+                (Some(_), Some(None)) => {
+                    self.push(&variant_path, Incompatible, "variant no longer has content")
+                }
+            }
+        }
+        for name in new.0.keys() {
+            if !old.0.contains_key(name) {
+                self.push(&format!("{}.{}", path, name), Widening, "variant added");
+            }
+        }
+    }
+
+    fn multi(&mut self, path: &str, old: &MultiValidator, new: &MultiValidator) {
+        use ChangeKind::*;
+        let removed: Vec<_> = old.0.iter().filter(|v| !new.0.contains(v)).collect();
+        let added: Vec<_> = new.0.iter().filter(|v| !old.0.contains(v)).collect();
+        // Alternatives on both sides can still change through the types they
+        // refer to.
+        for shared in old.0.iter().filter(|v| new.0.contains(v)) {
+            self.same_refs(shared);
+        }
+        match (removed.is_empty(), added.is_empty()) {
+            (true, true) => (),
+            (true, false) => self.push(
+                path,
+                Widening,
+                format!("{} alternatives added", added.len()),
+            ),
+            (false, true) => self.push(
+                path,
+                Narrowing,
+                format!("{} alternatives removed", removed.len()),
+            ),
+            // Assume alternatives were changed in place if the count is the
+            // same, as is the case when a type inside an enum changes.
+            (false, false) if removed.len() == added.len() => {
+                for (i, (old, new)) in removed.into_iter().zip(added).enumerate() {
+                    self.validator(&format!("{}.multi[{}]", path, i), old, new);
+                }
+            }
+            (false, false) => self.push(path, Incompatible, "alternatives changed"),
+        }
+    }
+
+    fn lockbox(
+        &mut self,
+        path: &str,
+        old: (&String, u32, u32, bool),
+        new: (&String, u32, u32, bool),
+    ) {
+        self.comment(path, old.0, new.0);
+        self.min_len(path, "min_len", old.1, new.1);
+        self.max_len(path, "max_len", old.2, new.2);
+        self.permission(path, "size", old.3, new.3);
+    }
+
+    fn comment(&mut self, path: &str, old: &str, new: &str) {
+        if old != new {
+            let description = format!("comment changed from {:?} to {:?}", old, new);
+            self.push(path, ChangeKind::Neutral, description);
+        }
+    }
+
+    /// A lower bound on a length.
+    fn min_len(&mut self, path: &str, field: &str, old: u32, new: u32) {
+        if old != new {
+            let kind = if new < old {
+                ChangeKind::Widening
+            } else {
+                ChangeKind::Narrowing
+            };
+            self.push(
+                path,
+                kind,
+                format!("{} changed from {} to {}", field, old, new),
+            );
+        }
+    }
+
+    /// An upper bound on a length.
+    fn max_len(&mut self, path: &str, field: &str, old: u32, new: u32) {
+        if old != new {
+            let kind = if new > old {
+                ChangeKind::Widening
+            } else {
+                ChangeKind::Narrowing
+            };
+            self.push(
+                path,
+                kind,
+                format!("{} changed from {} to {}", field, old, new),
+            );
+        }
+    }
+
+    /// A lower bound on a value, which may be exclusive.
+    fn min<T: PartialOrd + Debug>(
+        &mut self,
+        path: &str,
+        field: &str,
+        old: T,
+        old_ex: bool,
+        new: T,
+        new_ex: bool,
+    ) {
+        if old == new && old_ex == new_ex {
+            return;
+        }
+        let kind = match new.partial_cmp(&old) {
+            Some(std::cmp::Ordering::Less) => ChangeKind::Widening,
+            Some(std::cmp::Ordering::Greater) => ChangeKind::Narrowing,
+            Some(std::cmp::Ordering::Equal) if old_ex => ChangeKind::Widening,
+            Some(std::cmp::Ordering::Equal) => ChangeKind::Narrowing,
+            None => ChangeKind::Incompatible,
+        };
+        let description = format!(
+            "{} changed from {}{:?} to {}{:?}",
+            field,
+            bound_ex(old_ex),
+            old,
+            bound_ex(new_ex),
+            new
+        );
+        self.push(path, kind, description);
+    }
+
+    /// An upper bound on a value, which may be exclusive.
+    fn max<T: PartialOrd + Debug>(
+        &mut self,
+        path: &str,
+        field: &str,
+        old: T,
+        old_ex: bool,
+        new: T,
+        new_ex: bool,
+    ) {
+        if old == new && old_ex == new_ex {
+            return;
+        }
+        let kind = match new.partial_cmp(&old) {
+            Some(std::cmp::Ordering::Greater) => ChangeKind::Widening,
+            Some(std::cmp::Ordering::Less) => ChangeKind::Narrowing,
+            Some(std::cmp::Ordering::Equal) if old_ex => ChangeKind::Widening,
+            Some(std::cmp::Ordering::Equal) => ChangeKind::Narrowing,
+            None => ChangeKind::Incompatible,
+        };
+        let description = format!(
+            "{} changed from {}{:?} to {}{:?}",
+            field,
+            bound_ex(old_ex),
+            old,
+            bound_ex(new_ex),
+            new
+        );
+        self.push(path, kind, description);
+    }
+
+    /// A list of required bits, where setting more bits is narrowing.
+    fn bits(&mut self, path: &str, field: &str, old: &[u8], new: &[u8]) {
+        let len = old.len().max(new.len());
+        let byte = |v: &[u8], i: usize| v.get(i).copied().unwrap_or(0);
+        let added = (0..len).any(|i| byte(new, i) & !byte(old, i) != 0);
+        let removed = (0..len).any(|i| byte(old, i) & !byte(new, i) != 0);
+        let kind = match (added, removed) {
+            (false, false) => return,
+            (true, false) => ChangeKind::Narrowing,
+            (false, true) => ChangeKind::Widening,
+            (true, true) => ChangeKind::Incompatible,
+        };
+        self.push(path, kind, format!("{} changed", field));
+    }
+
+    /// A list of allowed values, where an empty list allows anything.
+    fn allowed<T: PartialEq + Debug>(&mut self, path: &str, old: &[T], new: &[T]) {
+        let kind = match (old.is_empty(), new.is_empty()) {
+            (true, true) => return,
+            (false, true) => ChangeKind::Widening,
+            (true, false) => ChangeKind::Narrowing,
+            (false, false) => {
+                let added = new.iter().any(|v| !old.contains(v));
+                let removed = old.iter().any(|v| !new.contains(v));
+                match (added, removed) {
+                    (false, false) => return,
+                    (true, false) => ChangeKind::Widening,
+                    (false, true) => ChangeKind::Narrowing,
+                    (true, true) => ChangeKind::Incompatible,
+                }
+            }
+        };
+        self.push(
+            path,
+            kind,
+            format!("allowed values changed from {:?} to {:?}", old, new),
+        );
+    }
+
+    fn in_list<T: PartialEq + Debug>(&mut self, path: &str, old: &[T], new: &[T]) {
+        self.allowed(&format!("{}.in", path), old, new)
+    }
+
+    fn nin_list<T: PartialEq + Debug>(&mut self, path: &str, old: &[T], new: &[T]) {
+        self.banned(path, "nin", old, new)
+    }
+
+    /// A list of banned values.
+    fn banned<T: PartialEq + Debug>(&mut self, path: &str, field: &str, old: &[T], new: &[T]) {
+        let added = new.iter().any(|v| !old.contains(v));
+        let removed = old.iter().any(|v| !new.contains(v));
+        let kind = match (added, removed) {
+            (false, false) => return,
+            (true, false) => ChangeKind::Narrowing,
+            (false, true) => ChangeKind::Widening,
+            (true, true) => ChangeKind::Incompatible,
+        };
+        let description = format!("{} changed from {:?} to {:?}", field, old, new);
+        self.push(&format!("{}.{}", path, field), kind, description);
+    }
+
+    /// A flag that permits more queries when set. Queries aren't documents,
+    /// so these never change which documents pass.
+    fn permission(&mut self, path: &str, field: &str, old: bool, new: bool) {
+        match (old, new) {
+            (false, true) => self.push(
+                path,
+                ChangeKind::Neutral,
+                format!("{} now allowed in queries", field),
+            ),
+            (true, false) => self.push(
+                path,
+                ChangeKind::Neutral,
+                format!("{} no longer allowed in queries", field),
+            ),
+            _ => (),
+        }
+    }
+}
+
+fn bound_ex(ex: bool) -> &'static str {
+    if ex {
+        "exclusive "
+    } else {
+        ""
+    }
+}
+
+/// A short name for the kind of a validator.
+fn kind_name(v: &Validator) -> &'static str {
+    match v {
+        Validator::Null => "Null",
+        Validator::Bool(_) => "Bool",
+        Validator::Int(_) => "Int",
+        Validator::F32(_) => "F32",
+        Validator::F64(_) => "F64",
+        Validator::Bin(_) => "Bin",
+        Validator::Str(_) => "Str",
+        Validator::Array(_) => "Array",
+        Validator::Map(_) => "Map",
+        Validator::Time(_) => "Time",
+        Validator::Hash(_) => "Hash",
+        Validator::Identity(_) => "Identity",
+        Validator::StreamId(_) => "StreamId",
+        Validator::LockId(_) => "LockId",
+        Validator::DataLockbox(_) => "DataLockbox",
+        Validator::IdentityLockbox(_) => "IdentityLockbox",
+        Validator::StreamLockbox(_) => "StreamLockbox",
+        Validator::LockLockbox(_) => "LockLockbox",
+        Validator::Ref(_) => "Ref",
+        Validator::Multi(_) => "Multi",
+        Validator::Enum(_) => "Enum",
+        Validator::Any => "Any",
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+/// An error encountered while generating a schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaGenError {
+    /// An entry was added with a key that's already in use.
+    DuplicateEntry(String),
+    /// A schema was added to a [`SchemaRegistry`][crate::SchemaRegistry]
+    /// with a name that's already in use.
+    DuplicateSchema(String),
+    /// A validator name contains characters that would make it ambiguous.
+    InvalidName {
+        /// The offending name.
+        name: String,
+        /// The Rust types being generated when the name was found, outermost
+        /// first.
+        type_path: String,
+    },
+    /// Two different Rust types use the same validator name, but generate
+    /// different validators.
+    DuplicateName {
+        /// The shared validator name.
+        name: String,
+        /// The first Rust type to use the name.
+        first: String,
+        /// The Rust type that generated a different validator.
+        second: String,
+    },
+    /// Two different types ended up with the same name in the type list.
+    NameConflict(String),
+    /// A type has the same name as one imported with
+    /// [`SchemaGenerator::import_types`][crate::SchemaGenerator::import_types],
+    /// but generates a different validator.
+    ImportMismatch {
+        /// The name of the imported type.
+        name: String,
+        /// The Rust type that generated a different validator.
+        rust_type: String,
+    },
+    /// A validator allows regex queries, but the schema allows no regexes in
+    /// queries. Holds the entry or type with the validator, or "the document".
+    /// See [`SchemaGenerator::regexes`][crate::SchemaGenerator::regexes].
+    RegexNotAllowed(String),
+    /// A [`FogValidate`][crate::FogValidate] implementation reported an error
+    /// through [`SchemaGenerator::report_error`][crate::SchemaGenerator::report_error].
+    Type {
+        /// The Rust types being generated when the error was reported,
+        /// outermost first.
+        type_path: String,
+        /// The reported error.
+        message: String,
+    },
+    /// fog-pack failed to build the generated schema.
+    Build(fog_pack::error::Error),
+}
+
+impl fmt::Display for SchemaGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaGenError::DuplicateEntry(entry) => write!(
+                f,
+                "An entry table item ({:?}) was added more than once",
+                entry
+            ),
+            SchemaGenError::DuplicateSchema(name) => write!(
+                f,
+                "A schema ({:?}) was added to the registry more than once",
+                name
+            ),
+            SchemaGenError::InvalidName { name, type_path } => write!(
+                f,
+                "Validator name {:?} contains one of \",<>\" in its path or \":,<>\" in its name (in {})",
+                name, type_path
+            ),
+            SchemaGenError::DuplicateName {
+                name,
+                first,
+                second,
+            } => write!(
+                f,
+                "The types {} and {} both use the validator name {:?}, but have different validators",
+                first, second, name
+            ),
+            SchemaGenError::NameConflict(name) => {
+                write!(f, "More than one type was given the name {:?}", name)
+            }
+            SchemaGenError::ImportMismatch { name, rust_type } => write!(
+                f,
+                "The type {} generates a different validator than the imported type {:?}",
+                rust_type, name
+            ),
+            SchemaGenError::RegexNotAllowed(location) => write!(
+                f,
+                "The validator for {} allows regex queries, but the schema allows no regexes",
+                location
+            ),
+            SchemaGenError::Type { type_path, message } => write!(f, "{} (in {})", message, type_path),
+            SchemaGenError::Build(err) => write!(f, "Failed to build the schema: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SchemaGenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaGenError::Build(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<fog_pack::error::Error> for SchemaGenError {
+    fn from(err: fog_pack::error::Error) -> Self {
+        SchemaGenError::Build(err)
+    }
+}
@@ -1,29 +1,66 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
 
 use fog_pack::{
-    document::Document,
+    document::{Document, NewDocument},
     schema::{Compress, SchemaBuilder},
     types::Integer,
-    validator::Validator,
+    validator::*,
 };
 
-use crate::{FogValidate, Identifier, Name};
+use crate::naming::{self, NamingStrategy};
+use crate::passes::{self, BuildReport};
+use crate::query::{self, QueryPolicy};
+use crate::schema_doc::SchemaDoc;
+use crate::visit::children_mut;
+use crate::{FogValidate, Identifier, Name, SchemaGenError};
 
 #[derive(Clone, Debug)]
 pub struct SchemaGenerator {
     // Core Schema components
-    doc: Option<(Validator, String)>,
+    doc: Option<(Validator, String, &'static str)>,
+    doc_generate: Generate,
     max_regex: u8,
     description: Option<String>,
     name: Option<String>,
     doc_compress: Option<Compress>,
     version: Option<Integer>,
-    types: BTreeMap<String, Validator>,
+    pub(crate) types: BTreeMap<String, Validator>,
     entries: BTreeMap<String, EntryItem>,
+    imported: BTreeMap<String, Validator>,
 
-    // Name shortening stuff
-    long_names: Vec<Name>,
-    ident_tracker: BTreeMap<&'static str, IdentTracker>,
+    // Generation options
+    strict: bool,
+    naming: NamingStrategy,
+    dedup_types: bool,
+    inline_types: bool,
+    query_policy: QueryPolicy,
+    // The name suffix for types generated under an entry's own query policy,
+    // while that entry is being generated.
+    variant: Option<String>,
+    overrides: BTreeMap<&'static str, TypeOverride>,
+
+    // Error tracking: the first error reported, and the Rust types currently
+    // being generated, for reporting where an error happened.
+    error: Option<SchemaGenError>,
+    type_path: Vec<&'static str>,
+
+    // Every Rust type that's used each name in the type list, and the
+    // validators generated by the ones after the first, which must match the
+    // first one's.
+    pub(crate) type_owners: BTreeMap<String, Vec<&'static str>>,
+    name_checks: Vec<(String, &'static str, Validator)>,
+
+    // Refined validators in the type list, as the name of the type each one
+    // refines and the suffix that sets it apart.
+    pub(crate) refined: Vec<(Name, String)>,
+
+    // Types generated under an entry's own query policy, by name, along with
+    // the name of the type they're a variant of.
+    variants: BTreeMap<String, String>,
+
+    // Every name in the type list, for working out what to call them
+    pub(crate) long_names: Vec<Name>,
 }
 
 #[derive(Clone, Default, Debug)]
@@ -42,25 +79,26 @@ struct FixupState<'a> {
 impl<'a> FixupState<'a> {
     // Insert an identifier shortener at the current state (provided the
     // identifier is different)
-    fn shorten(&mut self) {
+    fn shorten(&mut self) -> Result<(), SchemaGenError> {
         if self.new_mods == self.full_mods {
-            return;
+            return Ok(());
         }
-        assert!(
-            self.map
-                .insert(
-                    Identifier {
-                        mods: self.full_mods.clone(),
-                        name: self.name
-                    },
-                    Identifier {
-                        mods: self.new_mods.clone(),
-                        name: self.name
-                    },
-                )
-                .is_none(),
-            "Shouldn't be creating more than one shortener for a unique name"
-        );
+        let full = Identifier {
+            mods: self.full_mods.clone(),
+            name: self.name,
+        };
+        let short = Identifier {
+            mods: self.new_mods.clone(),
+            name: self.name,
+        };
+        if self.map.insert(full, short).is_some() {
+            let full = Identifier {
+                mods: self.full_mods.clone(),
+                name: self.name,
+            };
+            return Err(SchemaGenError::NameConflict(full.to_string()));
+        }
+        Ok(())
     }
 }
 
@@ -77,24 +115,25 @@ impl IdentTracker {
     }
 
     // Try to shorten the names in this name tracker.
-    fn shorten(&self, state: &mut FixupState) {
+    fn shorten(&self, state: &mut FixupState) -> Result<(), SchemaGenError> {
         if self.exists {
-            state.shorten();
+            state.shorten()?;
         }
         if !self.exists && self.map.len() == 1 {
             let (k, v) = self.map.first_key_value().unwrap();
             state.full_mods.push(k);
-            v.shorten(state);
+            v.shorten(state)?;
             state.full_mods.pop();
         } else {
             for (k, v) in self.map.iter() {
                 state.full_mods.push(k);
                 state.new_mods.push(k);
-                v.shorten(state);
+                v.shorten(state)?;
                 state.full_mods.pop();
                 state.new_mods.pop();
             }
         }
+        Ok(())
     }
 }
 
@@ -103,6 +142,74 @@ struct EntryItem {
     validator: Validator,
     compress: Option<Compress>,
     type_name: String,
+    rust_type: &'static str,
+    generate: Generate,
+    query: Option<QueryPolicy>,
+}
+
+// Generates the validator for the document or an entry, returning it along
+// with its name and the Rust type it came from. These are kept so everything
+// can be generated again when an override is added.
+type Generate = fn(&mut SchemaGenerator) -> (Validator, String, &'static str);
+
+fn generate_root<T: FogValidate>(gen: &mut SchemaGenerator) -> (Validator, String, &'static str) {
+    let name = gen.name_for::<T>(false);
+    let validator = gen.generate::<T>(&name, false);
+    (
+        validator,
+        gen.variant_name(&name),
+        std::any::type_name::<T>(),
+    )
+}
+
+// A name and validator to use in place of a type's own.
+#[derive(Clone)]
+struct TypeOverride {
+    name: Name,
+    validator: Arc<dyn Fn(&mut SchemaGenerator) -> Validator + Send + Sync>,
+}
+
+impl std::fmt::Debug for TypeOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypeOverride")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+// Typed wrappers around `type_add_with`, which report an error if the type's
+// validator isn't the expected kind.
+macro_rules! refine_fns {
+    ($($(#[$attr:meta])* $fn_name:ident($variant:ident, $validator:ident, $kind:literal);)*) => {
+        $(
+            $(#[$attr])*
+            ///
+            /// Reports an error if the type's validator is any other kind. See
+            /// [`SchemaGenerator::type_add_with`] for how the refined
+            /// validator is added.
+            pub fn $fn_name<T: FogValidate>(
+                &mut self,
+                refine: impl FnOnce($validator) -> $validator,
+            ) -> Validator {
+                let mut mismatch = false;
+                let validator = self.type_add_with::<T>(|v| match v {
+                    Validator::$variant(v) => Validator::$variant(Box::new(refine(*v))),
+                    v => {
+                        mismatch = true;
+                        v
+                    }
+                });
+                if mismatch {
+                    self.report_error(format!(
+                        "{} can't be refined as a {} validator",
+                        std::any::type_name::<T>(),
+                        $kind
+                    ));
+                }
+                validator
+            }
+        )*
+    };
 }
 
 impl SchemaGenerator {
@@ -113,8 +220,24 @@ impl SchemaGenerator {
     /// the schema hash and compare the generate schema against it in a unit
     /// test, which can ensure the schema stays stable over time.
     pub fn new<T: FogValidate>() -> Self {
+        Self::new_inner::<T>(false)
+    }
+
+    /// Start generating a new "strict" schema for a document that passes the
+    /// validator for the given type.
+    ///
+    /// A strict schema leaves out all fields and enum variants marked as
+    /// `#[deprecated]`, instead of accepting them as optional. This can be used
+    /// to make a new version of a schema that only new documents will use,
+    /// while older readers keep using the permissive one.
+    pub fn new_strict<T: FogValidate>() -> Self {
+        Self::new_inner::<T>(true)
+    }
+
+    fn new_inner<T: FogValidate>(strict: bool) -> Self {
         let mut this = Self {
             doc: None,
+            doc_generate: generate_root::<T>,
             max_regex: 0,
             description: None,
             name: None,
@@ -122,13 +245,23 @@ impl SchemaGenerator {
             doc_compress: None,
             types: BTreeMap::new(),
             entries: BTreeMap::new(),
+            imported: BTreeMap::new(),
+            strict,
+            naming: NamingStrategy::default(),
+            dedup_types: false,
+            inline_types: false,
+            query_policy: QueryPolicy::default(),
+            variant: None,
+            overrides: BTreeMap::new(),
+            error: None,
+            type_path: Vec::new(),
+            type_owners: BTreeMap::new(),
+            name_checks: Vec::new(),
+            refined: Vec::new(),
+            variants: BTreeMap::new(),
             long_names: Vec::new(),
-            ident_tracker: BTreeMap::new(),
         };
-        this.doc = Some((
-            T::validator(&mut this, false),
-            T::validator_name(false).to_string(),
-        ));
+        this.doc = Some(generate_root::<T>(&mut this));
         this
     }
 
@@ -153,24 +286,202 @@ impl SchemaGenerator {
     /// Panics
     /// ------
     /// This function panics if there's already an entry validator with the
-    /// provided name.
-    pub fn entry_add<T: FogValidate>(mut self, entry: &str, compress: Option<Compress>) -> Self {
-        let validator = T::validator(&mut self, false);
-        if self
-            .entries
-            .insert(
-                entry.to_owned(),
-                EntryItem {
-                    validator,
-                    compress,
-                    type_name: T::validator_name(false).to_string(),
-                },
-            )
-            .is_some()
-        {
-            panic!("An entry table item ({:?}) is being overwritten! You probably didn't mean to do this.", entry);
+    /// provided name. Use [`SchemaGenerator::try_entry_add`] to get an error
+    /// instead.
+    pub fn entry_add<T: FogValidate>(self, entry: &str, compress: Option<Compress>) -> Self {
+        self.try_entry_add::<T>(entry, compress)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new entry type to the schema, like
+    /// [`SchemaGenerator::entry_add`], but return an error if there's already
+    /// an entry validator with the provided name.
+    pub fn try_entry_add<T: FogValidate>(
+        mut self,
+        entry: &str,
+        compress: Option<Compress>,
+    ) -> Result<Self, SchemaGenError> {
+        self.entry_insert(entry, compress, generate_root::<T>, None)?;
+        Ok(self)
+    }
+
+    /// Add a new entry type to the schema, like
+    /// [`SchemaGenerator::entry_add`], but with its own query policy. The
+    /// validators for the entry follow `query_policy` instead of the one set
+    /// with [`SchemaGenerator::query_policy`], so a type can allow queries as
+    /// an entry while staying strict in the document:
+    ///
+    /// ```
+    /// use fog_schemars::{FogValidate, QueryPolicy, SchemaGenerator};
+    ///
+    /// #[derive(FogValidate)]
+    /// struct Comment {
+    ///     text: String,
+    ///     likes: u32,
+    /// }
+    ///
+    /// #[derive(FogValidate)]
+    /// struct Post {
+    ///     pinned: Comment,
+    /// }
+    ///
+    /// let gen = SchemaGenerator::new::<Post>().entry_add_with_query::<Comment>(
+    ///     "comments",
+    ///     None,
+    ///     QueryPolicy::new().ord(true),
+    /// );
+    /// ```
+    ///
+    /// Types in the type list that the entry's policy changes get a separate
+    /// variant, named after the type plus `#` and the allowed queries, like
+    /// `Comment#query-ord`. Types the policy doesn't change keep their one
+    /// name.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics if there's already an entry validator with the
+    /// provided name. Use [`SchemaGenerator::try_entry_add_with_query`] to
+    /// get an error instead.
+    pub fn entry_add_with_query<T: FogValidate>(
+        self,
+        entry: &str,
+        compress: Option<Compress>,
+        query_policy: QueryPolicy,
+    ) -> Self {
+        self.try_entry_add_with_query::<T>(entry, compress, query_policy)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a new entry type to the schema with its own query policy, like
+    /// [`SchemaGenerator::entry_add_with_query`], but return an error if
+    /// there's already an entry validator with the provided name.
+    pub fn try_entry_add_with_query<T: FogValidate>(
+        mut self,
+        entry: &str,
+        compress: Option<Compress>,
+        query_policy: QueryPolicy,
+    ) -> Result<Self, SchemaGenError> {
+        self.entry_insert(entry, compress, generate_root::<T>, Some(query_policy))?;
+        Ok(self)
+    }
+
+    fn entry_insert(
+        &mut self,
+        entry: &str,
+        compress: Option<Compress>,
+        generate: Generate,
+        query: Option<QueryPolicy>,
+    ) -> Result<(), SchemaGenError> {
+        if self.entries.contains_key(entry) {
+            return Err(SchemaGenError::DuplicateEntry(entry.to_owned()));
+        }
+        let (validator, type_name, rust_type) = self.generate_entry(generate, query.as_ref());
+        self.entries.insert(
+            entry.to_owned(),
+            EntryItem {
+                validator,
+                compress,
+                type_name,
+                rust_type,
+                generate,
+                query,
+            },
+        );
+        self.merge_variants();
+        Ok(())
+    }
+
+    // Generate the validator for an entry, following its own query policy if
+    // it has one that differs from the schema's.
+    fn generate_entry(
+        &mut self,
+        generate: Generate,
+        query: Option<&QueryPolicy>,
+    ) -> (Validator, String, &'static str) {
+        let Some(query) = query.filter(|q| **q != self.query_policy) else {
+            return generate(self);
+        };
+        let policy = std::mem::replace(&mut self.query_policy, query.clone());
+        self.variant = Some(query.suffix());
+        let generated = generate(self);
+        self.variant = None;
+        self.query_policy = policy;
+        generated
+    }
+
+    // The name a type goes by in the type list, which has a suffix when it's
+    // generated under an entry's own query policy.
+    fn variant_name(&self, name: &Name) -> String {
+        match &self.variant {
+            Some(suffix) => format!("{}#{}", name, suffix),
+            None => name.to_string(),
+        }
+    }
+
+    // Merge every variant that turned out the same as the type it's a variant
+    // of back into that type. Variants can refer to each other, possibly in
+    // cycles, so start by assuming they all match, and drop any that don't
+    // until nothing changes.
+    fn merge_variants(&mut self) {
+        let mut merged: BTreeMap<String, String> = self
+            .variants
+            .iter()
+            .filter(|(_, base)| self.types.contains_key(*base))
+            .map(|(name, base)| (name.clone(), base.clone()))
+            .collect();
+        loop {
+            let mismatched: Vec<String> = merged
+                .iter()
+                .filter(|(name, base)| {
+                    let mut validator = self.types[*name].clone();
+                    visit_refs_mut(&mut validator, &mut |s| {
+                        if let Some(base) = merged.get(s) {
+                            *s = base.clone();
+                        }
+                    });
+                    validator != self.types[*base]
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            if mismatched.is_empty() {
+                break;
+            }
+            for name in mismatched {
+                merged.remove(&name);
+            }
+        }
+        if merged.is_empty() {
+            return;
+        }
+
+        let mut rename = |s: &mut String| {
+            if let Some(base) = merged.get(s) {
+                *s = base.clone();
+            }
+        };
+        for (name, base) in merged.iter() {
+            self.types.remove(name);
+            self.variants.remove(name);
+            self.refined
+                .retain(|(b, suffix)| format!("{}#{}", b, suffix) != *name);
+            for owner in self.type_owners.remove(name).unwrap_or_default() {
+                let owners = self.type_owners.entry(base.clone()).or_default();
+                if !owners.contains(&owner) {
+                    owners.push(owner);
+                }
+            }
+        }
+        for validator in self.types.values_mut() {
+            visit_refs_mut(validator, &mut rename);
+        }
+        for entry in self.entries.values_mut() {
+            visit_refs_mut(&mut entry.validator, &mut rename);
+            rename(&mut entry.type_name);
+        }
+        for (name, _, validator) in self.name_checks.iter_mut() {
+            visit_refs_mut(validator, &mut rename);
+            rename(name);
         }
-        self
     }
 
     /// Set the schema name. This is only used for documentation purposes.
@@ -196,16 +507,127 @@ impl SchemaGenerator {
         self.type_add_inner::<T>(true)
     }
 
+    /// Generate the full validator for a type, never a reference to it,
+    /// following any override set with [`SchemaGenerator::override_type`].
+    ///
+    /// Implementations of [`FogValidate`] that reuse another type's validator
+    /// as their own, like `Box<T>`, should call this instead of
+    /// [`FogValidate::validator`] so overrides apply to them too.
+    pub fn type_validator<T: FogValidate + ?Sized>(&mut self, opt: bool) -> Validator {
+        self.run_validator::<T>(opt)
+    }
+
+    /// Use a different name and validator for `T` than its [`FogValidate`]
+    /// implementation gives, wherever the generator is asked for `T`. This
+    /// lets an application change how a type from another crate, or one of
+    /// the built-in implementations, is validated:
+    ///
+    /// ```
+    /// # use fog_schemars::{FogValidate, Name, SchemaGenerator};
+    /// # use fog_pack::validator::TimeValidator;
+    /// #[derive(FogValidate)]
+    /// struct Event {
+    ///     at: std::time::SystemTime,
+    /// }
+    ///
+    /// let gen = SchemaGenerator::new::<Event>().override_type::<std::time::SystemTime>(
+    ///     Name::new("app", "Timestamp"),
+    ///     |_| TimeValidator::new().build(),
+    /// );
+    /// ```
+    ///
+    /// The override's validator always goes in the type list under the given
+    /// name, and is also used for optional fields. Types that forward to `T`,
+    /// like `Box<T>`, use the override's validator but keep their own name.
+    ///
+    /// Overrides can be added at any point; the document and every entry are
+    /// generated again to include it. Types without a [`FogValidate`]
+    /// implementation can't be overridden, as nothing can ask for them, but
+    /// can be given a validator with `#[fog(with = "...")]` or
+    /// `#[fog(schema_with = "...")]` instead.
+    pub fn override_type<T: FogValidate + ?Sized>(
+        mut self,
+        name: Name,
+        validator: impl Fn(&mut SchemaGenerator) -> Validator + Send + Sync + 'static,
+    ) -> Self {
+        self.overrides.insert(
+            std::any::type_name::<T>(),
+            TypeOverride {
+                name,
+                validator: Arc::new(validator),
+            },
+        );
+        self.regenerate();
+        self
+    }
+
+    /// Import the type list of an existing schema, so this schema can reuse
+    /// its types by name. Imported types are added to this schema's type
+    /// list as they are.
+    ///
+    /// Any local type that ends up with the same name as an imported one, once
+    /// the [naming strategy](SchemaGenerator::naming) has been applied, is
+    /// bound to the imported type instead of being added again. Building the
+    /// schema fails with [`SchemaGenError::ImportMismatch`] if the local type
+    /// doesn't generate exactly the same validator.
+    ///
+    /// This fails if the document isn't a schema, or if it has a type with
+    /// the same name as a previously imported type, but a different
+    /// validator.
+    pub fn import_types(mut self, schema: &Document) -> Result<Self, SchemaGenError> {
+        let schema = SchemaDoc::from_doc(schema)?;
+        for (name, validator) in schema.types {
+            match self.imported.entry(name) {
+                Entry::Vacant(e) => {
+                    e.insert(validator);
+                }
+                Entry::Occupied(e) if *e.get() != validator => {
+                    return Err(SchemaGenError::NameConflict(e.key().clone()))
+                }
+                Entry::Occupied(_) => (),
+            }
+        }
+        Ok(self)
+    }
+
+    // Throw away everything generated so far, and generate the document and
+    // entries again.
+    fn regenerate(&mut self) {
+        self.types.clear();
+        self.error = None;
+        self.type_path.clear();
+        self.type_owners.clear();
+        self.name_checks.clear();
+        self.refined.clear();
+        self.variants.clear();
+        self.long_names.clear();
+        self.doc = Some((self.doc_generate)(self));
+        for (key, mut entry) in std::mem::take(&mut self.entries) {
+            (entry.validator, entry.type_name, entry.rust_type) =
+                self.generate_entry(entry.generate, entry.query.as_ref());
+            self.entries.insert(key, entry);
+        }
+        self.merge_variants();
+    }
+
     fn type_add_inner<T: FogValidate>(&mut self, opt: bool) -> Validator {
-        let opt = opt && T::has_opt();
-        let name = T::validator_name(opt);
-        let name_str = name.to_string();
+        let opt = opt && self.has_opt_for::<T>();
+        let name = self.name_for::<T>(opt);
+        let name_str = self.variant_name(&name);
         // Immediately return a ref if it's already been loaded into the schema.
         if self.types.contains_key(&name_str) {
+            self.check_owner::<T>(&name_str, opt);
             return Validator::new_ref(name_str);
         }
 
-        if T::should_reference(opt) {
+        if self.should_reference_for::<T>(opt) {
+            if !name.is_valid() {
+                self.record_error(SchemaGenError::InvalidName {
+                    name: name_str.clone(),
+                    type_path: self.type_path_with::<T>(),
+                });
+            }
+
             // CAREFUL ORDERING: We insert our name into both maps immediately,
             // and only then do we generate the validator. In this way,
             // recursive types won't infinitely recurse - they'll stop once they
@@ -213,18 +635,380 @@ impl SchemaGenerator {
             // a placeholder validator, but that's not really a big deal.
 
             self.types.insert(name_str.clone(), Validator::Any);
-            self.ident_tracker
-                .entry(name.id.name)
-                .or_default()
-                .modify(name.id.mods.iter().copied());
-            self.long_names.push(name);
+            self.type_owners
+                .insert(name_str.clone(), vec![std::any::type_name::<T>()]);
+            if let Some(suffix) = &self.variant {
+                self.variants.insert(name_str.clone(), name.to_string());
+                self.refined.push((name.clone(), suffix.clone()));
+            }
+            // Variants and refinements may have already named the type.
+            if !self.long_names.contains(&name) {
+                self.long_names.push(name);
+            }
 
-            let validator = T::validator(self, opt);
+            let validator = self.run_validator::<T>(opt);
             self.types.insert(name_str.clone(), validator);
             Validator::new_ref(name_str)
         } else {
-            T::validator(self, opt)
+            self.run_validator::<T>(opt)
+        }
+    }
+
+    /// Create a validator for a type, then refine it with `refine`, returning
+    /// either the refined validator or a reference to it. This adds
+    /// constraints at the point where a type is used, without needing a new
+    /// type, e.g. limiting the length of one `Vec<T>` field:
+    ///
+    /// ```
+    /// # use fog_schemars::SchemaGenerator;
+    /// # use fog_pack::validator::Validator;
+    /// # fn validator(gen: &mut SchemaGenerator) -> Validator {
+    /// gen.type_add_with::<Vec<u8>>(|v| match v {
+    ///     Validator::Array(v) => v.max_len(16).build(),
+    ///     v => v,
+    /// })
+    /// # }
+    /// ```
+    ///
+    /// If the type goes in the type list and `refine` changes its validator,
+    /// the refined validator is added to the type list as well. It's named
+    /// after the type, plus `#` and part of the refined validator's hash, so
+    /// each distinct refinement gets its own name.
+    ///
+    /// The typed helpers, like [`SchemaGenerator::refine_str`], cover the
+    /// common case of refining one kind of validator.
+    pub fn type_add_with<T: FogValidate>(
+        &mut self,
+        refine: impl FnOnce(Validator) -> Validator,
+    ) -> Validator {
+        self.type_add_with_inner::<T>(false, refine)
+    }
+
+    /// Create a validator for a type, then refine it with `refine`, as with
+    /// [`SchemaGenerator::type_add_with`].
+    ///
+    /// This is specifically for when a type is in a field that has both a
+    /// default implementation and has a `skip_serializing_if` attribute.
+    pub fn type_add_opt_with<T: FogValidate>(
+        &mut self,
+        refine: impl FnOnce(Validator) -> Validator,
+    ) -> Validator {
+        self.type_add_with_inner::<T>(true, refine)
+    }
+
+    refine_fns! {
+        /// Create a validator for a type whose validator is a
+        /// [`BoolValidator`], then refine it.
+        refine_bool(Bool, BoolValidator, "boolean");
+        /// Create a validator for a type whose validator is an
+        /// [`IntValidator`], then refine it, e.g. with
+        /// `gen.refine_int::<u32>(|v| v.max(100))`.
+        refine_int(Int, IntValidator, "integer");
+        /// Create a validator for a type whose validator is an
+        /// [`F32Validator`], then refine it.
+        refine_f32(F32, F32Validator, "f32");
+        /// Create a validator for a type whose validator is an
+        /// [`F64Validator`], then refine it.
+        refine_f64(F64, F64Validator, "f64");
+        /// Create a validator for a type whose validator is a
+        /// [`BinValidator`], then refine it.
+        refine_bin(Bin, BinValidator, "binary");
+        /// Create a validator for a type whose validator is a
+        /// [`StrValidator`], then refine it, e.g. with
+        /// `gen.refine_str::<String>(|v| v.max_len(64))`.
+        refine_str(Str, StrValidator, "string");
+        /// Create a validator for a type whose validator is an
+        /// [`ArrayValidator`], then refine it, e.g. with
+        /// `gen.refine_array::<Vec<Hash>>(|v| v.max_len(16))`.
+        refine_array(Array, ArrayValidator, "array");
+        /// Create a validator for a type whose validator is a
+        /// [`MapValidator`], then refine it.
+        refine_map(Map, MapValidator, "map");
+        /// Create a validator for a type whose validator is a
+        /// [`TimeValidator`], then refine it.
+        refine_time(Time, TimeValidator, "time");
+        /// Create a validator for a type whose validator is a
+        /// [`HashValidator`], then refine it.
+        refine_hash(Hash, HashValidator, "hash");
+    }
+
+    fn type_add_with_inner<T: FogValidate>(
+        &mut self,
+        opt: bool,
+        refine: impl FnOnce(Validator) -> Validator,
+    ) -> Validator {
+        let opt = opt && self.has_opt_for::<T>();
+        let validator = self.run_validator::<T>(opt);
+        let refined = refine(validator.clone());
+        if !self.should_reference_for::<T>(opt) {
+            return refined;
+        }
+        if refined == validator {
+            return self.type_add_inner::<T>(opt);
+        }
+
+        let base = self.name_for::<T>(opt);
+        if !base.is_valid() {
+            self.record_error(SchemaGenError::InvalidName {
+                name: base.to_string(),
+                type_path: self.type_path_with::<T>(),
+            });
+        }
+        let suffix: String = match NewDocument::new(None, &refined) {
+            Ok(doc) => doc.hash().digest()[..4]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            Err(err) => {
+                self.record_error(err.into());
+                return refined;
+            }
+        };
+        let name_str = format!("{}#{}", base, suffix);
+        match self.types.entry(name_str.clone()) {
+            Entry::Occupied(e) => {
+                if *e.get() != refined {
+                    self.record_error(SchemaGenError::NameConflict(name_str.clone()));
+                }
+            }
+            Entry::Vacant(e) => {
+                e.insert(refined);
+                if !self.long_names.contains(&base) {
+                    self.long_names.push(base.clone());
+                }
+                self.refined.push((base, suffix));
+            }
+        }
+        Validator::new_ref(name_str)
+    }
+
+    // Another Rust type may use a name that's already in the type list. This
+    // is expected for types that forward to another (like `Box<T>`), but it's
+    // an error if the validators differ. The first type may still be in the
+    // middle of generating its validator, so the comparison waits until the
+    // schema is built.
+    fn check_owner<T: FogValidate>(&mut self, name: &str, opt: bool) {
+        let rust_type = std::any::type_name::<T>();
+        let owners = self.type_owners.entry(name.to_owned()).or_default();
+        if owners.contains(&rust_type) {
+            return;
         }
+        owners.push(rust_type);
+        let validator = self.run_validator::<T>(opt);
+        self.name_checks
+            .push((name.to_owned(), rust_type, validator));
+    }
+
+    // Look for any name that was used by Rust types with different
+    // validators.
+    fn find_duplicate_name(&self) -> Option<SchemaGenError> {
+        let (doc, doc_name, doc_type) = self.doc.as_ref().unwrap();
+        let entries = self
+            .entries
+            .values()
+            .map(|e| (&e.type_name, e.rust_type, &e.validator));
+        std::iter::once((doc_name, *doc_type, doc))
+            .chain(entries)
+            .filter(|(name, rust_type, _)| {
+                self.type_owners
+                    .get(*name)
+                    .is_some_and(|owners| !owners.contains(rust_type))
+            })
+            .chain(self.name_checks.iter().map(|(n, t, v)| (n, *t, v)))
+            .find(|(name, _, validator)| self.types.get(*name) != Some(*validator))
+            .map(|(name, rust_type, _)| SchemaGenError::DuplicateName {
+                name: name.clone(),
+                first: self.type_owners[name][0].to_owned(),
+                second: rust_type.to_owned(),
+            })
+    }
+
+    // Generate the validator for a document or entry type, checking its name
+    // first.
+    fn generate<T: FogValidate>(&mut self, name: &Name, opt: bool) -> Validator {
+        if !name.is_valid() {
+            self.record_error(SchemaGenError::InvalidName {
+                name: name.to_string(),
+                type_path: self.type_path_with::<T>(),
+            });
+        }
+        self.run_validator::<T>(opt)
+    }
+
+    // Call the type's validator function, tracking the type so any reported
+    // errors can say where they came from.
+    fn run_validator<T: FogValidate + ?Sized>(&mut self, opt: bool) -> Validator {
+        self.type_path.push(std::any::type_name::<T>());
+        let validator = match self.overrides.get(std::any::type_name::<T>()) {
+            Some(o) => {
+                let f = o.validator.clone();
+                f(self)
+            }
+            None => T::validator(self, opt),
+        };
+        self.type_path.pop();
+        validator
+    }
+
+    // The name, opt flag, and referencing of a type, following any override.
+    // Overridden types always go in the type list, and have no separate
+    // validator for optional fields.
+
+    fn name_for<T: FogValidate + ?Sized>(&self, opt: bool) -> Name {
+        match self.overrides.get(std::any::type_name::<T>()) {
+            Some(o) => o.name.clone(),
+            None => T::validator_name(opt),
+        }
+    }
+
+    fn has_opt_for<T: FogValidate + ?Sized>(&self) -> bool {
+        !self.overrides.contains_key(std::any::type_name::<T>()) && T::has_opt()
+    }
+
+    fn should_reference_for<T: FogValidate + ?Sized>(&self, opt: bool) -> bool {
+        self.overrides.contains_key(std::any::type_name::<T>()) || T::should_reference(opt)
+    }
+
+    fn type_path_with<T>(&self) -> String {
+        let mut path = self.type_path.join(" -> ");
+        if !path.is_empty() {
+            path.push_str(" -> ");
+        }
+        path.push_str(std::any::type_name::<T>());
+        path
+    }
+
+    fn record_error(&mut self, err: SchemaGenError) {
+        self.error.get_or_insert(err);
+    }
+
+    /// Report that a validator couldn't be generated. In general, this should
+    /// only be called by an implementation of [`FogValidate`], which should
+    /// then return a placeholder validator like [`Validator::Null`].
+    ///
+    /// The error is recorded along with the Rust types currently being
+    /// generated, and makes [`SchemaGenerator::try_build`] fail. Only the
+    /// first reported error is kept.
+    pub fn report_error(&mut self, message: impl Into<String>) {
+        self.record_error(SchemaGenError::Type {
+            type_path: self.type_path.join(" -> "),
+            message: message.into(),
+        });
+    }
+
+    /// Look up the validator a reference points to, following references
+    /// until reaching a validator that isn't one. Anything else, including a
+    /// reference to a type that isn't in the type list, is returned as-is.
+    ///
+    /// A type that's still being generated resolves to [`Validator::Any`], as
+    /// a placeholder for its real validator.
+    pub fn resolve<'a>(&'a self, mut validator: &'a Validator) -> &'a Validator {
+        while let Validator::Ref(name) = validator {
+            match self.types.get(name) {
+                Some(v) => validator = v,
+                None => break,
+            }
+        }
+        validator
+    }
+
+    /// The validator for the schema's document. This is generated in full, so
+    /// it's never a reference.
+    pub fn doc(&self) -> &Validator {
+        &self.doc.as_ref().unwrap().0
+    }
+
+    /// Iterate over every type in the type list, along with its validator.
+    ///
+    /// Types are listed under their full names, before the
+    /// [naming strategy](SchemaGenerator::naming) is applied, and in order of
+    /// name.
+    pub fn types(&self) -> impl Iterator<Item = (&str, &Validator)> {
+        self.types.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Look up a type in the type list by its full name.
+    pub fn type_get(&self, name: &str) -> Option<&Validator> {
+        self.types.get(name)
+    }
+
+    /// Look up the validator for `T` in the type list. Returns `None` if `T`
+    /// hasn't been added, or if `T` isn't put in the type list because its
+    /// [`FogValidate::should_reference`] returns false.
+    pub fn validator_for<T: FogValidate>(&self) -> Option<&Validator> {
+        let name = self.name_for::<T>(false).to_string();
+        let owners = self.type_owners.get(&name)?;
+        if owners.contains(&std::any::type_name::<T>()) {
+            self.types.get(&name)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over every entry added to the schema, in order of key, with
+    /// its validator and compression setting.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Validator, Option<&Compress>)> {
+        self.entries
+            .iter()
+            .map(|(k, v)| (k.as_str(), &v.validator, v.compress.as_ref()))
+    }
+
+    /// Whether this generator is making a strict schema, which leaves out
+    /// anything deprecated. See [`SchemaGenerator::new_strict`].
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Set which kinds of queries the built-in validators allow. See
+    /// [`QueryPolicy`] for the options; by default, no queries are allowed.
+    ///
+    /// This can be set at any point; the document and every entry are
+    /// generated again to follow it.
+    pub fn query_policy(mut self, policy: QueryPolicy) -> Self {
+        self.query_policy = policy;
+        self.regenerate();
+        self
+    }
+
+    /// The query policy validators should follow. In general, this should
+    /// only be used by implementations of [`FogValidate`], through
+    /// [`QueryPolicy::apply`]. See [`SchemaGenerator::query_policy`].
+    pub fn queries(&self) -> &QueryPolicy {
+        &self.query_policy
+    }
+
+    /// Set how types in the schema's type list are named. Defaults to
+    /// [`NamingStrategy::ShortestUnique`].
+    pub fn naming(mut self, strategy: NamingStrategy) -> Self {
+        self.naming = strategy;
+        self
+    }
+
+    /// Merge types with identical validators into one when building the
+    /// schema, rewriting references to the removed types. Names that were
+    /// merged away are listed in the report from
+    /// [`SchemaGenerator::build_with_report`]. Defaults to `false`.
+    ///
+    /// The merged types keep whichever name sorts first, so adding or
+    /// removing an identical type can rename others. Types from
+    /// [`SchemaGenerator::import_types`] are never merged away, and local
+    /// types identical to one are merged into it.
+    pub fn dedup_types(mut self, dedup: bool) -> Self {
+        self.dedup_types = dedup;
+        self
+    }
+
+    /// When building the schema, replace references to types that are only
+    /// used once, and aren't recursive, with the type's validator, and drop
+    /// any types that nothing uses. Both are listed in the report from
+    /// [`SchemaGenerator::build_with_report`]. This is opt-in: it defaults to
+    /// `false`, which keeps every type named in the type list.
+    ///
+    /// This runs after [`SchemaGenerator::dedup_types`], so merged types
+    /// count as used by everything that used any of them.
+    pub fn inline_types(mut self, inline: bool) -> Self {
+        self.inline_types = inline;
+        self
     }
 
     /// Set the schema version. This is only used for documentation purposes.
@@ -233,88 +1017,171 @@ impl SchemaGenerator {
         self
     }
 
-    /// Set the maximum number of regexes allowed in a query.
+    /// Set the maximum number of regexes allowed in a query. If this is 0, the
+    /// default, building the schema fails with
+    /// [`SchemaGenError::RegexNotAllowed`] if any validator allows regex
+    /// queries.
     pub fn regexes(mut self, max_regex: u8) -> Self {
         self.max_regex = max_regex;
         self
     }
 
+    /// Build the schema document.
+    ///
+    /// Every type added while generating gets its own entry in the type list,
+    /// unless the opt-in passes [`SchemaGenerator::dedup_types`] or
+    /// [`SchemaGenerator::inline_types`] are enabled.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics if an error was found while generating the
+    /// schema, like an invalid validator name or an error reported with
+    /// [`SchemaGenerator::report_error`]. Use [`SchemaGenerator::try_build`]
+    /// to get these as errors instead.
     pub fn build(self) -> fog_pack::error::Result<Document> {
-        // We're ready to build. Begin by shortening identifiers as much as we can.
-        let mut ident_fixups = HashMap::new();
-        for (k, v) in self.ident_tracker.iter() {
-            let mut state = FixupState {
-                map: &mut ident_fixups,
-                full_mods: Vec::new(),
-                new_mods: Vec::new(),
-                name: k,
-            };
-            v.shorten(&mut state);
+        match self.try_build() {
+            Ok(doc) => Ok(doc),
+            Err(SchemaGenError::Build(err)) => Err(err),
+            Err(err) => panic!("{}", err),
         }
-        // With the identifiers shortened, let's try shortening the names
-        let fixups: BTreeMap<String, String> = self
-            .long_names
-            .into_iter()
-            .filter_map(|name| {
-                let new_name = name.try_shorten(&ident_fixups);
-                if new_name != name {
-                    Some((name.to_string(), new_name.to_string()))
-                } else {
-                    None
+    }
+
+    /// Build the schema document, returning an error if anything went wrong
+    /// while generating it.
+    pub fn try_build(self) -> Result<Document, SchemaGenError> {
+        self.build_with_report().map(|(doc, _)| doc)
+    }
+
+    /// Build the schema document, like [`SchemaGenerator::try_build`], and
+    /// also report what the optional passes,
+    /// [`SchemaGenerator::dedup_types`] and
+    /// [`SchemaGenerator::inline_types`], changed.
+    pub fn build_with_report(self) -> Result<(Document, BuildReport), SchemaGenError> {
+        self.check()?;
+        let fixups = naming_fixups(&self.naming, &self.long_names, &self.types, &self.refined)?;
+        self.build_with_fixups(&fixups)
+    }
+
+    // Fail if anything went wrong while generating the schema.
+    pub(crate) fn check(&self) -> Result<(), SchemaGenError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        if let Some(err) = self.find_duplicate_name() {
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    // Build the schema, renaming each type in the type list as given.
+    pub(crate) fn build_with_fixups(
+        self,
+        fixups: &BTreeMap<String, String>,
+    ) -> Result<(Document, BuildReport), SchemaGenError> {
+        let rename = |v: &mut Validator| {
+            visit_refs_mut(v, &mut |s| {
+                if let Some(new_s) = fixups.get(s) {
+                    *s = new_s.clone();
                 }
             })
-            .collect();
+        };
 
-        // Function to recursively check for references and perform the renaming
-        // as needed. Most of this is to visit every validator in the tree; it's
-        // only the `Validator::Ref` case that needs changing.
-        fn fixup(fixups: &BTreeMap<String, String>, v: &mut Validator) {
-            match v {
-                Validator::Ref(s) => {
-                    if let Some(new_s) = fixups.get(s) {
-                        *s = new_s.clone();
-                    }
-                }
-                Validator::Array(v) => {
-                    v.contains.iter_mut().for_each(|v| fixup(fixups, v));
-                    v.prefix.iter_mut().for_each(|v| fixup(fixups, v));
-                    fixup(fixups, &mut v.items);
-                }
-                Validator::Map(v) => {
-                    v.req.values_mut().for_each(|v| fixup(fixups, v));
-                    v.opt.values_mut().for_each(|v| fixup(fixups, v));
-                    if let Some(v) = &mut v.values {
-                        fixup(fixups, v)
-                    }
+        // Rename the types. Some strategies can give two types the same name,
+        // which is only fine if they're identical.
+        let mut types = BTreeMap::new();
+        let mut owners = BTreeMap::new();
+        for (k, mut v) in self.types {
+            rename(&mut v);
+            let owner = self.type_owners.get(&k).map_or("", |o| o[0]);
+            let name = fixups.get(&k).cloned().unwrap_or(k);
+            match types.entry(name) {
+                Entry::Vacant(e) => {
+                    owners.insert(e.key().clone(), owner);
+                    e.insert(v);
                 }
-                Validator::Hash(v) => {
-                    if let Some(v) = &mut v.link {
-                        fixup(fixups, v)
-                    }
+                Entry::Occupied(e) if *e.get() != v => {
+                    return Err(SchemaGenError::NameConflict(e.key().clone()))
                 }
-                Validator::Enum(v) => {
-                    v.0.values_mut().for_each(|v| {
-                        if let Some(v) = v {
-                            fixup(fixups, v)
-                        }
-                    });
+                Entry::Occupied(_) => (),
+            }
+        }
+
+        // Local types with the same name as an imported one are bound to it,
+        // and must match it exactly.
+        let imported: BTreeSet<String> = self.imported.keys().cloned().collect();
+        for (name, imported) in self.imported {
+            match types.entry(name) {
+                Entry::Vacant(e) => {
+                    e.insert(imported);
                 }
-                Validator::Multi(v) => {
-                    v.0.iter_mut().for_each(|v| fixup(fixups, v));
+                Entry::Occupied(e) if *e.get() != imported => {
+                    return Err(SchemaGenError::ImportMismatch {
+                        rust_type: owners[e.key()].to_owned(),
+                        name: e.key().clone(),
+                    })
                 }
-
-                _ => (),
+                Entry::Occupied(_) => (),
             }
         }
 
-        // If the document's validator is in our type list, just use that instead of duplicating it.
-        // We have to check if the name must be shortened too.
-        let (mut doc, doc_id) = self.doc.unwrap();
-        if self.types.contains_key(&doc_id) {
+        // If the document's validator is in our type list, just use that
+        // instead of duplicating it.
+        let (mut doc, doc_id, _) = self.doc.unwrap();
+        if self.type_owners.contains_key(&doc_id) {
             doc = Validator::new_ref(doc_id);
         }
-        // Perform the name shortening
-        fixup(&fixups, &mut doc);
+        rename(&mut doc);
+        let mut entries: Vec<(String, Validator, Option<Compress>)> = self
+            .entries
+            .into_iter()
+            .map(|(k, mut v)| {
+                // If the entry's validator is in our type list, just use that
+                // instead of duplicating it.
+                if self.type_owners.contains_key(&v.type_name) {
+                    v.validator = Validator::new_ref(v.type_name);
+                }
+                rename(&mut v.validator);
+                (k, v.validator, v.compress)
+            })
+            .collect();
+
+        // Run the optional passes over the finished type list.
+        let mut report = BuildReport::default();
+        if self.dedup_types {
+            report.merged = passes::find_duplicates(&types, &imported);
+            types.retain(|k, _| !report.merged.contains_key(k));
+            let validators = std::iter::once(&mut doc)
+                .chain(entries.iter_mut().map(|(_, v, _)| v))
+                .chain(types.values_mut());
+            passes::rename_refs(&report.merged, validators);
+        }
+        if self.inline_types {
+            let roots = std::iter::once(&mut doc)
+                .chain(entries.iter_mut().map(|(_, v, _)| v))
+                .collect();
+            passes::inline_types(roots, &mut types, &mut report);
+        }
+        let roots = std::iter::once(&mut doc)
+            .chain(entries.iter_mut().map(|(_, v, _)| v))
+            .collect();
+        passes::splice_multis(roots, &mut types);
+        report.types = types.keys().cloned().collect();
+
+        // Regex queries need the schema to allow regexes.
+        if self.max_regex == 0 {
+            let roots = std::iter::once(("the document".to_owned(), &doc))
+                .chain(
+                    entries
+                        .iter()
+                        .map(|(k, v, _)| (format!("entry {:?}", k), v)),
+                )
+                .chain(types.iter().map(|(k, v)| (format!("type {:?}", k), v)));
+            for (location, validator) in roots {
+                if query::allows_regex(validator) {
+                    return Err(SchemaGenError::RegexNotAllowed(location));
+                }
+            }
+        }
 
         // Build the schema
         let mut builder = SchemaBuilder::new(doc).regexes(self.max_regex);
@@ -330,25 +1197,89 @@ impl SchemaGenerator {
         if let Some(v) = self.version {
             builder = builder.version(v);
         }
-        for (k, mut v) in self.entries {
-            // If the entry's validator is in our type list, just use that instead of duplicating it.
-            if self.types.contains_key(&v.type_name) {
-                v.validator = Validator::new_ref(v.type_name);
-            }
-            // Perform the name shortening
-            fixup(&fixups, &mut v.validator);
-            builder = builder.entry_add(&k, v.validator, v.compress);
+        for (k, v, compress) in entries {
+            builder = builder.entry_add(&k, v, compress);
         }
-        for (k, mut v) in self.types {
-            // Perform the name shortening
-            let name = if let Some(name) = fixups.get(&k) {
-                name
-            } else {
-                &k
-            };
-            fixup(&fixups, &mut v);
-            builder = builder.type_add(name, v);
+        for (k, v) in types {
+            builder = builder.type_add(&k, v);
         }
-        builder.build()
+        Ok((builder.build()?, report))
+    }
+}
+
+/// Work out what each type in the type list will be called, returning a map
+/// of full names to new ones.
+pub(crate) fn naming_fixups(
+    naming: &NamingStrategy,
+    long_names: &[Name],
+    types: &BTreeMap<String, Validator>,
+    refined: &[(Name, String)],
+) -> Result<BTreeMap<String, String>, SchemaGenError> {
+    let mut fixups = match naming {
+        NamingStrategy::ShortestUnique => shortest_names(long_names)?,
+        NamingStrategy::FullPath => BTreeMap::new(),
+        NamingStrategy::BareName => naming::bare_names(long_names)?,
+        NamingStrategy::StructuralHash => naming::structural_names(types)?,
+        NamingStrategy::Custom(f) => long_names
+            .iter()
+            .map(|name| (name.to_string(), f(name)))
+            .collect(),
+    };
+    // Refined types follow the names of the types they refine. Structural
+    // hashes already cover every type.
+    if !matches!(naming, NamingStrategy::StructuralHash) {
+        for (base, suffix) in refined.iter() {
+            let base = base.to_string();
+            let new_base = fixups.get(&base).unwrap_or(&base);
+            let new_name = format!("{}#{}", new_base, suffix);
+            fixups.insert(format!("{}#{}", base, suffix), new_name);
+        }
+    }
+    Ok(fixups)
+}
+
+// Shorten the module paths of every name in the type list as much as we can
+// while keeping them unique.
+fn shortest_names(long_names: &[Name]) -> Result<BTreeMap<String, String>, SchemaGenError> {
+    let mut ident_tracker: BTreeMap<&'static str, IdentTracker> = BTreeMap::new();
+    for name in long_names {
+        ident_tracker
+            .entry(name.id.name)
+            .or_default()
+            .modify(name.id.mods.iter().copied());
+    }
+
+    // Begin by shortening identifiers as much as we can.
+    let mut ident_fixups = HashMap::new();
+    for (k, v) in ident_tracker.iter() {
+        let mut state = FixupState {
+            map: &mut ident_fixups,
+            full_mods: Vec::new(),
+            new_mods: Vec::new(),
+            name: k,
+        };
+        v.shorten(&mut state)?;
+    }
+    // With the identifiers shortened, let's try shortening the names
+    Ok(long_names
+        .iter()
+        .filter_map(|name| {
+            let new_name = name.try_shorten(&ident_fixups);
+            if new_name != *name {
+                Some((name.to_string(), new_name.to_string()))
+            } else {
+                None
+            }
+        })
+        .collect())
+}
+
+/// Recursively visit every reference in a validator.
+pub(crate) fn visit_refs_mut(v: &mut Validator, f: &mut impl FnMut(&mut String)) {
+    match v {
+        Validator::Ref(s) => f(s),
+        v => children_mut(v)
+            .into_iter()
+            .for_each(|v| visit_refs_mut(v, f)),
     }
 }
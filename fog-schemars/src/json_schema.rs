@@ -0,0 +1,486 @@
+//! Export of schemas to [JSON Schema 2020-12](https://json-schema.org/draft/2020-12/schema).
+//!
+//! [`export`] turns a schema document into a JSON Schema that checks the JSON
+//! form of documents and entries, as `serde_json` would write them. The
+//! document's validator becomes the root schema, each type in the type list
+//! becomes a schema in `$defs`, and each entry's validator goes under
+//! `x-fog-entries`, keyed by entry name. References become `$ref`s into
+//! `$defs`, and comments become descriptions.
+//!
+//! JSON can't express everything a fog-pack validator checks, so some
+//! mappings lose information. Apart from [`MultiValidator`], a lossy mapping
+//! only ever drops a check, so anything that passes the fog-pack schema also
+//! passes the JSON Schema:
+//!
+//! - `Bin` is an array of integers from 0 to 255, as `serde_json` writes
+//!   bytes. Its length and `in`/`nin` lists are kept, but its bit fields and
+//!   numeric bounds are dropped.
+//! - `Str` lengths are in bytes, while JSON Schema counts characters, so
+//!   `max_len` only limits characters to the same number, and `min_len` to a
+//!   quarter of it. Character lengths are kept as they are, and `matches`
+//!   becomes `pattern`, assuming the regex means the same in both syntaxes.
+//!   The banned prefixes, suffixes, and characters are dropped, as are the
+//!   `in`/`nin` lists when the string is normalized first.
+//! - `Array` and `Map` drop their `in`/`nin` lists, and `Map` drops
+//!   `same_len`.
+//! - `Hash`, `Identity`, `StreamId`, and `LockId` are objects with a single
+//!   `Hash`, `Identity`, `StreamId`, or `LockId` key holding a base58 string.
+//!   Their `in`/`nin` lists are kept, but the documents a hash links to aren't
+//!   checked, nor are their schemas.
+//! - `Time` is an object with a single `Time` key, holding the timestamp's
+//!   `std`, `secs`, and `nanos` integers. Its bounds and `in`/`nin` lists are
+//!   dropped.
+//! - Lockboxes are objects with a single key named after the lockbox type,
+//!   holding a base64 string. Their lengths are dropped.
+//! - Floating point bounds and values that aren't finite are dropped.
+//! - Query permissions, the schema's version, and compression settings have
+//!   nothing to map to, and are left out.
+//!
+//! A [`MultiValidator`] becomes a `oneOf`. fog-pack accepts a value if any of
+//! its validators do, while `oneOf` needs exactly one to, so alternatives
+//! that accept the same values should be avoided. An [`EnumValidator`] is a
+//! `oneOf` too, but its alternatives never overlap: each is either the
+//! variant's name as a constant string, or an object with the variant's name
+//! as its single key.
+
+use std::fmt::Write as _;
+
+use fog_pack::{document::Document, validator::*};
+
+use crate::schema_doc::SchemaDoc;
+
+/// Export a schema as a JSON Schema 2020-12 document, returning it as JSON
+/// text. See the [module documentation](self) for how validators are mapped.
+///
+/// ```
+/// use fog_schemars::{json_schema, FogValidate, SchemaGenerator};
+///
+/// #[derive(FogValidate)]
+/// struct Post {
+///     title: String,
+/// }
+///
+/// let schema = SchemaGenerator::new::<Post>().build().unwrap();
+/// let json = json_schema::export(&schema).unwrap();
+/// assert!(json.contains(r#""required": ["#));
+/// ```
+///
+/// This fails if the document isn't a schema.
+pub fn export(schema: &Document) -> fog_pack::error::Result<String> {
+    let schema = SchemaDoc::from_doc(schema)?;
+
+    let mut root = vec![(
+        "$schema".to_owned(),
+        string("https://json-schema.org/draft/2020-12/schema"),
+    )];
+    if !schema.name.is_empty() {
+        root.push(("title".to_owned(), string(&schema.name)));
+    }
+    let mut doc = match validator(&schema.doc) {
+        Json::Object(doc) => doc,
+        _ => Vec::new(),
+    };
+    if !schema.description.is_empty() {
+        doc.retain(|(k, _)| k != "description");
+        root.push(("description".to_owned(), string(&schema.description)));
+    }
+    root.extend(doc);
+    if !schema.types.is_empty() {
+        let types = schema
+            .types
+            .iter()
+            .map(|(name, v)| (name.clone(), validator(v)))
+            .collect();
+        root.push(("$defs".to_owned(), Json::Object(types)));
+    }
+    if !schema.entries.is_empty() {
+        let entries = schema
+            .entries
+            .iter()
+            .map(|(key, entry)| (key.clone(), validator(&entry.entry)))
+            .collect();
+        root.push(("x-fog-entries".to_owned(), Json::Object(entries)));
+    }
+
+    let mut out = String::new();
+    Json::Object(root).write(&mut out, 0);
+    out.push('\n');
+    Ok(out)
+}
+
+/// A JSON value, with object keys kept in order.
+enum Json {
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn write(&self, out: &mut String, indent: usize) {
+        match self {
+            Json::Bool(b) => write!(out, "{}", b).unwrap(),
+            Json::Number(n) => out.push_str(n),
+            Json::String(s) => write_str(out, s),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Object(items) if items.is_empty() => out.push_str("{}"),
+            // Arrays of plain values stay on one line.
+            Json::Array(items)
+                if items
+                    .iter()
+                    .all(|v| !matches!(v, Json::Array(_) | Json::Object(_))) =>
+            {
+                out.push('[');
+                for (i, v) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    v.write(out, indent);
+                }
+                out.push(']');
+            }
+            Json::Array(items) => {
+                out.push('[');
+                for (i, v) in items.iter().enumerate() {
+                    out.push_str(if i > 0 { ",\n" } else { "\n" });
+                    write!(out, "{:width$}", "", width = (indent + 1) * 2).unwrap();
+                    v.write(out, indent + 1);
+                }
+                write!(out, "\n{:width$}]", "", width = indent * 2).unwrap();
+            }
+            Json::Object(items) => {
+                out.push('{');
+                for (i, (k, v)) in items.iter().enumerate() {
+                    out.push_str(if i > 0 { ",\n" } else { "\n" });
+                    write!(out, "{:width$}", "", width = (indent + 1) * 2).unwrap();
+                    write_str(out, k);
+                    out.push_str(": ");
+                    v.write(out, indent + 1);
+                }
+                write!(out, "\n{:width$}}}", "", width = indent * 2).unwrap();
+            }
+        }
+    }
+}
+
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn string(s: &str) -> Json {
+    Json::String(s.to_owned())
+}
+
+fn number(n: impl ToString) -> Json {
+    Json::Number(n.to_string())
+}
+
+/// A JSON Schema object, built up one keyword at a time.
+#[derive(Default)]
+struct Schema(Vec<(String, Json)>);
+
+impl Schema {
+    fn of_type(kind: &str) -> Self {
+        let mut schema = Self::default();
+        schema.set("type", string(kind));
+        schema
+    }
+
+    fn set(&mut self, keyword: &str, value: Json) {
+        self.0.push((keyword.to_owned(), value));
+    }
+
+    fn comment(&mut self, comment: &str) {
+        if !comment.is_empty() {
+            self.set("description", string(comment));
+        }
+    }
+
+    /// Set `enum` and `not` from `in` and `nin` lists.
+    fn lists<T>(&mut self, in_list: &[T], nin_list: &[T], f: impl Fn(&T) -> Option<Json>) {
+        let in_list: Vec<Json> = in_list.iter().filter_map(&f).collect();
+        let nin_list: Vec<Json> = nin_list.iter().filter_map(&f).collect();
+        if !in_list.is_empty() {
+            self.set("enum", Json::Array(in_list));
+        }
+        if !nin_list.is_empty() {
+            let not = vec![("enum".to_owned(), Json::Array(nin_list))];
+            self.set("not", Json::Object(not));
+        }
+    }
+
+    fn bounds(&mut self, min: Option<Json>, ex_min: bool, max: Option<Json>, ex_max: bool) {
+        if let Some(min) = min {
+            self.set(
+                if ex_min {
+                    "exclusiveMinimum"
+                } else {
+                    "minimum"
+                },
+                min,
+            );
+        }
+        if let Some(max) = max {
+            self.set(
+                if ex_max {
+                    "exclusiveMaximum"
+                } else {
+                    "maximum"
+                },
+                max,
+            );
+        }
+    }
+
+    fn len(&mut self, min_keyword: &str, min: u32, max_keyword: &str, max: u32) {
+        if min != 0 {
+            self.set(min_keyword, number(min));
+        }
+        if max != u32::MAX {
+            self.set(max_keyword, number(max));
+        }
+    }
+
+    fn build(self) -> Json {
+        Json::Object(self.0)
+    }
+}
+
+/// An object with a single required key, the way `serde_json` writes fog-pack
+/// types and enum variants with values.
+fn tagged(tag: &str, value: Json, comment: &str) -> Json {
+    let mut schema = Schema::of_type("object");
+    schema.comment(comment);
+    schema.set("properties", Json::Object(vec![(tag.to_owned(), value)]));
+    schema.set("required", Json::Array(vec![string(tag)]));
+    schema.set("additionalProperties", Json::Bool(false));
+    schema.build()
+}
+
+fn base58_id<T>(
+    tag: &str,
+    comment: &str,
+    in_list: &[T],
+    nin_list: &[T],
+    f: impl Fn(&T) -> String,
+) -> Json {
+    let mut inner = Schema::of_type("string");
+    inner.lists(in_list, nin_list, |v| Some(Json::String(f(v))));
+    tagged(tag, inner.build(), comment)
+}
+
+fn lockbox(tag: &str, comment: &str) -> Json {
+    let mut inner = Schema::of_type("string");
+    inner.set("contentEncoding", string("base64"));
+    tagged(tag, inner.build(), comment)
+}
+
+fn float(f: f64) -> Option<Json> {
+    f.is_finite().then(|| number(f))
+}
+
+fn bytes(b: &[u8]) -> Json {
+    Json::Array(b.iter().map(number).collect())
+}
+
+fn validator(v: &Validator) -> Json {
+    match v {
+        Validator::Null => Schema::of_type("null").build(),
+        Validator::Any => Json::Object(Vec::new()),
+        Validator::Ref(name) => {
+            let mut schema = Schema::default();
+            schema.set("$ref", Json::String(def_ref(name)));
+            schema.build()
+        }
+        Validator::Bool(v) => {
+            let mut schema = Schema::of_type("boolean");
+            schema.comment(&v.comment);
+            schema.lists(&v.in_list, &v.nin_list, |v| Some(Json::Bool(*v)));
+            schema.build()
+        }
+        Validator::Int(v) => {
+            let d = IntValidator::default();
+            let mut schema = Schema::of_type("integer");
+            schema.comment(&v.comment);
+            schema.bounds(
+                (v.min != d.min).then(|| number(v.min)),
+                v.ex_min,
+                (v.max != d.max).then(|| number(v.max)),
+                v.ex_max,
+            );
+            schema.lists(&v.in_list, &v.nin_list, |v| Some(number(v)));
+            schema.build()
+        }
+        Validator::F32(v) => {
+            let mut schema = Schema::of_type("number");
+            schema.comment(&v.comment);
+            schema.bounds(float(v.min as f64), v.ex_min, float(v.max as f64), v.ex_max);
+            schema.lists(&v.in_list, &v.nin_list, |v| float(*v as f64));
+            schema.build()
+        }
+        Validator::F64(v) => {
+            let mut schema = Schema::of_type("number");
+            schema.comment(&v.comment);
+            schema.bounds(float(v.min), v.ex_min, float(v.max), v.ex_max);
+            schema.lists(&v.in_list, &v.nin_list, |v| float(*v));
+            schema.build()
+        }
+        Validator::Bin(v) => {
+            let mut schema = Schema::of_type("array");
+            schema.comment(&v.comment);
+            let mut byte = Schema::of_type("integer");
+            byte.set("minimum", number(0));
+            byte.set("maximum", number(255));
+            schema.set("items", byte.build());
+            schema.len("minItems", v.min_len, "maxItems", v.max_len);
+            schema.lists(&v.in_list, &v.nin_list, |v| Some(bytes(v)));
+            schema.build()
+        }
+        Validator::Str(v) => {
+            let mut schema = Schema::of_type("string");
+            schema.comment(&v.comment);
+            // Each character takes 1 to 4 bytes.
+            let min = v.min_char.max(v.min_len.div_ceil(4));
+            let max = v.max_char.min(v.max_len);
+            schema.len("minLength", min, "maxLength", max);
+            if let Some(regex) = &v.matches {
+                schema.set("pattern", string(regex.as_str()));
+            }
+            if matches!(v.normalize, Normalize::None) {
+                schema.lists(&v.in_list, &v.nin_list, |v| Some(string(v)));
+            }
+            schema.build()
+        }
+        Validator::Array(v) => {
+            let mut schema = Schema::of_type("array");
+            schema.comment(&v.comment);
+            if !v.prefix.is_empty() {
+                let prefix = v.prefix.iter().map(validator).collect();
+                schema.set("prefixItems", Json::Array(prefix));
+            }
+            if *v.items != Validator::Any {
+                schema.set("items", validator(&v.items));
+            }
+            match v.contains.as_slice() {
+                [] => (),
+                [contains] => schema.set("contains", validator(contains)),
+                contains => {
+                    let all = contains
+                        .iter()
+                        .map(|v| Json::Object(vec![("contains".to_owned(), validator(v))]))
+                        .collect();
+                    schema.set("allOf", Json::Array(all));
+                }
+            }
+            schema.len("minItems", v.min_len, "maxItems", v.max_len);
+            if v.unique {
+                schema.set("uniqueItems", Json::Bool(true));
+            }
+            schema.build()
+        }
+        Validator::Map(v) => {
+            let mut schema = Schema::of_type("object");
+            schema.comment(&v.comment);
+            let properties: Vec<(String, Json)> = v
+                .req
+                .iter()
+                .chain(v.opt.iter())
+                .map(|(k, v)| (k.clone(), validator(v)))
+                .collect();
+            if !properties.is_empty() {
+                schema.set("properties", Json::Object(properties));
+            }
+            if !v.req.is_empty() {
+                let required = v.req.keys().map(|k| string(k)).collect();
+                schema.set("required", Json::Array(required));
+            }
+            match &v.values {
+                Some(values) => schema.set("additionalProperties", validator(values)),
+                None => schema.set("additionalProperties", Json::Bool(false)),
+            }
+            if let Some(keys) = &v.keys {
+                schema.set("propertyNames", validator(&Validator::Str(keys.clone())));
+            }
+            schema.len("minProperties", v.min_len, "maxProperties", v.max_len);
+            schema.build()
+        }
+        Validator::Time(v) => {
+            let mut inner = Schema::of_type("object");
+            let fields = ["std", "secs", "nanos"];
+            let properties = fields
+                .iter()
+                .map(|f| (f.to_string(), Schema::of_type("integer").build()))
+                .collect();
+            inner.set("properties", Json::Object(properties));
+            inner.set("required", Json::Array(fields.map(string).into()));
+            tagged("Time", inner.build(), &v.comment)
+        }
+        Validator::Hash(v) => base58_id("Hash", &v.comment, &v.in_list, &v.nin_list, |v| {
+            v.to_base58()
+        }),
+        Validator::Identity(v) => base58_id("Identity", &v.comment, &v.in_list, &v.nin_list, |v| {
+            v.to_base58()
+        }),
+        Validator::StreamId(v) => base58_id("StreamId", &v.comment, &v.in_list, &v.nin_list, |v| {
+            v.to_base58()
+        }),
+        Validator::LockId(v) => base58_id("LockId", &v.comment, &v.in_list, &v.nin_list, |v| {
+            v.to_base58()
+        }),
+        Validator::DataLockbox(v) => lockbox("DataLockbox", &v.comment),
+        Validator::IdentityLockbox(v) => lockbox("IdentityLockbox", &v.comment),
+        Validator::StreamLockbox(v) => lockbox("StreamLockbox", &v.comment),
+        Validator::LockLockbox(v) => lockbox("LockLockbox", &v.comment),
+        Validator::Multi(v) => {
+            let mut schema = Schema::default();
+            schema.set("oneOf", Json::Array(v.iter().map(validator).collect()));
+            schema.build()
+        }
+        Validator::Enum(v) => {
+            let variants = v
+                .iter()
+                .map(|(name, v)| match v {
+                    Some(v) => tagged(name, validator(v), ""),
+                    None => Json::Object(vec![("const".to_owned(), string(name))]),
+                })
+                .collect();
+            let mut schema = Schema::default();
+            schema.set("oneOf", Json::Array(variants));
+            schema.build()
+        }
+    }
+}
+
+/// A reference to a type in `$defs`, as a URI fragment holding a JSON
+/// pointer. Type names can hold characters like `<` and `#`, which have to be
+/// escaped.
+fn def_ref(name: &str) -> String {
+    let mut s = String::from("#/$defs/");
+    for c in name.chars() {
+        match c {
+            '~' => s.push_str("~0"),
+            '/' => s.push_str("~1"),
+            c if c.is_ascii_alphanumeric() || "-._!$&'()*+,;=:@".contains(c) => s.push(c),
+            c => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    write!(s, "%{:02X}", b).unwrap();
+                }
+            }
+        }
+    }
+    s
+}
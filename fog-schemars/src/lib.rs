@@ -1,11 +1,36 @@
 #![forbid(unsafe_code)]
 
+mod cell;
+pub mod diff;
+mod error;
 mod gen;
+pub mod json_schema;
+mod naming;
+mod passes;
+mod query;
+mod registry;
+mod render;
+pub mod rust;
+mod schema_doc;
 mod schema_impls;
+pub mod testing;
+mod text;
+pub mod typescript;
+mod visit;
+
+#[doc(hidden)]
+pub mod _private;
 
 use std::collections::HashMap;
 
+pub use cell::SchemaCell;
+pub use error::SchemaGenError;
 pub use gen::SchemaGenerator;
+pub use naming::NamingStrategy;
+pub use passes::BuildReport;
+pub use query::QueryPolicy;
+pub use registry::SchemaRegistry;
+pub use render::render;
 
 use fog_pack::validator::*;
 
@@ -39,15 +64,12 @@ impl Name {
         Self::with_types(path, name, Vec::new())
     }
 
+    /// Create a name with type parameters.
+    ///
+    /// The path can't contain any of `,<>`, and the name can't contain any of
+    /// `:,<>`. Using such a name makes schema generation fail with
+    /// [`SchemaGenError::InvalidName`].
     pub fn with_types(path: &'static str, name: &'static str, type_params: Vec<Name>) -> Self {
-        assert!(
-            !path.contains([',', '<', '>']),
-            "Path shouldn't contain any of \",<>\""
-        );
-        assert!(
-            !name.contains([',', '<', '>', ':']),
-            "Name shouldn't contain any of \":,<>\""
-        );
         let mods: Vec<&'static str> = path.split("::").collect();
         Self {
             id: Identifier { mods, name },
@@ -55,6 +77,44 @@ impl Name {
         }
     }
 
+    /// The module path of the type, split on `::`.
+    pub fn path(&self) -> &[&'static str] {
+        &self.id.mods
+    }
+
+    /// The type's own name, without its module path or type parameters.
+    pub fn ident(&self) -> &'static str {
+        self.id.name
+    }
+
+    /// The type parameters of the type.
+    pub fn type_params(&self) -> &[Name] {
+        &self.type_params
+    }
+
+    /// The name without any module paths, including on its type parameters.
+    pub fn bare(&self) -> String {
+        let mut s = self.id.name.to_owned();
+        if let Some((first, rest)) = self.type_params.split_first() {
+            s.push('<');
+            s.push_str(&first.bare());
+            for r in rest {
+                s.push(',');
+                s.push_str(&r.bare());
+            }
+            s.push('>');
+        }
+        s
+    }
+
+    // Check that neither this name nor any of its type parameters contain
+    // characters that would make the full name ambiguous.
+    pub(crate) fn is_valid(&self) -> bool {
+        !self.id.mods.iter().any(|m| m.contains([',', '<', '>']))
+            && !self.id.name.contains([',', '<', '>', ':'])
+            && self.type_params.iter().all(Name::is_valid)
+    }
+
     pub(crate) fn try_shorten(&self, map: &HashMap<Identifier, Identifier>) -> Name {
         let id = if let Some(id) = map.get(&self.id) {
             id.clone()
@@ -96,6 +156,44 @@ pub use fog_schemars_derive::*;
 /// `skip_serializing_if`. In the latter case, the `opt` flag is set on all
 /// calls into this trait. This can be thought of as each trait implementation
 /// returning up to two separate validators.
+///
+/// Derived implementations make a field with only `default` optional, but
+/// check it with the full validator, and warn about fields that have only one
+/// of the two attributes. The warning can be silenced by adding
+/// `#[fog(allow_missing_skip)]` to the field or the whole type.
+///
+/// Serde's `alias` and `other` attributes are also followed. Required fields
+/// with aliases and externally tagged enums with an `other` variant need a
+/// [`MultiValidator`] to express them. fog-pack doesn't check a
+/// `MultiValidator` reached directly from another one, as happens with
+/// `Option<T>`, so building the schema splices the alternatives of any such
+/// validator into the outer one. Each aliased required field multiplies the
+/// number of alternatives by its number of names, and generation fails if a
+/// type would need more than 64.
+///
+/// Only a required field is checked to be present under exactly one of its
+/// names. An optional field's names are each accepted on their own, so a
+/// document with both a name and its alias still passes, as does one with
+/// both names of a required field when `allow_unknown_fields` or
+/// `extra_values` accept the other one as an unknown key.
+///
+/// Derived validators describe the serialized form of a type: names come from
+/// serde's serialize-side `rename`, and fields or variants that are never
+/// serialized are left out. The exception is fields marked
+/// `skip_serializing`, which are kept as optional so documents written before
+/// they were skipped still pass. Tuple structs don't do this: their fields are
+/// found by position, so a field that is no longer written moves every later
+/// one, and it is left out like any other field that is never serialized.
+///
+/// Derived structs reject any key that isn't one of their fields, as with
+/// serde's `deny_unknown_fields`. Add `#[fog(allow_unknown_fields)]` to accept
+/// any other keys, or `#[fog(extra_values = "Type")]` to check them against
+/// `Type`'s validator. On an enum, these apply to all of its struct variants.
+///
+/// Fields marked `#[deprecated]` are always optional, and deprecated enum
+/// variants are still accepted, with a comment noting they're deprecated
+/// wherever fog-pack has room for one. Both are left out entirely when using
+/// [`SchemaGenerator::new_strict`].
 #[allow(unused_variables)]
 pub trait FogValidate {
     /// Whether or not the fog-pack Validator for this type should be reused where possible through
@@ -129,14 +227,77 @@ pub trait FogValidate {
     ///   `foo::Config` and `bar::Config`.
     /// - `foo::thing::Config` and `bar::thing::Config` will not change.
     ///
+    /// Other ways of naming types can be picked with
+    /// [`SchemaGenerator::naming`].
     fn validator_name(opt: bool) -> Name;
 
     /// Generates a fog-pack validator for this type.
     ///
     /// If the returned validator depends on any sub-validators, then it must
     /// get them by calling [`SchemaGenerator::type_add`], with the exception of
-    /// setting the [`StrValidator`] for a [`MapValidator`].
+    /// setting the [`StrValidator`] for a [`MapValidator`]. To reuse another
+    /// type's validator in full, call [`SchemaGenerator::type_validator`].
     ///
-    /// This shouldn't ever return a [`Validator::Ref`].
+    /// This shouldn't ever return a [`Validator::Ref`]. If no validator can
+    /// be made for the type, report why with
+    /// [`SchemaGenerator::report_error`] and return a placeholder.
     fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator;
 }
+
+/// A type that describes a complete fog-pack schema: the type of the
+/// documents, plus its entries and compression settings.
+///
+/// This is usually derived alongside [`FogValidate`], using `#[fog_schema]`
+/// attributes to fill in the rest of the schema:
+///
+/// ```
+/// use fog_schemars::{FogSchema, FogValidate};
+///
+/// #[derive(FogValidate)]
+/// struct Comment {
+///     text: String,
+/// }
+///
+/// #[derive(FogValidate, FogSchema)]
+/// #[fog_schema(name = "Blog Post", description = "A post that can be commented on")]
+/// #[fog_schema(version = 2, doc_compress = "zstd:19")]
+/// #[fog_schema(entry("comments" = Comment, compress = "zstd"))]
+/// struct Post {
+///     title: String,
+///     body: String,
+/// }
+///
+/// assert_eq!(Post::ENTRIES, &["comments"]);
+/// let schema_doc = Post::schema_document();
+/// ```
+///
+/// Compression settings can be `"none"`, `"zstd"` for the default zstd
+/// compression, or `"zstd:<level>"` for a specific compression level. Entries
+/// without a compression setting use the schema's default. The maximum number
+/// of regexes allowed in queries can be set with `regexes = <N>`.
+pub trait FogSchema: FogValidate {
+    /// The keys of every entry in the schema.
+    const ENTRIES: &'static [&'static str];
+
+    /// Set up a generator for the complete schema.
+    fn schema_generator() -> SchemaGenerator;
+
+    /// Generate the schema document.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics if an error was found while generating the
+    /// schema, or if fog-pack fails to build it. Use
+    /// [`FogSchema::try_schema_document`] to get these as errors instead.
+    fn schema_document() -> fog_pack::document::Document {
+        Self::schema_generator()
+            .build()
+            .expect("Schema should be buildable")
+    }
+
+    /// Generate the schema document, returning an error if anything went
+    /// wrong while generating or building it.
+    fn try_schema_document() -> Result<fog_pack::document::Document, SchemaGenError> {
+        Self::schema_generator().try_build()
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use fog_pack::{document::NewDocument, validator::Validator};
+
+use crate::{gen::visit_refs_mut, Name, SchemaGenError};
+
+/// How the generator names the types in a schema's type list.
+///
+/// The names end up in the schema, so changing the strategy, or anything the
+/// strategy depends on, changes the schema's hash.
+#[derive(Clone, Default)]
+pub enum NamingStrategy {
+    /// Shorten module paths as much as possible while keeping every name
+    /// unique. For a type named "Config":
+    /// - `crate::foo::Config` and `crate::bar::Config` will shorten to
+    ///   `foo::Config` and `bar::Config`.
+    /// - `foo::thing::Config` and `bar::thing::Config` will not change.
+    ///
+    /// This is the default. Names stay short and readable, but moving a type,
+    /// or adding a type with the same name, can change other types' names.
+    #[default]
+    ShortestUnique,
+    /// Use the full module path of every type. Names only change if the types
+    /// themselves are moved or renamed.
+    FullPath,
+    /// Use only the type name, without any module path. Building the schema
+    /// fails with [`SchemaGenError::NameConflict`] if two types have the same
+    /// name.
+    BareName,
+    /// Name each type after a hash of its validator, so names only change if
+    /// the validator changes. Any types referenced by the validator are
+    /// included in the hash through their own hashes, so moving a type
+    /// between modules doesn't change the schema at all.
+    StructuralHash,
+    /// Name each type with a function of its [`Name`]. Building the schema
+    /// fails with [`SchemaGenError::NameConflict`] if two types end up with
+    /// the same name but different validators.
+    Custom(Arc<dyn Fn(&Name) -> String + Send + Sync>),
+}
+
+impl NamingStrategy {
+    /// Name each type with a function of its [`Name`].
+    pub fn custom(f: impl Fn(&Name) -> String + Send + Sync + 'static) -> Self {
+        NamingStrategy::Custom(Arc::new(f))
+    }
+}
+
+impl std::fmt::Debug for NamingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NamingStrategy::ShortestUnique => f.write_str("ShortestUnique"),
+            NamingStrategy::FullPath => f.write_str("FullPath"),
+            NamingStrategy::BareName => f.write_str("BareName"),
+            NamingStrategy::StructuralHash => f.write_str("StructuralHash"),
+            NamingStrategy::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Map each full name to a bare name, failing if two types share one.
+pub(crate) fn bare_names(names: &[Name]) -> Result<BTreeMap<String, String>, SchemaGenError> {
+    let mut used = HashMap::new();
+    let mut fixups = BTreeMap::new();
+    for name in names {
+        let bare = name.bare();
+        let full = name.to_string();
+        if let Some(other) = used.insert(bare.clone(), full.clone()) {
+            if other != full {
+                return Err(SchemaGenError::NameConflict(bare));
+            }
+        }
+        fixups.insert(full, bare);
+    }
+    Ok(fixups)
+}
+
+/// Map each type name to a hash of its validator.
+pub(crate) fn structural_names(
+    types: &BTreeMap<String, Validator>,
+) -> Result<BTreeMap<String, String>, SchemaGenError> {
+    let mut hasher = StructuralHasher {
+        types,
+        stack: Vec::new(),
+        done: BTreeMap::new(),
+    };
+    let mut fixups = BTreeMap::new();
+    for name in types.keys() {
+        let (hash, _) = hasher.hash(name)?;
+        fixups.insert(name.clone(), hash);
+    }
+    Ok(fixups)
+}
+
+struct StructuralHasher<'a> {
+    types: &'a BTreeMap<String, Validator>,
+    // The types currently being hashed, for spotting recursion.
+    stack: Vec<&'a str>,
+    // Hashes that don't depend on where the hashing started.
+    done: BTreeMap<&'a str, String>,
+}
+
+impl<'a> StructuralHasher<'a> {
+    // Hash a type, returning the hash and the lowest point in the stack that
+    // the type refers back to. A type that recurses back to a type further up
+    // the stack gets a hash that depends on that type, so it can't be reused
+    // elsewhere.
+    fn hash(&mut self, name: &'a str) -> Result<(String, usize), SchemaGenError> {
+        if let Some(hash) = self.done.get(name) {
+            return Ok((hash.clone(), usize::MAX));
+        }
+        let depth = self.stack.len();
+        self.stack.push(name);
+
+        // Swap every reference for the hash of the type it refers to. A
+        // reference back up the stack is replaced with how far up it goes.
+        let mut validator = self.types[name].clone();
+        let mut lowest = usize::MAX;
+        let mut err = None;
+        visit_refs_mut(&mut validator, &mut |r| {
+            if err.is_some() {
+                return;
+            }
+            if let Some(pos) = self.stack.iter().position(|s| *s == r.as_str()) {
+                lowest = lowest.min(pos);
+                *r = format!("^{}", self.stack.len() - pos);
+            } else if let Some((name, _)) = self.types.get_key_value(r.as_str()) {
+                match self.hash(name) {
+                    Ok((hash, low)) => {
+                        lowest = lowest.min(low);
+                        *r = hash;
+                    }
+                    Err(e) => err = Some(e),
+                }
+            }
+        });
+        self.stack.pop();
+        if let Some(err) = err {
+            return Err(err);
+        }
+
+        let hash = NewDocument::new(None, &validator)?.hash().to_string();
+        if lowest >= depth {
+            self.done.insert(name, hash.clone());
+            lowest = usize::MAX;
+        }
+        Ok((hash, lowest))
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use fog_pack::validator::Validator;
+
+use crate::gen::visit_refs_mut;
+use crate::visit::children_mut;
+
+/// What the optional passes in [`SchemaGenerator::build_with_report`] changed.
+///
+/// [`SchemaGenerator::build_with_report`]: crate::SchemaGenerator::build_with_report
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BuildReport {
+    /// The names of every type in the finished type list.
+    pub types: BTreeSet<String>,
+    /// Types removed by [deduplication], mapped to the type they were merged
+    /// into.
+    ///
+    /// [deduplication]: crate::SchemaGenerator::dedup_types
+    pub merged: BTreeMap<String, String>,
+    /// Types that were [inlined] into the one place they were used.
+    ///
+    /// [inlined]: crate::SchemaGenerator::inline_types
+    pub inlined: BTreeSet<String>,
+    /// Types that were dropped by [inlining] because nothing used them.
+    ///
+    /// [inlining]: crate::SchemaGenerator::inline_types
+    pub pruned: BTreeSet<String>,
+}
+
+/// Point every reference in the schema at a new name, where there is one.
+pub(crate) fn rename_refs<'a>(
+    renames: &BTreeMap<String, String>,
+    validators: impl Iterator<Item = &'a mut Validator>,
+) {
+    for v in validators {
+        visit_refs_mut(v, &mut |s| {
+            if let Some(new_s) = renames.get(s) {
+                *s = new_s.clone();
+            }
+        });
+    }
+}
+
+/// Find types with identical validators, returning a map of the types to
+/// remove to the ones they should be replaced with.
+///
+/// Types that only refer to each other can be identical without their
+/// validators being equal (eg. two recursive lists), so this starts by
+/// assuming every type is the same, then keeps splitting them into groups
+/// with matching validators, where references are to groups instead of
+/// names, until no group splits any further.
+///
+/// Imported types are never removed, so they keep the names the schema they
+/// came from gave them.
+pub(crate) fn find_duplicates(
+    types: &BTreeMap<String, Validator>,
+    imported: &BTreeSet<String>,
+) -> BTreeMap<String, String> {
+    let mut groups: BTreeMap<&str, usize> = types.keys().map(|k| (k.as_str(), 0)).collect();
+    let mut group_count = 1;
+    loop {
+        let mut keys: BTreeMap<(usize, String), usize> = BTreeMap::new();
+        let mut next = BTreeMap::new();
+        for (name, validator) in types.iter() {
+            let mut validator = validator.clone();
+            visit_refs_mut(&mut validator, &mut |s| {
+                if let Some(group) = groups.get(s.as_str()) {
+                    *s = format!("#{}", group);
+                }
+            });
+            // Validator has no Hash or Ord, but its debug output covers all
+            // of its settings.
+            let key = (groups[name.as_str()], format!("{:?}", validator));
+            let len = keys.len();
+            next.insert(name.as_str(), *keys.entry(key).or_insert(len));
+        }
+        groups = next;
+        if keys.len() == group_count {
+            break;
+        }
+        group_count = keys.len();
+    }
+
+    // Keep the first name in each group, or the first imported one.
+    let mut kept: BTreeMap<usize, &str> = BTreeMap::new();
+    for (name, group) in groups.iter() {
+        if imported.contains(*name) {
+            kept.entry(*group).or_insert(name);
+        }
+    }
+    let mut merged = BTreeMap::new();
+    for (name, group) in groups {
+        if imported.contains(name) {
+            continue;
+        }
+        match kept.get(&group) {
+            Some(first) => {
+                merged.insert(name.to_owned(), (*first).to_owned());
+            }
+            None => {
+                kept.insert(group, name);
+            }
+        }
+    }
+    merged
+}
+
+/// Every type a validator refers to directly, once for each reference.
+fn refs_of(v: &Validator) -> Vec<String> {
+    let mut refs = Vec::new();
+    visit_refs_mut(&mut v.clone(), &mut |s| refs.push(s.clone()));
+    refs
+}
+
+/// Replace references to types that are used exactly once, and aren't
+/// recursive, with the types themselves, and drop types that can't be
+/// reached from the document or entry validators.
+pub(crate) fn inline_types(
+    mut roots: Vec<&mut Validator>,
+    types: &mut BTreeMap<String, Validator>,
+    report: &mut BuildReport,
+) {
+    // Find every reachable type, and count how often each is referenced.
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut queue: Vec<String> = roots.iter().flat_map(|v| refs_of(v)).collect();
+    while let Some(name) = queue.pop() {
+        let count = counts.entry(name.clone()).or_default();
+        *count += 1;
+        if *count == 1 {
+            if let Some(v) = types.get(&name) {
+                queue.extend(refs_of(v));
+            }
+        }
+    }
+    types.retain(|name, _| {
+        let reachable = counts.contains_key(name);
+        if !reachable {
+            report.pruned.insert(name.clone());
+        }
+        reachable
+    });
+
+    // Types used once can still be recursive, by way of some other type.
+    let recursive = |name: &String| {
+        let mut seen = BTreeSet::new();
+        let mut queue = refs_of(&types[name]);
+        while let Some(next) = queue.pop() {
+            if next == *name {
+                return true;
+            }
+            if seen.insert(next.clone()) {
+                if let Some(v) = types.get(&next) {
+                    queue.extend(refs_of(v));
+                }
+            }
+        }
+        false
+    };
+    let candidates: BTreeMap<String, Validator> = counts
+        .iter()
+        .filter(|(name, count)| **count == 1 && types.contains_key(*name) && !recursive(name))
+        .map(|(name, _)| (name.clone(), types[name].clone()))
+        .collect();
+
+    let mut inlined = BTreeSet::new();
+    for v in roots.iter_mut() {
+        inline_refs(v, false, &candidates, &mut inlined);
+    }
+    for v in types.values_mut() {
+        inline_refs(v, false, &candidates, &mut inlined);
+    }
+    types.retain(|name, _| !inlined.contains(name));
+    report.inlined = inlined;
+}
+
+fn inline_refs(
+    v: &mut Validator,
+    in_multi: bool,
+    candidates: &BTreeMap<String, Validator>,
+    inlined: &mut BTreeSet<String>,
+) {
+    match v {
+        Validator::Ref(name) => {
+            let Some(inner) = candidates.get(name) else {
+                return;
+            };
+            // Multi validators directly inside another are spliced into it
+            // when the schema is built, so those stay as references.
+            if in_multi && matches!(inner, Validator::Multi(_)) {
+                return;
+            }
+            inlined.insert(name.clone());
+            *v = inner.clone();
+            inline_refs(v, in_multi, candidates, inlined);
+        }
+        v => {
+            let in_multi = matches!(v, Validator::Multi(_));
+            children_mut(v)
+                .into_iter()
+                .for_each(|v| inline_refs(v, in_multi, candidates, inlined));
+        }
+    }
+}
+
+/// Replace every alternative of a multi validator that is itself a multi
+/// validator, or a reference to a type that is one, with that validator's
+/// alternatives.
+///
+/// fog-pack doesn't check the alternatives of a multi validator reached
+/// directly from another one, so without this, something like an
+/// `Option<T>` where `T`'s validator is a multi validator would only ever
+/// accept null.
+pub(crate) fn splice_multis(roots: Vec<&mut Validator>, types: &mut BTreeMap<String, Validator>) {
+    let original = types.clone();
+    for v in roots {
+        splice(v, &original, &mut Vec::new());
+    }
+    for (name, v) in types.iter_mut() {
+        splice(v, &original, &mut vec![name.clone()]);
+    }
+}
+
+// Splice the multi validators inside `v`. `visiting` holds the types whose
+// alternatives are being spliced in, so a type that is one of its own
+// alternatives, which adds nothing, can be dropped instead of spliced forever.
+fn splice(v: &mut Validator, types: &BTreeMap<String, Validator>, visiting: &mut Vec<String>) {
+    match v {
+        Validator::Multi(v) => {
+            let mut branches = Vec::with_capacity(v.0.len());
+            for branch in std::mem::take(&mut v.0) {
+                splice_branch(branch, types, visiting, &mut branches);
+            }
+            v.0 = branches;
+        }
+        v => children_mut(v)
+            .into_iter()
+            .for_each(|v| splice(v, types, visiting)),
+    }
+}
+
+fn splice_branch(
+    branch: Validator,
+    types: &BTreeMap<String, Validator>,
+    visiting: &mut Vec<String>,
+    branches: &mut Vec<Validator>,
+) {
+    match branch {
+        Validator::Multi(inner) => {
+            for branch in inner.0 {
+                splice_branch(branch, types, visiting, branches);
+            }
+        }
+        Validator::Ref(name) if matches!(types.get(&name), Some(Validator::Multi(_))) => {
+            if visiting.contains(&name) {
+                return;
+            }
+            let Some(Validator::Multi(inner)) = types.get(&name) else {
+                unreachable!()
+            };
+            visiting.push(name);
+            for branch in inner.0.iter() {
+                splice_branch(branch.clone(), types, visiting, branches);
+            }
+            visiting.pop();
+        }
+        mut branch => {
+            splice(&mut branch, types, visiting);
+            if !branches.contains(&branch) {
+                branches.push(branch);
+            }
+        }
+    }
+}
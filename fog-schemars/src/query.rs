@@ -0,0 +1,188 @@
+use fog_pack::validator::Validator;
+
+use crate::visit::children;
+
+/// Which kinds of queries the built-in validators allow.
+///
+/// fog-pack only lets a query use a validator feature if the entry's
+/// validator allows it, and every validator allows none by default. Setting a
+/// policy with [`SchemaGenerator::query_policy`] makes the validators for
+/// primitive and fog-pack types allow the chosen kinds of queries:
+///
+/// ```
+/// use fog_schemars::{QueryPolicy, SchemaGenerator};
+///
+/// #[derive(fog_schemars::FogValidate)]
+/// struct Comment {
+///     text: String,
+///     likes: u32,
+/// }
+///
+/// let gen = SchemaGenerator::new::<Comment>()
+///     .query_policy(QueryPolicy::new().ord(true).size(true))
+///     .entry_add::<Comment>("comments", None);
+/// ```
+///
+/// Query settings only matter for entries, but documents get them too, as
+/// they use the same validators. To allow queries on an entry alone, add it
+/// with [`SchemaGenerator::entry_add_with_query`] instead.
+///
+/// [`SchemaGenerator::query_policy`]: crate::SchemaGenerator::query_policy
+/// [`SchemaGenerator::entry_add_with_query`]: crate::SchemaGenerator::entry_add_with_query
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryPolicy {
+    /// Allow queries for specific values, using `in` and `nin`. This is the
+    /// `query` setting of every validator that has one.
+    pub values: bool,
+    /// Allow ordering queries, using `min` and `max`, on integers, floats, and
+    /// timestamps.
+    pub ord: bool,
+    /// Allow bit field queries, using `bits_set` and `bits_clr`, on integers
+    /// and byte sequences.
+    pub bit: bool,
+    /// Allow size queries, using `min_len` and `max_len`, on strings, byte
+    /// sequences, arrays, and maps.
+    pub size: bool,
+    /// Allow regex queries on strings. The schema must also allow regexes
+    /// with [`SchemaGenerator::regexes`].
+    ///
+    /// [`SchemaGenerator::regexes`]: crate::SchemaGenerator::regexes
+    pub regex: bool,
+    /// Allow queries that ban prefixes, suffixes, or characters on strings.
+    pub ban: bool,
+}
+
+impl QueryPolicy {
+    /// A policy that allows no queries. This is the default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow queries for specific values.
+    pub fn values(mut self, values: bool) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// Allow ordering queries on integers, floats, and timestamps.
+    pub fn ord(mut self, ord: bool) -> Self {
+        self.ord = ord;
+        self
+    }
+
+    /// Allow bit field queries on integers and byte sequences.
+    pub fn bit(mut self, bit: bool) -> Self {
+        self.bit = bit;
+        self
+    }
+
+    /// Allow size queries on strings, byte sequences, arrays, and maps.
+    pub fn size(mut self, size: bool) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Allow regex queries on strings.
+    pub fn regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    /// Allow ban queries on strings.
+    pub fn ban(mut self, ban: bool) -> Self {
+        self.ban = ban;
+        self
+    }
+
+    /// Whether this policy allows no queries at all.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // The suffix for the names of types generated under this policy, listing
+    // the allowed queries, like `query-ord-size`.
+    pub(crate) fn suffix(&self) -> String {
+        let flags = [
+            (self.values, "values"),
+            (self.ord, "ord"),
+            (self.bit, "bit"),
+            (self.size, "size"),
+            (self.regex, "regex"),
+            (self.ban, "ban"),
+        ];
+        let mut suffix = String::from("query");
+        for (_, flag) in flags.iter().filter(|(on, _)| *on) {
+            suffix.push('-');
+            suffix.push_str(flag);
+        }
+        if self.is_empty() {
+            suffix.push_str("-none");
+        }
+        suffix
+    }
+
+    /// Allow the queries this policy allows on a validator. Settings that
+    /// are already on are left on, and validators without any query settings
+    /// are returned as they are.
+    pub fn apply(&self, mut validator: Validator) -> Validator {
+        match &mut validator {
+            Validator::Bool(v) => v.query |= self.values,
+            Validator::Int(v) => {
+                v.query |= self.values;
+                v.ord |= self.ord;
+                v.bit |= self.bit;
+            }
+            Validator::F32(v) => {
+                v.query |= self.values;
+                v.ord |= self.ord;
+            }
+            Validator::F64(v) => {
+                v.query |= self.values;
+                v.ord |= self.ord;
+            }
+            Validator::Bin(v) => {
+                v.query |= self.values;
+                v.bit |= self.bit;
+                v.size |= self.size;
+            }
+            Validator::Str(v) => {
+                v.query |= self.values;
+                v.size |= self.size;
+                v.regex |= self.regex;
+                v.ban |= self.ban;
+            }
+            Validator::Array(v) => {
+                v.query |= self.values;
+                v.size |= self.size;
+            }
+            Validator::Map(v) => {
+                v.query |= self.values;
+                v.size |= self.size;
+            }
+            Validator::Time(v) => {
+                v.query |= self.values;
+                v.ord |= self.ord;
+            }
+            Validator::Hash(v) => v.query |= self.values,
+            Validator::Identity(v) => v.query |= self.values,
+            Validator::StreamId(v) => v.query |= self.values,
+            Validator::LockId(v) => v.query |= self.values,
+            Validator::DataLockbox(v) => v.size |= self.size,
+            Validator::IdentityLockbox(v) => v.size |= self.size,
+            Validator::StreamLockbox(v) => v.size |= self.size,
+            Validator::LockLockbox(v) => v.size |= self.size,
+            _ => (),
+        }
+        validator
+    }
+}
+
+/// Whether a validator, or any validator inside it, allows regex queries.
+pub(crate) fn allows_regex(validator: &Validator) -> bool {
+    match validator {
+        Validator::Str(v) => v.regex,
+        Validator::Map(v) if v.keys.as_ref().is_some_and(|k| k.regex) => true,
+        v => children(v).into_iter().any(allows_regex),
+    }
+}
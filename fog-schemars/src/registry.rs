@@ -0,0 +1,285 @@
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+
+use fog_pack::{document::Document, types::Hash, validator::Validator};
+
+use crate::gen::{naming_fixups, visit_refs_mut};
+use crate::{BuildReport, Name, NamingStrategy, QueryPolicy, SchemaGenError, SchemaGenerator};
+
+/// A family of related schemas whose shared types get the same names in all
+/// of them.
+///
+/// ```
+/// use fog_schemars::{FogValidate, SchemaGenerator, SchemaRegistry};
+///
+/// #[derive(FogValidate)]
+/// struct Author {
+///     name: String,
+/// }
+///
+/// #[derive(FogValidate)]
+/// struct Post {
+///     author: Author,
+///     body: String,
+/// }
+///
+/// #[derive(FogValidate)]
+/// struct Profile {
+///     author: Author,
+///     bio: String,
+/// }
+///
+/// let schemas = SchemaRegistry::new()
+///     .schema_add("post", SchemaGenerator::new::<Post>())
+///     .schema_add("profile", SchemaGenerator::new::<Profile>())
+///     .build()
+///     .unwrap();
+/// let (post_doc, post_hash) = &schemas["post"];
+/// ```
+///
+/// Only the naming is shared. Each generator still generates its own
+/// validators, and the types of each schema are named by the
+/// [`NamingStrategy`] of the registry, which looks at the types of every
+/// schema at once; the naming strategies of the generators themselves aren't
+/// used. Any other options, like [`SchemaGenerator::dedup_types`], still
+/// apply to each schema separately.
+///
+/// Strict schemas, and schemas with a [`QueryPolicy`], can generate a
+/// different validator for a type than the rest. Where they do, their version
+/// of the type is named with a suffix for its settings, like `Type#strict` or
+/// `Type#query-ord`.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<String, SchemaGenerator>,
+    naming: NamingStrategy,
+}
+
+impl SchemaRegistry {
+    /// Start an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pick how the types of every schema are named. See [`NamingStrategy`]
+    /// for the options; the default is [`NamingStrategy::ShortestUnique`].
+    pub fn naming(mut self, strategy: NamingStrategy) -> Self {
+        self.naming = strategy;
+        self
+    }
+
+    /// Add a schema to the registry.
+    ///
+    /// Panics
+    /// ------
+    /// This function panics if a schema has already been added with the same
+    /// name.
+    pub fn schema_add(self, name: &str, schema: SchemaGenerator) -> Self {
+        self.try_schema_add(name, schema)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Add a schema to the registry, or fail with
+    /// [`SchemaGenError::DuplicateSchema`] if a schema has already been added
+    /// with the same name.
+    pub fn try_schema_add(
+        mut self,
+        name: &str,
+        schema: SchemaGenerator,
+    ) -> Result<Self, SchemaGenError> {
+        match self.schemas.entry(name.to_owned()) {
+            Entry::Occupied(_) => Err(SchemaGenError::DuplicateSchema(name.to_owned())),
+            Entry::Vacant(e) => {
+                e.insert(schema);
+                Ok(self)
+            }
+        }
+    }
+
+    /// Build every schema, returning each schema document and its hash, by
+    /// schema name.
+    pub fn build(self) -> Result<BTreeMap<String, (Document, Hash)>, SchemaGenError> {
+        let schemas = self.build_with_report()?;
+        Ok(schemas
+            .into_iter()
+            .map(|(name, (doc, hash, _))| (name, (doc, hash)))
+            .collect())
+    }
+
+    /// Build every schema, like [`SchemaRegistry::build`], and also report
+    /// what went into each one. The [`BuildReport::types`] of each report
+    /// lists the types that schema includes.
+    ///
+    /// This fails if any schema fails to build, or if a name is used for
+    /// different validators in different schemas with the same settings.
+    pub fn build_with_report(
+        self,
+    ) -> Result<BTreeMap<String, (Document, Hash, BuildReport)>, SchemaGenError> {
+        for schema in self.schemas.values() {
+            schema.check()?;
+        }
+        let renames = settings_renames(&self.schemas);
+
+        // Gather up the type lists of every schema, which must agree on what
+        // each name means.
+        let mut types: BTreeMap<String, (Validator, &'static str)> = BTreeMap::new();
+        let mut long_names: Vec<Name> = Vec::new();
+        let mut refined = Vec::new();
+        for (schema_name, schema) in self.schemas.iter() {
+            let renames = &renames[schema_name];
+            for (name, validator) in renamed_types(schema, renames) {
+                let owner = schema.type_owners.get(&name).map_or("", |owners| owners[0]);
+                let name = renames.get(&name).cloned().unwrap_or(name);
+                match types.entry(name.clone()) {
+                    Entry::Vacant(e) => {
+                        e.insert((validator, owner));
+                    }
+                    Entry::Occupied(e) if e.get().0 != validator => {
+                        let first = e.get().1;
+                        return Err(if first == owner {
+                            SchemaGenError::NameConflict(name)
+                        } else {
+                            SchemaGenError::DuplicateName {
+                                name,
+                                first: first.to_owned(),
+                                second: owner.to_owned(),
+                            }
+                        });
+                    }
+                    Entry::Occupied(_) => (),
+                }
+            }
+            for name in schema.long_names.iter() {
+                if !long_names.contains(name) {
+                    long_names.push(name.clone());
+                }
+            }
+            refined.extend(schema.refined.iter().cloned());
+            // Renamed types follow the names of the types they're a variant
+            // of, like other refined types.
+            for (name, new_name) in renames.iter() {
+                let suffix = &new_name[name.len() + 1..];
+                let base = schema
+                    .long_names
+                    .iter()
+                    .find(|n| n.to_string() == *name)
+                    .map(|n| (n.clone(), suffix.to_owned()))
+                    .or_else(|| {
+                        schema.refined.iter().find_map(|(b, s)| {
+                            (format!("{}#{}", b, s) == *name)
+                                .then(|| (b.clone(), format!("{}#{}", s, suffix)))
+                        })
+                    });
+                if let Some(base) = base {
+                    if !refined.contains(&base) {
+                        refined.push(base);
+                    }
+                }
+            }
+        }
+        let types = types
+            .into_iter()
+            .map(|(name, (validator, _))| (name, validator))
+            .collect();
+        let fixups = naming_fixups(&self.naming, &long_names, &types, &refined)?;
+
+        let mut schemas = BTreeMap::new();
+        for (name, schema) in self.schemas {
+            let mut fixups = fixups.clone();
+            for (old, new) in renames[&name].iter() {
+                let new = fixups.get(new).unwrap_or(new).clone();
+                fixups.insert(old.clone(), new);
+            }
+            let (doc, report) = schema.build_with_fixups(&fixups)?;
+            let hash = doc.hash().clone();
+            schemas.insert(name, (doc, hash, report));
+        }
+        Ok(schemas)
+    }
+}
+
+/// The suffix that sets apart types generated with a schema's settings, or
+/// `None` for the default settings.
+fn settings_suffix(schema: &SchemaGenerator) -> Option<String> {
+    let query = (*schema.queries() != QueryPolicy::default()).then(|| schema.queries().suffix());
+    match (schema.is_strict(), query) {
+        (false, None) => None,
+        (true, None) => Some("strict".to_owned()),
+        (false, Some(query)) => Some(query),
+        (true, Some(query)) => Some(format!("strict-{}", query)),
+    }
+}
+
+/// A schema's type list, with every name in `renames` renamed, including in
+/// references.
+fn renamed_types(
+    schema: &SchemaGenerator,
+    renames: &BTreeMap<String, String>,
+) -> Vec<(String, Validator)> {
+    schema
+        .types
+        .iter()
+        .map(|(name, validator)| {
+            let mut validator = validator.clone();
+            visit_refs_mut(&mut validator, &mut |s| {
+                if let Some(new_s) = renames.get(s) {
+                    *s = new_s.clone();
+                }
+            });
+            (name.clone(), validator)
+        })
+        .collect()
+}
+
+/// Work out which types need a different name in each schema, because
+/// schemas with different settings, like strict ones, generated a different
+/// validator for them. Types from schemas with the default settings keep
+/// their names, and the rest get their settings added as a suffix, like
+/// `Type#strict`.
+///
+/// Renaming a type changes the validators that refer to it, so this repeats
+/// until no more types differ.
+fn settings_renames(
+    schemas: &BTreeMap<String, SchemaGenerator>,
+) -> BTreeMap<String, BTreeMap<String, String>> {
+    let suffixes: BTreeMap<&String, Option<String>> = schemas
+        .iter()
+        .map(|(name, schema)| (name, settings_suffix(schema)))
+        .collect();
+    let mut renamed: BTreeSet<String> = BTreeSet::new();
+    loop {
+        let renames = |schema_name: &String| -> BTreeMap<String, String> {
+            let Some(suffix) = &suffixes[schema_name] else {
+                return BTreeMap::new();
+            };
+            renamed
+                .iter()
+                .filter(|name| schemas[schema_name].types.contains_key(*name))
+                .map(|name| (name.clone(), format!("{}#{}", name, suffix)))
+                .collect()
+        };
+        // The validator each type has under each group of settings.
+        let mut seen: BTreeMap<String, Vec<(&Option<String>, Validator)>> = BTreeMap::new();
+        for (schema_name, schema) in schemas.iter() {
+            let suffix = &suffixes[schema_name];
+            for (name, validator) in renamed_types(schema, &renames(schema_name)) {
+                let versions = seen.entry(name).or_default();
+                if !versions.iter().any(|(s, _)| *s == suffix) {
+                    versions.push((suffix, validator));
+                }
+            }
+        }
+        let differing: Vec<String> = seen
+            .into_iter()
+            .filter(|(name, versions)| {
+                !renamed.contains(name) && versions.iter().any(|(_, v)| *v != versions[0].1)
+            })
+            .map(|(name, _)| name)
+            .collect();
+        if differing.is_empty() {
+            return schemas
+                .keys()
+                .map(|name| (name.clone(), renames(name)))
+                .collect();
+        }
+        renamed.extend(differing);
+    }
+}
@@ -0,0 +1,402 @@
+use std::fmt::{Display, Write as _};
+
+use fog_pack::{document::Document, schema::Compress, validator::*};
+
+use crate::schema_doc::{compress_eq, SchemaDoc};
+use crate::text::comment;
+
+/// Render a schema as indented text, for reviewing it or changes to it.
+///
+/// The document validator comes first, then each entry's validator, then the
+/// type list. Each validator is listed with its constraints and comment, and
+/// the validators inside it are indented below it. References are shown by
+/// the name of the type they refer to, which is rendered once in the type
+/// list:
+///
+/// ```
+/// use fog_schemars::{render, FogValidate, SchemaGenerator};
+///
+/// /// A blog post.
+/// #[derive(FogValidate)]
+/// struct Post {
+///     title: String,
+///     likes: u32,
+/// }
+///
+/// let schema = SchemaGenerator::new::<Post>().build().unwrap();
+/// let text = render(&schema).unwrap();
+/// assert!(text.contains("req \"likes\": Int (>= 0, <= 4294967295)"));
+/// ```
+///
+/// Settings that are left at their defaults aren't shown, so the rendering
+/// only lists what a validator actually checks or allows.
+///
+/// This fails if the document isn't a schema.
+pub fn render(schema: &Document) -> fog_pack::error::Result<String> {
+    Ok(render_schema(&SchemaDoc::from_doc(schema)?))
+}
+
+pub(crate) fn render_schema(schema: &SchemaDoc) -> String {
+    let mut r = Renderer { out: String::new() };
+    if !schema.name.is_empty() {
+        r.line(0, format!("name: {:?}", schema.name));
+    }
+    if !schema.description.is_empty() {
+        r.line(0, format!("description: {:?}", schema.description));
+    }
+    if schema.version != 0.into() {
+        r.line(0, format!("version: {}", schema.version));
+    }
+    if schema.max_regex != 0 {
+        r.line(0, format!("regexes: {}", schema.max_regex));
+    }
+    if !compress_eq(&schema.doc_compress, &Compress::default()) {
+        r.line(0, format!("doc_compress: {:?}", schema.doc_compress));
+    }
+    if !r.out.is_empty() {
+        r.out.push('\n');
+    }
+
+    r.validator(0, "doc: ", &schema.doc);
+    for (key, entry) in schema.entries.iter() {
+        r.out.push('\n');
+        let label = if compress_eq(&entry.compress, &Compress::default()) {
+            format!("entry {:?}: ", key)
+        } else {
+            format!("entry {:?} (compress: {:?}): ", key, entry.compress)
+        };
+        r.validator(0, &label, &entry.entry);
+    }
+    for (name, validator) in schema.types.iter() {
+        r.out.push('\n');
+        r.validator(0, &format!("type {:?}: ", name), validator);
+    }
+    r.out
+}
+
+struct Renderer {
+    out: String,
+}
+
+impl Renderer {
+    fn line(&mut self, indent: usize, text: impl Display) {
+        writeln!(self.out, "{:width$}{}", "", text, width = indent * 2).unwrap();
+    }
+
+    /// Render a validator as a line starting with `label`, followed by its
+    /// comment and the validators inside it.
+    fn validator(&mut self, indent: usize, label: &str, v: &Validator) {
+        let mut c = Constraints::default();
+        let kind = match v {
+            Validator::Null => "Null",
+            Validator::Any => "Any",
+            Validator::Ref(name) => {
+                self.line(indent, format!("{}ref {:?}", label, name));
+                return;
+            }
+            Validator::Bool(v) => {
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query)]);
+                "Bool"
+            }
+            Validator::Int(v) => {
+                let d = IntValidator::default();
+                c.bounds(
+                    (v.min != d.min).then(|| v.min.to_string()),
+                    v.ex_min,
+                    (v.max != d.max).then(|| v.max.to_string()),
+                    v.ex_max,
+                );
+                if v.bits_set != 0 {
+                    c.push(format!("bits_set {:#x}", v.bits_set));
+                }
+                if v.bits_clr != 0 {
+                    c.push(format!("bits_clr {:#x}", v.bits_clr));
+                }
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query), ("bit", v.bit), ("ord", v.ord)]);
+                "Int"
+            }
+            Validator::F32(v) => {
+                c.bounds(
+                    (!v.min.is_nan()).then(|| v.min.to_string()),
+                    v.ex_min,
+                    (!v.max.is_nan()).then(|| v.max.to_string()),
+                    v.ex_max,
+                );
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query), ("ord", v.ord)]);
+                "F32"
+            }
+            Validator::F64(v) => {
+                c.bounds(
+                    (!v.min.is_nan()).then(|| v.min.to_string()),
+                    v.ex_min,
+                    (!v.max.is_nan()).then(|| v.max.to_string()),
+                    v.ex_max,
+                );
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query), ("ord", v.ord)]);
+                "F64"
+            }
+            Validator::Bin(v) => {
+                let d = BinValidator::default();
+                c.bounds(
+                    (v.min != d.min).then(|| hex(&v.min)),
+                    v.ex_min,
+                    (v.max != d.max).then(|| hex(&v.max)),
+                    v.ex_max,
+                );
+                c.len("len", v.min_len, v.max_len);
+                if !v.bits_set.is_empty() {
+                    c.push(format!("bits_set {}", hex(&v.bits_set)));
+                }
+                if !v.bits_clr.is_empty() {
+                    c.push(format!("bits_clr {}", hex(&v.bits_clr)));
+                }
+                c.list("in", &v.in_list, |v| hex(v));
+                c.list("nin", &v.nin_list, |v| hex(v));
+                c.flags([
+                    ("query", v.query),
+                    ("bit", v.bit),
+                    ("ord", v.ord),
+                    ("size", v.size),
+                ]);
+                "Bin"
+            }
+            Validator::Str(v) => {
+                c.len("len", v.min_len, v.max_len);
+                c.len("chars", v.min_char, v.max_char);
+                if let Some(regex) = &v.matches {
+                    c.push(format!("matches {:?}", regex.as_str()));
+                }
+                if !matches!(v.normalize, Normalize::None) {
+                    c.push(format!("normalize {:?}", v.normalize));
+                }
+                c.list("in", &v.in_list, |v| format!("{:?}", v));
+                c.list("nin", &v.nin_list, |v| format!("{:?}", v));
+                c.list("ban_prefix", &v.ban_prefix, |v| format!("{:?}", v));
+                c.list("ban_suffix", &v.ban_suffix, |v| format!("{:?}", v));
+                if !v.ban_char.is_empty() {
+                    c.push(format!("ban_char {:?}", v.ban_char));
+                }
+                c.flags([
+                    ("query", v.query),
+                    ("regex", v.regex),
+                    ("ban", v.ban),
+                    ("size", v.size),
+                ]);
+                "Str"
+            }
+            Validator::Array(v) => {
+                c.len("len", v.min_len, v.max_len);
+                if v.unique {
+                    c.push("unique");
+                }
+                c.list("in", &v.in_list, |v| format!("{:?}", v));
+                c.list("nin", &v.nin_list, |v| format!("{:?}", v));
+                c.flags([
+                    ("query", v.query),
+                    ("array", v.array),
+                    ("contains_ok", v.contains_ok),
+                    ("unique_ok", v.unique_ok),
+                    ("size", v.size),
+                ]);
+                "Array"
+            }
+            Validator::Map(v) => {
+                c.len("len", v.min_len, v.max_len);
+                c.list("same_len", &v.same_len, |v| format!("{:?}", v));
+                c.list("in", &v.in_list, |v| format!("{:?}", v));
+                c.list("nin", &v.nin_list, |v| format!("{:?}", v));
+                c.flags([
+                    ("query", v.query),
+                    ("size", v.size),
+                    ("map_ok", v.map_ok),
+                    ("same_len_ok", v.same_len_ok),
+                ]);
+                "Map"
+            }
+            Validator::Time(v) => {
+                let d = TimeValidator::default();
+                c.bounds(
+                    (v.min != d.min).then(|| v.min.to_string()),
+                    v.ex_min,
+                    (v.max != d.max).then(|| v.max.to_string()),
+                    v.ex_max,
+                );
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query), ("ord", v.ord)]);
+                "Time"
+            }
+            Validator::Hash(v) => {
+                c.list("schema", &v.schema, |v| match v {
+                    Some(hash) => hash.to_string(),
+                    None => "none".to_owned(),
+                });
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([
+                    ("query", v.query),
+                    ("link_ok", v.link_ok),
+                    ("schema_ok", v.schema_ok),
+                ]);
+                "Hash"
+            }
+            Validator::Identity(v) => {
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query)]);
+                "Identity"
+            }
+            Validator::StreamId(v) => {
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query)]);
+                "StreamId"
+            }
+            Validator::LockId(v) => {
+                c.list("in", &v.in_list, |v| v.to_string());
+                c.list("nin", &v.nin_list, |v| v.to_string());
+                c.flags([("query", v.query)]);
+                "LockId"
+            }
+            Validator::DataLockbox(v) => {
+                c.len("len", v.min_len, v.max_len);
+                c.flags([("size", v.size)]);
+                "DataLockbox"
+            }
+            Validator::IdentityLockbox(v) => {
+                c.len("len", v.min_len, v.max_len);
+                c.flags([("size", v.size)]);
+                "IdentityLockbox"
+            }
+            Validator::StreamLockbox(v) => {
+                c.len("len", v.min_len, v.max_len);
+                c.flags([("size", v.size)]);
+                "StreamLockbox"
+            }
+            Validator::LockLockbox(v) => {
+                c.len("len", v.min_len, v.max_len);
+                c.flags([("size", v.size)]);
+                "LockLockbox"
+            }
+            Validator::Multi(_) => "Multi",
+            Validator::Enum(_) => "Enum",
+        };
+        if c.0.is_empty() {
+            self.line(indent, format!("{}{}", label, kind));
+        } else {
+            self.line(indent, format!("{}{} ({})", label, kind, c.0.join(", ")));
+        }
+
+        let indent = indent + 1;
+        if let Some(comment) = comment(v) {
+            for line in comment.lines() {
+                self.line(indent, format!("// {}", line));
+            }
+        }
+        match v {
+            Validator::Array(v) => {
+                for (i, prefix) in v.prefix.iter().enumerate() {
+                    self.validator(indent, &format!("prefix {}: ", i), prefix);
+                }
+                if *v.items != Validator::Any {
+                    self.validator(indent, "items: ", &v.items);
+                }
+                for contains in v.contains.iter() {
+                    self.validator(indent, "contains: ", contains);
+                }
+            }
+            Validator::Map(v) => {
+                if let Some(keys) = &v.keys {
+                    self.validator(indent, "keys: ", &Validator::Str(keys.clone()));
+                }
+                if let Some(values) = &v.values {
+                    self.validator(indent, "values: ", values);
+                }
+                for (key, v) in v.req.iter() {
+                    self.validator(indent, &format!("req {:?}: ", key), v);
+                }
+                for (key, v) in v.opt.iter() {
+                    self.validator(indent, &format!("opt {:?}: ", key), v);
+                }
+            }
+            Validator::Hash(v) => {
+                if let Some(link) = &v.link {
+                    self.validator(indent, "link: ", link);
+                }
+            }
+            Validator::Multi(v) => {
+                for v in v.iter() {
+                    self.validator(indent, "- ", v);
+                }
+            }
+            Validator::Enum(v) => {
+                for (variant, v) in v.iter() {
+                    match v {
+                        Some(v) => self.validator(indent, &format!("{:?}: ", variant), v),
+                        None => self.line(indent, format!("{:?}", variant)),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The constraints on a validator, as short descriptions.
+#[derive(Default)]
+struct Constraints(Vec<String>);
+
+impl Constraints {
+    fn push(&mut self, constraint: impl Into<String>) {
+        self.0.push(constraint.into());
+    }
+
+    fn bounds(&mut self, min: Option<String>, ex_min: bool, max: Option<String>, ex_max: bool) {
+        if let Some(min) = min {
+            self.push(format!("{} {}", if ex_min { ">" } else { ">=" }, min));
+        }
+        if let Some(max) = max {
+            self.push(format!("{} {}", if ex_max { "<" } else { "<=" }, max));
+        }
+    }
+
+    fn len(&mut self, name: &str, min: u32, max: u32) {
+        if min != 0 {
+            self.push(format!("{} >= {}", name, min));
+        }
+        if max != u32::MAX {
+            self.push(format!("{} <= {}", name, max));
+        }
+    }
+
+    fn list<T>(&mut self, name: &str, items: &[T], f: impl Fn(&T) -> String) {
+        if !items.is_empty() {
+            let items: Vec<String> = items.iter().map(f).collect();
+            self.push(format!("{} [{}]", name, items.join(", ")));
+        }
+    }
+
+    /// Query permissions, listed together as one constraint.
+    fn flags<const N: usize>(&mut self, flags: [(&str, bool); N]) {
+        let allowed: Vec<&str> = flags.iter().filter(|f| f.1).map(|f| f.0).collect();
+        if !allowed.is_empty() {
+            self.push(format!("queries: {}", allowed.join(" ")));
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut s = String::from("0x");
+    for b in bytes {
+        write!(s, "{:02x}", b).unwrap();
+    }
+    s
+}
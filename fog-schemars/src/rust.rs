@@ -0,0 +1,1844 @@
+//! Generation of Rust types from schemas.
+//!
+//! [`export`] turns an existing schema back into Rust source: a type for each
+//! type in the schema's type list, and for the document and each entry where
+//! they aren't one of those, all deriving serde's `Serialize` and
+//! `Deserialize` along with [`FogValidate`]. A `schema` function starts a
+//! [`SchemaGenerator`] for them with the schema's settings, and building it
+//! gives back a schema with the same hash, which is worth checking in a test:
+//!
+//! ```ignore
+//! assert_eq!(schema().build().unwrap().hash(), original.hash());
+//! ```
+//!
+//! Validators become the Rust types that the derive would make them from:
+//!
+//! | Validator | Rust |
+//! |-----------|------|
+//! | `Map` | a struct, with a field for each key |
+//! | `Enum` | an enum, tagged the way serde does by default |
+//! | `Multi` | an enum, tagged internally or adjacently if every validator in it is a map with the same tag key, and untagged otherwise |
+//! | anything else | a newtype struct |
+//!
+//! Fields use whichever type has exactly the validator the field has: the
+//! primitive types, `String`, fog-pack's own types, `Vec<T>`,
+//! `BTreeMap<String, T>`, `Option<T>`, or one of the generated types.
+//! Optional keys become fields marked
+//! `#[serde(default, skip_serializing_if = "...")]`, holding an `Option<T>`
+//! unless the type has its own validator for optional fields, as `String` and
+//! `Vec<T>` do. A validator that no type has, like a string with a length
+//! limit, is built by a function written out next to the type, and the field
+//! uses it through `#[fog(schema_with = "...")]` with the closest type. Byte
+//! strings use `serde_bytes::ByteBuf` and string regexes use `regex::Regex`,
+//! so code with them needs those crates.
+//!
+//! Types keep their names with `#[fog(rename = "...")]`. Names with module
+//! paths or type parameters can't be given that way, so those types get a
+//! handwritten [`FogValidate`] implementation instead, as do structs whose
+//! validator has settings the derive can't make, like a comment. Rust
+//! identifiers are made from the names by dropping anything that can't be in
+//! one, so `Pair<::u8>` becomes `PairU8`, with a number at the end of any that
+//! would clash. Types named `Option<T>` that are exactly an `Option<T>` are
+//! left as that. The document's type is `Document`, and each entry's type is
+//! its key followed by `Entry`, as in `CommentsEntry`.
+//!
+//! Some schemas can't be made again this way, and the generated source starts
+//! with a `NOTE` comment for each reason it won't have the same hash:
+//! - types that nothing refers to, as when they come from
+//!   [`SchemaGenerator::import_types`]
+//! - a document or entry that's a reference to a type nothing else refers to
+//! - compression using a dictionary
+//! - names with a module path that the default naming strategy would shorten
+//! - names a [`Name`] can't display as, like those of generic types made
+//!   under an entry's own query policy
+//! - lockbox values in a validator's `in` or `nin` lists
+//!
+//! The `fog-schema-to-rust` binary does the same for an encoded schema
+//! document, read from a file or from standard input when given `-`, and
+//! prints the result.
+//!
+//! [`FogValidate`]: crate::FogValidate
+//! [`Name`]: crate::Name
+//! [`SchemaGenerator`]: crate::SchemaGenerator
+//! [`SchemaGenerator::import_types`]: crate::SchemaGenerator::import_types
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::num::*;
+
+use fog_pack::{document::Document, schema::Compress, types::*, validator::*};
+
+use crate::gen::visit_refs_mut;
+use crate::schema_doc::{compress_eq, SchemaDoc};
+use crate::text::{identifier, unique};
+use crate::{FogValidate, SchemaGenerator};
+
+/// Generate Rust types for a schema, returning the contents of a Rust module.
+/// See the [module documentation](self) for how validators are mapped.
+///
+/// ```
+/// use fog_schemars::{rust, FogValidate, SchemaGenerator};
+///
+/// #[derive(FogValidate)]
+/// struct Post {
+///     title: String,
+/// }
+///
+/// let schema = SchemaGenerator::new::<Post>().build().unwrap();
+/// let code = rust::export(&schema).unwrap();
+/// assert!(code.contains("pub struct Document {\n    pub title: String,\n}"));
+/// ```
+///
+/// This fails if the document isn't a schema.
+pub fn export(schema: &Document) -> fog_pack::error::Result<String> {
+    let schema = SchemaDoc::from_doc(schema)?;
+    Ok(Writer::new(&schema).write())
+}
+
+/// A Rust type with a fixed validator, which validators can be matched
+/// against.
+struct Leaf {
+    /// The type, as it's written in the generated code.
+    ty: &'static str,
+    /// Where to import the type from, if it isn't in the prelude.
+    import: Option<&'static str>,
+    validator: Validator,
+    /// The validator for the type when it's an optional field.
+    opt: Validator,
+    /// The type's name when it's a type parameter, as in `Option<::u8>`.
+    name: String,
+    /// The `skip_serializing_if` function to use with the `opt` validator.
+    skip: Option<&'static str>,
+}
+
+fn leaf<T: FogValidate>(
+    gen: &mut SchemaGenerator,
+    ty: &'static str,
+    import: Option<&'static str>,
+    skip: Option<&'static str>,
+) -> Leaf {
+    Leaf {
+        ty,
+        import,
+        validator: gen.type_add::<T>(),
+        opt: gen.type_add_opt::<T>(),
+        name: T::validator_name(false).to_string(),
+        skip,
+    }
+}
+
+/// Every type a field can have without needing a validator function. Types
+/// that share a validator are left out, and when a validator fits several
+/// integer types, the first one is used.
+fn leaves() -> Vec<Leaf> {
+    let gen = &mut SchemaGenerator::new::<()>();
+    vec![
+        leaf::<()>(gen, "()", None, None),
+        leaf::<bool>(gen, "bool", None, Some("std::ops::Not::not")),
+        leaf::<u8>(gen, "u8", None, None),
+        leaf::<u16>(gen, "u16", None, None),
+        leaf::<u32>(gen, "u32", None, None),
+        leaf::<u64>(gen, "u64", None, None),
+        leaf::<i8>(gen, "i8", None, None),
+        leaf::<i16>(gen, "i16", None, None),
+        leaf::<i32>(gen, "i32", None, None),
+        leaf::<i64>(gen, "i64", None, None),
+        leaf::<Integer>(gen, "Integer", Some("fog_pack::types::Integer"), None),
+        leaf::<NonZeroU8>(gen, "NonZeroU8", Some("std::num::NonZeroU8"), None),
+        leaf::<NonZeroU16>(gen, "NonZeroU16", Some("std::num::NonZeroU16"), None),
+        leaf::<NonZeroU32>(gen, "NonZeroU32", Some("std::num::NonZeroU32"), None),
+        leaf::<NonZeroU64>(gen, "NonZeroU64", Some("std::num::NonZeroU64"), None),
+        leaf::<NonZeroI8>(gen, "NonZeroI8", Some("std::num::NonZeroI8"), None),
+        leaf::<NonZeroI16>(gen, "NonZeroI16", Some("std::num::NonZeroI16"), None),
+        leaf::<NonZeroI32>(gen, "NonZeroI32", Some("std::num::NonZeroI32"), None),
+        leaf::<NonZeroI64>(gen, "NonZeroI64", Some("std::num::NonZeroI64"), None),
+        leaf::<f32>(gen, "f32", None, None),
+        leaf::<f64>(gen, "f64", None, None),
+        leaf::<String>(gen, "String", None, Some("String::is_empty")),
+        leaf::<char>(gen, "char", None, None),
+        leaf::<Timestamp>(gen, "Timestamp", Some("fog_pack::types::Timestamp"), None),
+        leaf::<Hash>(gen, "Hash", Some("fog_pack::types::Hash"), None),
+        leaf::<Identity>(gen, "Identity", Some("fog_pack::types::Identity"), None),
+        leaf::<StreamId>(gen, "StreamId", Some("fog_pack::types::StreamId"), None),
+        leaf::<LockId>(gen, "LockId", Some("fog_pack::types::LockId"), None),
+        leaf::<DataLockbox>(
+            gen,
+            "DataLockbox",
+            Some("fog_pack::types::DataLockbox"),
+            None,
+        ),
+        leaf::<IdentityLockbox>(
+            gen,
+            "IdentityLockbox",
+            Some("fog_pack::types::IdentityLockbox"),
+            None,
+        ),
+        leaf::<StreamLockbox>(
+            gen,
+            "StreamLockbox",
+            Some("fog_pack::types::StreamLockbox"),
+            None,
+        ),
+        leaf::<LockLockbox>(
+            gen,
+            "LockLockbox",
+            Some("fog_pack::types::LockLockbox"),
+            None,
+        ),
+        leaf::<Value>(gen, "Value", Some("fog_pack::types::Value"), None),
+    ]
+}
+
+/// A Rust type whose validator is exactly some validator.
+enum Shape {
+    /// A type from the type list.
+    Ref(String),
+    Leaf(usize),
+    Vec(Box<Shape>),
+    Map(Box<Shape>),
+}
+
+fn shape(leaves: &[Leaf], v: &Validator) -> Option<Shape> {
+    match v {
+        Validator::Ref(name) => return Some(Shape::Ref(name.clone())),
+        Validator::Array(a) if *v == ArrayValidator::new().items((*a.items).clone()).build() => {
+            return Some(Shape::Vec(Box::new(shape(leaves, &a.items)?)));
+        }
+        Validator::Map(m) => {
+            if let Some(values) = &m.values {
+                if *v == MapValidator::new().values((**values).clone()).build() {
+                    return Some(Shape::Map(Box::new(shape(leaves, values)?)));
+                }
+            }
+        }
+        _ => (),
+    }
+    leaves
+        .iter()
+        .position(|l| l.validator == *v)
+        .map(Shape::Leaf)
+}
+
+/// The name a shape's type has as a type parameter.
+fn shape_name(leaves: &[Leaf], shape: &Shape) -> String {
+    match shape {
+        Shape::Ref(name) => name.clone(),
+        Shape::Leaf(i) => leaves[*i].name.clone(),
+        Shape::Vec(s) => format!("::Vec<{}>", shape_name(leaves, s)),
+        Shape::Map(s) => format!("::Map<{}>", shape_name(leaves, s)),
+    }
+}
+
+/// If a map validator is one a derived struct can have, this is the
+/// container attribute for the struct's unknown keys, if it needs one.
+fn struct_extra(leaves: &[Leaf], m: &MapValidator) -> Option<Option<Extra>> {
+    let mut plain = MapValidator::new();
+    plain.req = m.req.clone();
+    plain.opt = m.opt.clone();
+    plain.values = m.values.clone();
+    if plain != *m {
+        return None;
+    }
+    match m.values.as_deref() {
+        None => Some(None),
+        Some(Validator::Any) => Some(Some(Extra::Any)),
+        Some(v) => Some(Some(Extra::Type(shape(leaves, v)?))),
+    }
+}
+
+/// What a derived struct checks unknown keys against.
+enum Extra {
+    Any,
+    Type(Shape),
+}
+
+/// The variant a tag validator accepts, if it accepts exactly one.
+fn tag_name(v: &Validator) -> Option<&str> {
+    match v {
+        Validator::Str(s)
+            if s.in_list.len() == 1
+                && *v == StrValidator::new().in_add(s.in_list[0].clone()).build() =>
+        {
+            Some(&s.in_list[0])
+        }
+        _ => None,
+    }
+}
+
+/// How the variants of an enum made from a [`MultiValidator`] are told
+/// apart, as serde would tag them.
+enum Tagging {
+    Internal(String),
+    Adjacent(String, String),
+    Untagged,
+}
+
+/// Work out how a [`MultiValidator`] was tagged, by looking for a key that
+/// every validator in it has, holding a different variant name in each.
+fn multi_tagging(leaves: &[Leaf], multi: &MultiValidator) -> Tagging {
+    let maps: Option<Vec<&MapValidator>> = multi
+        .iter()
+        .map(|v| match v {
+            Validator::Map(m) if struct_extra(leaves, m).is_some() => Some(&**m),
+            _ => None,
+        })
+        .collect();
+    let Some(maps) = maps else {
+        return Tagging::Untagged;
+    };
+    let Some(first) = maps.first() else {
+        return Tagging::Untagged;
+    };
+
+    for tag in first.req.keys() {
+        let names: Option<BTreeSet<&str>> =
+            maps.iter().map(|m| tag_name(m.req.get(tag)?)).collect();
+        if names.is_none_or(|names| names.len() != maps.len()) {
+            continue;
+        }
+
+        // Adjacently tagged variants hold their content under one other key,
+        // which unit variants leave out.
+        let mut content = None;
+        let adjacent = maps.iter().all(|m| {
+            m.opt.is_empty()
+                && m.values.is_none()
+                && m.req.keys().filter(|k| *k != tag).all(|k| {
+                    content.get_or_insert(k);
+                    content == Some(k)
+                })
+        });
+        match content {
+            Some(content) if adjacent => {
+                return Tagging::Adjacent(tag.clone(), content.clone());
+            }
+            _ => return Tagging::Internal(tag.clone()),
+        }
+    }
+    Tagging::Untagged
+}
+
+/// What a variant of a generated enum holds.
+enum Body<'v> {
+    Unit,
+    Struct(MapValidator, Option<Extra>),
+    Newtype(&'v Validator),
+}
+
+/// A field of a generated type, and the attributes it needs.
+#[derive(Default)]
+struct Field {
+    serde: Vec<String>,
+    fog: Vec<String>,
+    ty: String,
+}
+
+/// A Rust expression, which is laid out on one line if it's short enough.
+enum Expr {
+    Code(String),
+    /// A base expression followed by method calls.
+    Chain(String, Vec<(String, Vec<Expr>)>),
+    Some(Box<Expr>),
+}
+
+impl Expr {
+    fn flat(&self) -> String {
+        match self {
+            Expr::Code(code) => code.clone(),
+            Expr::Chain(base, calls) => {
+                let mut out = base.clone();
+                for (method, args) in calls {
+                    let args: Vec<String> = args.iter().map(Expr::flat).collect();
+                    write!(out, ".{}({})", method, args.join(", ")).unwrap();
+                }
+                out
+            }
+            Expr::Some(e) => format!("Some({})", e.flat()),
+        }
+    }
+
+    /// Lay out the expression, starting at the given indent level.
+    fn layout(&self, indent: usize) -> String {
+        let flat = self.flat();
+        if flat.len() <= 60 && !flat.contains('\n') {
+            return flat;
+        }
+        match self {
+            Expr::Code(code) => code.replace('\n', &format!("\n{:w$}", "", w = indent * 4)),
+            Expr::Chain(base, calls) => {
+                // Calls on a struct literal line up with the literal.
+                let pad = match base.contains('\n') {
+                    true => indent * 4,
+                    false => (indent + 1) * 4,
+                };
+                let mut out = base.replace('\n', &format!("\n{:w$}", "", w = indent * 4));
+                for (method, args) in calls {
+                    let flat_args: Vec<String> = args.iter().map(Expr::flat).collect();
+                    let call = format!(".{}({})", method, flat_args.join(", "));
+                    if pad + call.len() <= 100 && !call.contains('\n') {
+                        write!(out, "\n{:pad$}{}", "", call).unwrap();
+                        continue;
+                    }
+                    write!(out, "\n{:pad$}.{}(", "", method).unwrap();
+                    for arg in args {
+                        write!(out, "\n{:w$}{},", "", arg.layout(indent + 2), w = pad + 4).unwrap();
+                    }
+                    write!(out, "\n{:pad$})", "").unwrap();
+                }
+                out
+            }
+            Expr::Some(e) => format!("Some({})", e.layout(indent)),
+        }
+    }
+}
+
+fn code(code: impl Into<String>) -> Expr {
+    Expr::Code(code.into())
+}
+
+struct Writer<'a> {
+    schema: &'a SchemaDoc,
+    leaves: Vec<Leaf>,
+    /// The types in the type list that are an `Option<T>`, with the validator
+    /// for `T`.
+    options: BTreeMap<&'a str, &'a Validator>,
+    /// The identifiers of the other types in the type list.
+    idents: BTreeMap<&'a str, String>,
+    /// The types that each type in the type list refers to, directly or not.
+    reach: BTreeMap<&'a str, BTreeSet<String>>,
+    taken: BTreeSet<String>,
+    fn_names: BTreeSet<String>,
+    imports: BTreeSet<&'static str>,
+    /// Whether the type being written has a handwritten `FogValidate`
+    /// implementation, so its fields don't need validator functions.
+    manual: bool,
+    /// Whether any type derives `FogValidate`.
+    derived: bool,
+    notes: Vec<String>,
+    items: Vec<String>,
+    /// Validator functions for the type being written.
+    pending: Vec<String>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(schema: &'a SchemaDoc) -> Self {
+        let leaves = leaves();
+
+        // A type named `Option<T>` is `Option<T>`, as long as `T` is a type
+        // with the right name and validator.
+        let mut options = BTreeMap::new();
+        for (name, v) in schema.types.iter() {
+            let Some(param) = name
+                .strip_prefix("Option<")
+                .and_then(|n| n.strip_suffix('>'))
+            else {
+                continue;
+            };
+            let Validator::Multi(multi) = v else {
+                continue;
+            };
+            if let [Validator::Null, inner] = multi.0.as_slice() {
+                let inner_name = shape(&leaves, inner).map(|s| shape_name(&leaves, &s));
+                if *inner != Validator::Null && inner_name.as_deref() == Some(param) {
+                    options.insert(name.as_str(), inner);
+                }
+            }
+        }
+
+        let mut taken = BTreeSet::new();
+        let mut idents = BTreeMap::new();
+        for name in schema.types.keys() {
+            if !options.contains_key(name.as_str()) {
+                idents.insert(
+                    name.as_str(),
+                    unique(&mut taken, RESERVED, "", identifier(name)),
+                );
+            }
+        }
+
+        let mut reach = BTreeMap::new();
+        for name in schema.types.keys() {
+            let mut found = BTreeSet::new();
+            let mut stack = vec![name.clone()];
+            while let Some(next) = stack.pop() {
+                if let Some(v) = schema.types.get(&next) {
+                    for r in refs(v) {
+                        if found.insert(r.clone()) {
+                            stack.push(r);
+                        }
+                    }
+                }
+            }
+            reach.insert(name.as_str(), found);
+        }
+
+        Writer {
+            schema,
+            leaves,
+            options,
+            idents,
+            reach,
+            taken,
+            fn_names: BTreeSet::new(),
+            imports: BTreeSet::new(),
+            manual: false,
+            derived: false,
+            notes: Vec::new(),
+            items: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn write(mut self) -> String {
+        let schema = self.schema;
+        self.check_reachable();
+
+        for (name, v) in schema.types.iter() {
+            if !self.options.contains_key(name.as_str()) {
+                let ident = self.idents[name.as_str()].clone();
+                self.define(&ident, Some(name), v);
+            }
+        }
+
+        let doc = self.root("Document".to_owned(), &schema.doc);
+        let mut calls = Vec::new();
+        if !schema.name.is_empty() {
+            calls.push(("name".to_owned(), vec![code(format!("{:?}", schema.name))]));
+        }
+        if !schema.description.is_empty() {
+            calls.push((
+                "description".to_owned(),
+                vec![code(format!("{:?}", schema.description))],
+            ));
+        }
+        if schema.version != 0.into() {
+            calls.push(("version".to_owned(), vec![code(int(schema.version))]));
+        }
+        if schema.max_regex != 0 {
+            calls.push((
+                "regexes".to_owned(),
+                vec![code(schema.max_regex.to_string())],
+            ));
+        }
+        if let Some(compress) = self.compress(&schema.doc_compress, "the document") {
+            calls.push(("doc_compress".to_owned(), vec![code(compress)]));
+        }
+        for (key, entry) in schema.entries.iter() {
+            let ty = self.root(format!("{}Entry", identifier(key)), &entry.entry);
+            let compress = match self.compress(&entry.compress, &format!("entry {:?}", key)) {
+                Some(compress) => Expr::Some(Box::new(code(compress))),
+                None => code("None"),
+            };
+            calls.push((
+                format!("entry_add::<{}>", ty),
+                vec![code(format!("{:?}", key)), compress],
+            ));
+        }
+        let schema_fn = Expr::Chain(format!("SchemaGenerator::new::<{}>()", doc), calls).layout(1);
+
+        let mut out = String::new();
+        if schema.name.is_empty() {
+            out.push_str("// Rust types for a fog-pack schema.\n");
+        } else {
+            writeln!(out, "// Rust types for the {:?} schema.", schema.name).unwrap();
+        }
+        for line in schema.description.lines() {
+            writeln!(out, "// {}", line).unwrap();
+        }
+        if !self.notes.is_empty() {
+            out.push_str("//\n");
+            for note in self.notes.iter() {
+                writeln!(out, "// NOTE: {}", note).unwrap();
+            }
+        }
+        out.push('\n');
+
+        self.imports.insert("fog_schemars::SchemaGenerator");
+        if self.derived || self.items.iter().any(|i| i.contains("impl FogValidate")) {
+            self.imports.insert("fog_schemars::FogValidate");
+        }
+        if !self.items.is_empty() {
+            self.imports.insert("serde::Deserialize");
+            self.imports.insert("serde::Serialize");
+        }
+        let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for import in self.imports.iter() {
+            let (path, item) = import.rsplit_once("::").unwrap();
+            groups.entry(path).or_default().push(item);
+        }
+        for (path, items) in groups {
+            match items.as_slice() {
+                [item] => writeln!(out, "use {}::{};", path, item).unwrap(),
+                _ => writeln!(out, "use {}::{{{}}};", path, items.join(", ")).unwrap(),
+            }
+        }
+
+        out.push_str("\n/// Start generating the schema these types describe.\n");
+        out.push_str("pub fn schema() -> SchemaGenerator {\n");
+        writeln!(out, "    {}", schema_fn).unwrap();
+        out.push_str("}\n");
+        for item in self.items.iter() {
+            out.push('\n');
+            out.push_str(item);
+        }
+        out
+    }
+
+    /// Note any types that won't end up in the schema again, as nothing the
+    /// generator starts from refers to them.
+    fn check_reachable(&mut self) {
+        let schema = self.schema;
+        let roots = std::iter::once(&schema.doc).chain(schema.entries.values().map(|e| &e.entry));
+
+        // Types that are only the whole document or entry aren't added to the
+        // type list, so something else needs to refer to them.
+        let mut nested = BTreeSet::new();
+        for v in schema.types.values() {
+            nested.extend(refs(v));
+        }
+        for v in roots.clone() {
+            if !matches!(v, Validator::Ref(_)) {
+                nested.extend(refs(v));
+            }
+        }
+        let mut reachable = BTreeSet::new();
+        for v in roots {
+            for r in refs(v) {
+                if let Some(reach) = self.reach.get(r.as_str()) {
+                    reachable.extend(reach.iter().cloned());
+                }
+                reachable.insert(r);
+            }
+            if let Validator::Ref(name) = v {
+                if !nested.contains(name) {
+                    self.notes.push(format!(
+                        "{:?} is only used as a whole document or entry, so it won't be in the \
+                        type list",
+                        name
+                    ));
+                }
+            }
+        }
+        for name in schema.types.keys() {
+            if !reachable.contains(name) {
+                self.notes.push(format!(
+                    "nothing refers to {:?}, so it won't be in the type list",
+                    name
+                ));
+            }
+        }
+
+        // The default naming strategy drops module paths that aren't needed
+        // to tell types apart.
+        for name in schema.types.keys() {
+            let base = name.split('<').next().unwrap();
+            if let Some((_, ident)) = base.rsplit_once("::") {
+                let shared = schema.types.keys().any(|other| {
+                    let other = other.split('<').next().unwrap();
+                    other != base && other.rsplit(':').next() == Some(ident)
+                });
+                if !shared {
+                    self.notes
+                        .push(format!("{:?} will lose its module path", name));
+                }
+            }
+        }
+    }
+
+    /// The type to use for the document or an entry, defining one if needed.
+    fn root(&mut self, ident: String, v: &Validator) -> String {
+        if let Some(ty) = self.exact(v, None) {
+            return ty;
+        }
+        let ident = unique(&mut self.taken, RESERVED, "", ident);
+        self.define(&ident, None, v);
+        ident
+    }
+
+    fn compress(&mut self, compress: &Compress, what: &str) -> Option<String> {
+        if compress_eq(compress, &Compress::default()) {
+            return None;
+        }
+        self.imports.insert("fog_pack::schema::Compress");
+        match compress {
+            Compress::None => Some("Compress::None".to_owned()),
+            Compress::General { level, .. }
+                if compress_eq(compress, &Compress::new_zstd_general(*level)) =>
+            {
+                Some(format!("Compress::new_zstd_general({})", level))
+            }
+            _ => {
+                self.notes
+                    .push(format!("the compression for {} can't be written out", what));
+                None
+            }
+        }
+    }
+
+    /// Write out a type, with `name` as its name in the type list if it's in
+    /// there.
+    fn define(&mut self, ident: &str, name: Option<&str>, v: &Validator) {
+        // Names with module paths or type parameters can't be set with an
+        // attribute, so those types get a handwritten implementation, as do
+        // structs with settings the derive can't make.
+        self.manual = name.is_some_and(|n| n.contains([':', '<', '>', ',']))
+            || matches!(v, Validator::Map(m) if struct_extra(&self.leaves, m).is_none()
+                && !(m.req.is_empty() && m.opt.is_empty()));
+        self.derived |= !self.manual;
+        let owner = name;
+
+        let mut out = String::new();
+        if self.manual {
+            out.push_str("#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]\n");
+        } else {
+            out.push_str(
+                "#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]\n",
+            );
+        }
+        let mut fog = Vec::new();
+        let mut serde = Vec::new();
+        if let (Some(name), false) = (name, self.manual) {
+            fog.push(format!("rename = {:?}", name));
+        }
+
+        let prefix = snake(ident);
+        let body = match v {
+            // A handwritten validator can have a struct with any fields.
+            Validator::Map(m)
+                if struct_extra(&self.leaves, m).is_some()
+                    || (self.manual && !(m.req.is_empty() && m.opt.is_empty())) =>
+            {
+                let extra = struct_extra(&self.leaves, m).flatten();
+                fog.extend(self.extra_attr(extra));
+                let fields = self.fields(&prefix, owner, m, 0, "pub ");
+                format!("pub struct {} {}\n", ident, fields)
+            }
+            Validator::Enum(e) => {
+                let variants = e
+                    .iter()
+                    .map(|(variant, v)| (variant.clone(), self.body(v.as_ref())))
+                    .collect();
+                self.variants(ident, &prefix, owner, variants)
+            }
+            Validator::Multi(multi) => {
+                let variants = match multi_tagging(&self.leaves, multi) {
+                    Tagging::Internal(tag) => {
+                        serde.push(format!("tag = {:?}", tag));
+                        multi
+                            .iter()
+                            .map(|v| {
+                                let Validator::Map(m) = v else { unreachable!() };
+                                let mut m = (**m).clone();
+                                let tag_v = m.req.remove(&tag).unwrap();
+                                let name = tag_name(&tag_v).unwrap().to_owned();
+                                let body = if m == MapValidator::new() {
+                                    Body::Unit
+                                } else {
+                                    let extra = struct_extra(&self.leaves, &m).unwrap();
+                                    Body::Struct(m, extra)
+                                };
+                                (name, body)
+                            })
+                            .collect()
+                    }
+                    Tagging::Adjacent(tag, content) => {
+                        serde.push(format!("tag = {:?}, content = {:?}", tag, content));
+                        multi
+                            .iter()
+                            .map(|v| {
+                                let Validator::Map(m) = v else { unreachable!() };
+                                let name = tag_name(&m.req[&tag]).unwrap().to_owned();
+                                (name, self.body(m.req.get(&content)))
+                            })
+                            .collect()
+                    }
+                    Tagging::Untagged => {
+                        serde.push("untagged".to_owned());
+                        let mut taken = BTreeSet::new();
+                        multi
+                            .iter()
+                            .map(|v| {
+                                let name = match v {
+                                    Validator::Ref(name) => identifier(name),
+                                    v => kind(v).to_owned(),
+                                };
+                                let name = unique(&mut taken, &["Self"], "", name);
+                                let body = match v {
+                                    Validator::Null => Body::Unit,
+                                    v => self.body(Some(v)),
+                                };
+                                (name, body)
+                            })
+                            .collect()
+                    }
+                };
+                self.variants(ident, &prefix, owner, variants)
+            }
+            v => {
+                // A newtype's validator is its field's full validator, so a
+                // reference has to come from a function.
+                let field = match v {
+                    Validator::Ref(_) if !self.manual => Field {
+                        fog: vec![format!("schema_with = {:?}", self.with_fn(&prefix, v))],
+                        ty: self.closest(v, owner),
+                        ..Field::default()
+                    },
+                    v => self.req_field(v, owner, &prefix),
+                };
+                let attrs = match field.fog.is_empty() {
+                    true => String::new(),
+                    false => format!("#[fog({})] ", field.fog.join(", ")),
+                };
+                format!("pub struct {}({}pub {});\n", ident, attrs, field.ty)
+            }
+        };
+        if !fog.is_empty() {
+            writeln!(out, "#[fog({})]", fog.join(", ")).unwrap();
+        }
+        if !serde.is_empty() {
+            writeln!(out, "#[serde({})]", serde.join(", ")).unwrap();
+        }
+        out.push_str(&body);
+
+        if self.manual {
+            let name_expr = match name {
+                Some(name) => name_expr(name).unwrap_or_else(|| {
+                    self.notes
+                        .push(format!("{:?} can't be written as a `Name`", name));
+                    format!("Name::new(\"\", {:?})", identifier(name))
+                }),
+                None => format!("Name::new(\"\", {:?})", ident),
+            };
+            self.imports.insert("fog_schemars::Name");
+            self.imports.insert("fog_pack::validator::*");
+            let mut uses_gen = false;
+            let validator = self.validator(v, &mut uses_gen).layout(2);
+            let gen = if uses_gen { "gen" } else { "_" };
+            write!(
+                out,
+                "\nimpl FogValidate for {ident} {{\n    \
+                fn validator_name(_: bool) -> Name {{\n        {name_expr}\n    }}\n\n    \
+                fn validator({gen}: &mut SchemaGenerator, _: bool) -> Validator {{\n        \
+                {validator}\n    }}\n}}\n"
+            )
+            .unwrap();
+        }
+        self.manual = false;
+
+        self.items.push(out);
+        self.items.append(&mut self.pending);
+    }
+
+    fn extra_attr(&mut self, extra: Option<Extra>) -> Option<String> {
+        if self.manual {
+            return None;
+        }
+        match extra? {
+            Extra::Any => Some("allow_unknown_fields".to_owned()),
+            Extra::Type(shape) => Some(format!(
+                "extra_values = {:?}",
+                self.shape_type(&shape, None)
+            )),
+        }
+    }
+
+    /// What an enum variant holding a validator looks like, if it holds one.
+    fn body<'v>(&self, v: Option<&'v Validator>) -> Body<'v> {
+        match v {
+            None => Body::Unit,
+            Some(Validator::Map(m)) => match struct_extra(&self.leaves, m) {
+                Some(extra) => Body::Struct((**m).clone(), extra),
+                None => Body::Newtype(v.unwrap()),
+            },
+            Some(v) => Body::Newtype(v),
+        }
+    }
+
+    fn variants(
+        &mut self,
+        ident: &str,
+        prefix: &str,
+        owner: Option<&str>,
+        variants: Vec<(String, Body)>,
+    ) -> String {
+        let mut out = format!("pub enum {} {{\n", ident);
+        let mut taken = BTreeSet::new();
+        for (name, body) in variants {
+            let variant = unique(&mut taken, &["Self"], "", identifier(&name));
+            if variant != name {
+                writeln!(out, "    #[serde(rename = {:?})]", name).unwrap();
+            }
+            let prefix = format!("{}_{}", prefix, snake(&variant));
+            match body {
+                Body::Unit => writeln!(out, "    {},", variant).unwrap(),
+                Body::Struct(m, extra) => {
+                    if let Some(extra) = self.extra_attr(extra) {
+                        writeln!(out, "    #[fog({})]", extra).unwrap();
+                    }
+                    let fields = self.fields(&prefix, owner, &m, 1, "");
+                    writeln!(out, "    {} {},", variant, fields).unwrap();
+                }
+                Body::Newtype(v) => {
+                    let field = self.req_field(v, owner, &prefix);
+                    let attrs = match field.fog.is_empty() {
+                        true => String::new(),
+                        false => format!("#[fog({})] ", field.fog.join(", ")),
+                    };
+                    writeln!(out, "    {}({}{}),", variant, attrs, field.ty).unwrap();
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The fields of a struct, in braces.
+    fn fields(
+        &mut self,
+        prefix: &str,
+        owner: Option<&str>,
+        m: &MapValidator,
+        indent: usize,
+        vis: &str,
+    ) -> String {
+        let mut keys: Vec<(&String, &Validator, bool)> = m
+            .req
+            .iter()
+            .map(|(k, v)| (k, v, true))
+            .chain(m.opt.iter().map(|(k, v)| (k, v, false)))
+            .collect();
+        keys.sort_by(|a, b| a.0.cmp(b.0));
+        if keys.is_empty() {
+            return "{}".to_owned();
+        }
+
+        let pad = (indent + 1) * 4;
+        let mut out = String::from("{\n");
+        let mut taken = BTreeSet::new();
+        // Short struct variants go on one line, as rustfmt would put them.
+        let mut inline = Vec::new();
+        for (key, v, req) in keys {
+            let ident = unique(&mut taken, &[], "_", snake(key));
+            let fn_base = format!("{}_{}", prefix, ident);
+            let mut field = if req {
+                self.req_field(v, owner, &fn_base)
+            } else {
+                self.opt_field(v, owner, &fn_base)
+            };
+            if ident != *key {
+                field.serde.insert(0, format!("rename = {:?}", key));
+            }
+            if !field.serde.is_empty() {
+                writeln!(out, "{:pad$}#[serde({})]", "", field.serde.join(", ")).unwrap();
+            }
+            if !field.fog.is_empty() {
+                writeln!(out, "{:pad$}#[fog({})]", "", field.fog.join(", ")).unwrap();
+            }
+            writeln!(out, "{:pad$}{}{}: {},", "", vis, ident, field.ty).unwrap();
+            if field.serde.is_empty() && field.fog.is_empty() {
+                inline.push(format!("{}{}: {}", vis, ident, field.ty));
+            }
+        }
+        let inline = inline.join(", ");
+        if vis.is_empty() && !out.contains('#') && inline.len() <= 35 {
+            return format!("{{ {} }}", inline);
+        }
+        write!(out, "{:w$}}}", "", w = indent * 4).unwrap();
+        out
+    }
+
+    /// A field that must always be present.
+    fn req_field(&mut self, v: &Validator, owner: Option<&str>, fn_base: &str) -> Field {
+        if let Some(ty) = self.exact(v, owner) {
+            return Field {
+                ty,
+                ..Field::default()
+            };
+        }
+        let mut field = Field {
+            ty: self.closest(v, owner),
+            ..Field::default()
+        };
+        if !self.manual {
+            field
+                .fog
+                .push(format!("schema_with = {:?}", self.with_fn(fn_base, v)));
+        }
+        field
+    }
+
+    /// A field that may be left out.
+    fn opt_field(&mut self, v: &Validator, owner: Option<&str>, fn_base: &str) -> Field {
+        let skip = |skip: &str| {
+            vec![
+                "default".to_owned(),
+                format!("skip_serializing_if = {:?}", skip),
+            ]
+        };
+        if self.manual {
+            return Field {
+                serde: skip("Option::is_none"),
+                ty: format!("Option<{}>", self.closest(v, owner)),
+                ..Field::default()
+            };
+        }
+
+        // An `Option<T>` that isn't skipped keeps its whole validator.
+        if let Validator::Ref(name) = v {
+            if self.options.contains_key(name.as_str()) {
+                return Field {
+                    serde: vec!["default".to_owned()],
+                    fog: vec!["allow_missing_skip".to_owned()],
+                    ty: self.exact(v, owner).unwrap(),
+                };
+            }
+        }
+
+        // Types with their own validator for optional fields.
+        if let Some(leaf) = self.leaves.iter().find(|l| l.skip.is_some() && l.opt == *v) {
+            return Field {
+                serde: skip(leaf.skip.unwrap()),
+                ty: leaf.ty.to_owned(),
+                ..Field::default()
+            };
+        }
+        match v {
+            Validator::Array(a)
+                if *v
+                    == ArrayValidator::new()
+                        .items((*a.items).clone())
+                        .min_len(1)
+                        .build() =>
+            {
+                if let Some(items) = self.exact(&a.items, None) {
+                    return Field {
+                        serde: skip("Vec::is_empty"),
+                        ty: format!("Vec<{}>", items),
+                        ..Field::default()
+                    };
+                }
+            }
+            Validator::Map(m) => {
+                let values = m.values.as_deref().unwrap_or(&Validator::Any).clone();
+                let opt_map = MapValidator::new()
+                    .nin_add(BTreeMap::new())
+                    .values(values.clone())
+                    .build();
+                if *v == opt_map {
+                    if let Some(values) = self.exact(&values, None) {
+                        self.imports.insert("std::collections::BTreeMap");
+                        return Field {
+                            serde: skip("BTreeMap::is_empty"),
+                            ty: format!("BTreeMap<String, {}>", values),
+                            ..Field::default()
+                        };
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        let mut field = self.req_field(v, owner, fn_base);
+        field.serde = skip("Option::is_none");
+        field.ty = format!("Option<{}>", field.ty);
+        field
+    }
+
+    /// The type whose validator is exactly `v`, if there is one. `owner` is
+    /// the type in the type list holding it directly, if any, so types that
+    /// hold themselves can be boxed.
+    fn exact(&mut self, v: &Validator, owner: Option<&str>) -> Option<String> {
+        let shape = shape(&self.leaves, v)?;
+        Some(self.shape_type(&shape, owner))
+    }
+
+    fn shape_type(&mut self, shape: &Shape, owner: Option<&str>) -> String {
+        match shape {
+            Shape::Ref(name) => self.ref_type(name, owner),
+            Shape::Leaf(i) => {
+                let leaf = &self.leaves[*i];
+                if let Some(import) = leaf.import {
+                    self.imports.insert(import);
+                }
+                leaf.ty.to_owned()
+            }
+            Shape::Vec(s) => format!("Vec<{}>", self.shape_type(s, None)),
+            Shape::Map(s) => {
+                self.imports.insert("std::collections::BTreeMap");
+                format!("BTreeMap<String, {}>", self.shape_type(s, None))
+            }
+        }
+    }
+
+    fn ref_type(&mut self, name: &str, owner: Option<&str>) -> String {
+        if let Some(inner) = self.options.get(name).copied() {
+            return format!("Option<{}>", self.exact(inner, owner).unwrap());
+        }
+        let Some(ident) = self.idents.get(name).cloned() else {
+            self.notes
+                .push(format!("{:?} isn't in the type list", name));
+            self.imports.insert("fog_pack::types::Value");
+            return "Value".to_owned();
+        };
+        let recursive = owner.is_some_and(|o| o == name || self.reach[name].contains(o));
+        if recursive {
+            format!("Box<{}>", ident)
+        } else {
+            ident
+        }
+    }
+
+    /// The type closest to having the validator `v`, for a field that gets
+    /// its validator from a function.
+    fn closest(&mut self, v: &Validator, owner: Option<&str>) -> String {
+        if let Some(ty) = self.exact(v, owner) {
+            return ty;
+        }
+        let found = match v {
+            // The smallest integer type that holds every allowed value.
+            Validator::Int(int) => self.leaves.iter().position(|l| match &l.validator {
+                Validator::Int(l) => l.min <= int.min && l.max >= int.max,
+                _ => false,
+            }),
+            Validator::Bin(_) => return "serde_bytes::ByteBuf".to_owned(),
+            Validator::Array(a) if a.prefix.is_empty() => {
+                return format!("Vec<{}>", self.closest(&a.items, None));
+            }
+            Validator::Map(m) if m.req.is_empty() && m.opt.is_empty() => {
+                let values = m.values.as_deref().unwrap_or(&Validator::Any);
+                let values = self.closest(values, None);
+                self.imports.insert("std::collections::BTreeMap");
+                return format!("BTreeMap<String, {}>", values);
+            }
+            v => self
+                .leaves
+                .iter()
+                .position(|l| std::mem::discriminant(&l.validator) == std::mem::discriminant(v)),
+        };
+        let leaf = found.unwrap_or(self.leaves.len() - 1);
+        self.shape_type(&Shape::Leaf(leaf), None)
+    }
+
+    /// Write a function building the validator `v`, returning its name.
+    fn with_fn(&mut self, base: &str, v: &Validator) -> String {
+        let mut name = base.to_owned();
+        let mut n = 2;
+        while !self.fn_names.insert(name.clone()) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.imports.insert("fog_pack::validator::*");
+        let mut uses_gen = false;
+        let validator = self.validator(v, &mut uses_gen).layout(1);
+        let gen = if uses_gen { "gen" } else { "_" };
+        self.pending.push(format!(
+            "fn {}({}: &mut SchemaGenerator) -> Validator {{\n    {}\n}}\n",
+            name, gen, validator
+        ));
+        name
+    }
+
+    /// Code building a validator, with the settings it has that aren't the
+    /// default.
+    fn validator(&mut self, v: &Validator, uses_gen: &mut bool) -> Expr {
+        // Types that have exactly this validator can provide it.
+        if let Some(ty) = self.exact(v, None) {
+            *uses_gen = true;
+            return code(format!("gen.type_add::<{}>()", ty));
+        }
+        let mut c = Calls::default();
+        let base = match v {
+            Validator::Null => return code("Validator::Null"),
+            Validator::Any => return code("Validator::Any"),
+            Validator::Ref(_) => unreachable!(),
+            Validator::Bool(v) => {
+                c.comment(&v.comment);
+                c.list("in_add", &v.in_list, |v| v.to_string());
+                c.list("nin_add", &v.nin_list, |v| v.to_string());
+                c.flag("query", v.query);
+                "BoolValidator::new()".to_owned()
+            }
+            Validator::Int(v) => {
+                let d = IntValidator::new();
+                c.comment(&v.comment);
+                if v.bits_set != 0 {
+                    c.push("bits_set", format!("{:#x}", v.bits_set));
+                }
+                if v.bits_clr != 0 {
+                    c.push("bits_clr", format!("{:#x}", v.bits_clr));
+                }
+                if v.max != d.max {
+                    c.push("max", int(v.max));
+                }
+                if v.min != d.min {
+                    c.push("min", int(v.min));
+                }
+                c.flag("ex_max", v.ex_max);
+                c.flag("ex_min", v.ex_min);
+                c.list("in_add", &v.in_list, |v| int(*v));
+                c.list("nin_add", &v.nin_list, |v| int(*v));
+                c.flag("query", v.query);
+                c.flag("bit", v.bit);
+                c.flag("ord", v.ord);
+                "IntValidator::new()".to_owned()
+            }
+            Validator::F32(v) => {
+                c.comment(&v.comment);
+                if !v.max.is_nan() {
+                    c.push("max", float(v.max as f64, "f32"));
+                }
+                if !v.min.is_nan() {
+                    c.push("min", float(v.min as f64, "f32"));
+                }
+                c.flag("ex_max", v.ex_max);
+                c.flag("ex_min", v.ex_min);
+                c.list("in_add", &v.in_list, |v| float(*v as f64, "f32"));
+                c.list("nin_add", &v.nin_list, |v| float(*v as f64, "f32"));
+                c.flag("query", v.query);
+                c.flag("ord", v.ord);
+                "F32Validator::new()".to_owned()
+            }
+            Validator::F64(v) => {
+                c.comment(&v.comment);
+                if !v.max.is_nan() {
+                    c.push("max", float(v.max, "f64"));
+                }
+                if !v.min.is_nan() {
+                    c.push("min", float(v.min, "f64"));
+                }
+                c.flag("ex_max", v.ex_max);
+                c.flag("ex_min", v.ex_min);
+                c.list("in_add", &v.in_list, |v| float(*v, "f64"));
+                c.list("nin_add", &v.nin_list, |v| float(*v, "f64"));
+                c.flag("query", v.query);
+                c.flag("ord", v.ord);
+                "F64Validator::new()".to_owned()
+            }
+            Validator::Bin(v) => {
+                let d = BinValidator::new();
+                c.comment(&v.comment);
+                if !v.bits_set.is_empty() {
+                    c.push("bits_set", bytes(&v.bits_set));
+                }
+                if !v.bits_clr.is_empty() {
+                    c.push("bits_clr", bytes(&v.bits_clr));
+                }
+                if v.max != d.max {
+                    c.push("max", bytes(&v.max));
+                }
+                if v.min != d.min {
+                    c.push("min", bytes(&v.min));
+                }
+                c.flag("ex_max", v.ex_max);
+                c.flag("ex_min", v.ex_min);
+                c.len(v.max_len, v.min_len);
+                c.list("in_add", &v.in_list, |v| bytes(v));
+                c.list("nin_add", &v.nin_list, |v| bytes(v));
+                c.flag("query", v.query);
+                c.flag("bit", v.bit);
+                c.flag("ord", v.ord);
+                c.flag("size", v.size);
+                "BinValidator::new()".to_owned()
+            }
+            Validator::Str(v) => {
+                c.calls = str_calls(v).calls;
+                c.push("build", None);
+                return Expr::Chain("StrValidator::new()".to_owned(), c.calls);
+            }
+            Validator::Array(v) => {
+                c.comment(&v.comment);
+                for contains in v.contains.iter() {
+                    let contains = self.validator(contains, uses_gen);
+                    c.calls.push(("contains_add".to_owned(), vec![contains]));
+                }
+                if *v.items != Validator::Any {
+                    let items = self.validator(&v.items, uses_gen);
+                    c.calls.push(("items".to_owned(), vec![items]));
+                }
+                for prefix in v.prefix.iter() {
+                    let prefix = self.validator(prefix, uses_gen);
+                    c.calls.push(("prefix_add".to_owned(), vec![prefix]));
+                }
+                c.len(v.max_len, v.min_len);
+                for list in v.in_list.iter() {
+                    let values = self.values(list);
+                    c.push("in_add", format!("vec![{}]", values));
+                }
+                for list in v.nin_list.iter() {
+                    let values = self.values(list);
+                    c.push("nin_add", format!("vec![{}]", values));
+                }
+                c.flag("unique", v.unique);
+                c.flag("query", v.query);
+                c.flag("array", v.array);
+                c.flag("contains_ok", v.contains_ok);
+                c.flag("unique_ok", v.unique_ok);
+                c.flag("size", v.size);
+                "ArrayValidator::new()".to_owned()
+            }
+            Validator::Map(v) => {
+                c.comment(&v.comment);
+                if let Some(values) = &v.values {
+                    let values = self.validator(values, uses_gen);
+                    c.calls.push(("values".to_owned(), vec![values]));
+                }
+                for (key, req) in v.req.iter() {
+                    let req = self.validator(req, uses_gen);
+                    c.calls
+                        .push(("req_add".to_owned(), vec![code(format!("{:?}", key)), req]));
+                }
+                for (key, opt) in v.opt.iter() {
+                    let opt = self.validator(opt, uses_gen);
+                    c.calls
+                        .push(("opt_add".to_owned(), vec![code(format!("{:?}", key)), opt]));
+                }
+                if let Some(keys) = &v.keys {
+                    let keys = str_calls(keys);
+                    c.calls.push((
+                        "keys".to_owned(),
+                        vec![Expr::Chain("StrValidator::new()".to_owned(), keys.calls)],
+                    ));
+                }
+                c.len(v.max_len, v.min_len);
+                for map in v.in_list.iter() {
+                    let map = self.map_value(map);
+                    c.push("in_add", map);
+                }
+                for map in v.nin_list.iter() {
+                    let map = self.map_value(map);
+                    c.push("nin_add", map);
+                }
+                c.list("same_len_add", &v.same_len, |v| format!("{:?}", v));
+                c.flag("query", v.query);
+                c.flag("size", v.size);
+                c.flag("map_ok", v.map_ok);
+                c.flag("same_len_ok", v.same_len_ok);
+                "MapValidator::new()".to_owned()
+            }
+            Validator::Time(v) => {
+                let d = TimeValidator::new();
+                c.comment(&v.comment);
+                if v.max != d.max {
+                    let max = self.timestamp(&v.max);
+                    c.push("max", max);
+                }
+                if v.min != d.min {
+                    let min = self.timestamp(&v.min);
+                    c.push("min", min);
+                }
+                c.flag("ex_max", v.ex_max);
+                c.flag("ex_min", v.ex_min);
+                for t in v.in_list.iter() {
+                    let t = self.timestamp(t);
+                    c.push("in_add", t);
+                }
+                for t in v.nin_list.iter() {
+                    let t = self.timestamp(t);
+                    c.push("nin_add", t);
+                }
+                c.flag("query", v.query);
+                c.flag("ord", v.ord);
+                "TimeValidator::new()".to_owned()
+            }
+            Validator::Hash(v) => {
+                if let Some(link) = &v.link {
+                    let link = self.validator(link, uses_gen);
+                    c.calls.push(("link".to_owned(), vec![link]));
+                }
+                for schema in v.schema.iter() {
+                    match schema {
+                        Some(hash) => {
+                            let hash = self.base58("Hash", &hash.to_base58());
+                            c.push("schema_add", hash);
+                        }
+                        None => c.push("schema_self", None),
+                    }
+                }
+                for hash in v.in_list.iter() {
+                    let hash = self.base58("Hash", &hash.to_base58());
+                    c.push("in_add", hash);
+                }
+                for hash in v.nin_list.iter() {
+                    let hash = self.base58("Hash", &hash.to_base58());
+                    c.push("nin_add", hash);
+                }
+                c.flag("query", v.query);
+                c.flag("link_ok", v.link_ok);
+                c.flag("schema_ok", v.schema_ok);
+                // There's no builder method for a hash validator's comment.
+                if v.comment.is_empty() {
+                    "HashValidator::new()".to_owned()
+                } else {
+                    format!(
+                        "HashValidator {{\n    comment: {:?}.into(),\n    ..HashValidator::new()\n}}",
+                        v.comment
+                    )
+                }
+            }
+            Validator::Identity(v) => {
+                c.comment(&v.comment);
+                for id in v.in_list.iter() {
+                    let id = self.base58("Identity", &id.to_base58());
+                    c.push("in_add", id);
+                }
+                for id in v.nin_list.iter() {
+                    let id = self.base58("Identity", &id.to_base58());
+                    c.push("nin_add", id);
+                }
+                c.flag("query", v.query);
+                "IdentityValidator::new()".to_owned()
+            }
+            Validator::StreamId(v) => {
+                c.comment(&v.comment);
+                for id in v.in_list.iter() {
+                    let id = self.base58("StreamId", &id.to_base58());
+                    c.push("in_add", id);
+                }
+                for id in v.nin_list.iter() {
+                    let id = self.base58("StreamId", &id.to_base58());
+                    c.push("nin_add", id);
+                }
+                c.flag("query", v.query);
+                "StreamIdValidator::new()".to_owned()
+            }
+            Validator::LockId(v) => {
+                c.comment(&v.comment);
+                for id in v.in_list.iter() {
+                    let id = self.base58("LockId", &id.to_base58());
+                    c.push("in_add", id);
+                }
+                for id in v.nin_list.iter() {
+                    let id = self.base58("LockId", &id.to_base58());
+                    c.push("nin_add", id);
+                }
+                c.flag("query", v.query);
+                "LockIdValidator::new()".to_owned()
+            }
+            Validator::DataLockbox(v) => {
+                c.lockbox(&v.comment, v.max_len, v.min_len, v.size);
+                "DataLockboxValidator::new()".to_owned()
+            }
+            Validator::IdentityLockbox(v) => {
+                c.lockbox(&v.comment, v.max_len, v.min_len, v.size);
+                "IdentityLockboxValidator::new()".to_owned()
+            }
+            Validator::StreamLockbox(v) => {
+                c.lockbox(&v.comment, v.max_len, v.min_len, v.size);
+                "StreamLockboxValidator::new()".to_owned()
+            }
+            Validator::LockLockbox(v) => {
+                c.lockbox(&v.comment, v.max_len, v.min_len, v.size);
+                "LockLockboxValidator::new()".to_owned()
+            }
+            Validator::Enum(v) => {
+                for (variant, v) in v.iter() {
+                    let v = match v {
+                        Some(v) => Expr::Some(Box::new(self.validator(v, uses_gen))),
+                        None => code("None"),
+                    };
+                    c.calls
+                        .push(("insert".to_owned(), vec![code(format!("{:?}", variant)), v]));
+                }
+                "EnumValidator::new()".to_owned()
+            }
+            Validator::Multi(v) => {
+                for v in v.iter() {
+                    let v = self.validator(v, uses_gen);
+                    c.calls.push(("push".to_owned(), vec![v]));
+                }
+                "MultiValidator::new()".to_owned()
+            }
+        };
+        c.push("build", None);
+        Expr::Chain(base, c.calls)
+    }
+
+    fn values(&mut self, values: &[Value]) -> String {
+        let values: Vec<String> = values.iter().map(|v| self.value(v)).collect();
+        values.join(", ")
+    }
+
+    fn map_value(&mut self, map: &BTreeMap<String, Value>) -> String {
+        self.imports.insert("std::collections::BTreeMap");
+        let pairs: Vec<String> = map
+            .iter()
+            .map(|(k, v)| format!("({:?}.to_owned(), {})", k, self.value(v)))
+            .collect();
+        format!("BTreeMap::from([{}])", pairs.join(", "))
+    }
+
+    fn value(&mut self, v: &Value) -> String {
+        self.imports.insert("fog_pack::types::Value");
+        match v {
+            Value::Null => "Value::Null".to_owned(),
+            Value::Bool(v) => format!("Value::Bool({})", v),
+            Value::Int(v) => format!("Value::Int({}.into())", int(*v)),
+            Value::Str(v) => format!("Value::Str({:?}.to_owned())", v),
+            Value::F32(v) => format!("Value::F32({})", float(*v as f64, "f32")),
+            Value::F64(v) => format!("Value::F64({})", float(*v, "f64")),
+            Value::Bin(v) => format!("Value::Bin({})", bytes(v)),
+            Value::Array(v) => format!("Value::Array(vec![{}])", self.values(v)),
+            Value::Map(v) => format!("Value::Map({})", self.map_value(v)),
+            Value::Timestamp(v) => format!("Value::Timestamp({})", self.timestamp(v)),
+            Value::Hash(v) => format!("Value::Hash({})", self.base58("Hash", &v.to_base58())),
+            Value::Identity(v) => format!(
+                "Value::Identity({})",
+                self.base58("Identity", &v.to_base58())
+            ),
+            Value::LockId(v) => {
+                format!("Value::LockId({})", self.base58("LockId", &v.to_base58()))
+            }
+            Value::StreamId(v) => format!(
+                "Value::StreamId({})",
+                self.base58("StreamId", &v.to_base58())
+            ),
+            Value::DataLockbox(_)
+            | Value::IdentityLockbox(_)
+            | Value::StreamLockbox(_)
+            | Value::LockLockbox(_) => {
+                self.notes
+                    .push("lockbox values can't be written out, so they're null".to_owned());
+                "Value::Null".to_owned()
+            }
+        }
+    }
+
+    fn timestamp(&mut self, t: &Timestamp) -> String {
+        let (secs, nanos) = (t.timestamp_utc(), t.timestamp_subsec_nanos());
+        if Timestamp::from_utc(secs, nanos).as_ref() != Some(t) {
+            self.notes
+                .push(format!("the time {} can't be written out", t));
+        }
+        self.imports.insert("fog_pack::types::Timestamp");
+        format!("Timestamp::from_utc({}, {}).unwrap()", secs, nanos)
+    }
+
+    fn base58(&mut self, ty: &'static str, encoded: &str) -> String {
+        self.imports.insert(match ty {
+            "Hash" => "fog_pack::types::Hash",
+            "Identity" => "fog_pack::types::Identity",
+            "StreamId" => "fog_pack::types::StreamId",
+            _ => "fog_pack::types::LockId",
+        });
+        format!("{}::from_base58({:?}).unwrap()", ty, encoded)
+    }
+}
+
+/// Builder method calls for a validator.
+#[derive(Default)]
+struct Calls {
+    calls: Vec<(String, Vec<Expr>)>,
+}
+
+impl Calls {
+    fn push(&mut self, method: &'static str, arg: impl Into<Option<String>>) {
+        self.calls.push((
+            method.to_owned(),
+            arg.into().into_iter().map(code).collect(),
+        ));
+    }
+
+    fn comment(&mut self, comment: &str) {
+        if !comment.is_empty() {
+            self.push("comment", format!("{:?}", comment));
+        }
+    }
+
+    fn flag(&mut self, method: &'static str, set: bool) {
+        if set {
+            self.push(method, "true".to_owned());
+        }
+    }
+
+    fn len(&mut self, max: u32, min: u32) {
+        if max != u32::MAX {
+            self.push("max_len", max.to_string());
+        }
+        if min != 0 {
+            self.push("min_len", min.to_string());
+        }
+    }
+
+    fn list<T>(&mut self, method: &'static str, items: &[T], f: impl Fn(&T) -> String) {
+        for item in items {
+            self.push(method, f(item));
+        }
+    }
+
+    fn lockbox(&mut self, comment: &str, max_len: u32, min_len: u32, size: bool) {
+        self.comment(comment);
+        self.len(max_len, min_len);
+        self.flag("size", size);
+    }
+}
+
+/// The builder calls for a string validator, not including `build`, as map
+/// keys take the validator unbuilt.
+fn str_calls(v: &StrValidator) -> Calls {
+    let mut c = Calls::default();
+    c.comment(&v.comment);
+    c.len(v.max_len, v.min_len);
+    if v.max_char != u32::MAX {
+        c.push("max_char", v.max_char.to_string());
+    }
+    if v.min_char != 0 {
+        c.push("min_char", v.min_char.to_string());
+    }
+    match v.normalize {
+        Normalize::None => (),
+        Normalize::NFC => c.push("normalize", "Normalize::NFC".to_owned()),
+        Normalize::NFKC => c.push("normalize", "Normalize::NFKC".to_owned()),
+    }
+    if let Some(regex) = &v.matches {
+        c.push(
+            "matches",
+            format!("regex::Regex::new({:?}).unwrap()", regex.as_str()),
+        );
+    }
+    c.list("in_add", &v.in_list, |v| format!("{:?}", v));
+    c.list("nin_add", &v.nin_list, |v| format!("{:?}", v));
+    c.list("ban_prefix_add", &v.ban_prefix, |v| format!("{:?}", v));
+    c.list("ban_suffix_add", &v.ban_suffix, |v| format!("{:?}", v));
+    if !v.ban_char.is_empty() {
+        c.push("ban_char", format!("{:?}", v.ban_char));
+    }
+    c.flag("query", v.query);
+    c.flag("regex", v.regex);
+    c.flag("ban", v.ban);
+    c.flag("size", v.size);
+    c
+}
+
+/// Every type a validator refers to directly.
+fn refs(v: &Validator) -> Vec<String> {
+    let mut found = Vec::new();
+    visit_refs_mut(&mut v.clone(), &mut |r| found.push(r.clone()));
+    found
+}
+
+/// The kind of a validator, for naming untagged variants.
+fn kind(v: &Validator) -> &'static str {
+    match v {
+        Validator::Null => "Null",
+        Validator::Any => "Any",
+        Validator::Ref(_) => "Ref",
+        Validator::Bool(_) => "Bool",
+        Validator::Int(_) => "Int",
+        Validator::F32(_) => "F32",
+        Validator::F64(_) => "F64",
+        Validator::Bin(_) => "Bin",
+        Validator::Str(_) => "Str",
+        Validator::Array(_) => "Array",
+        Validator::Map(_) => "Map",
+        Validator::Time(_) => "Time",
+        Validator::Hash(_) => "Hash",
+        Validator::Identity(_) => "Identity",
+        Validator::StreamId(_) => "StreamId",
+        Validator::LockId(_) => "LockId",
+        Validator::DataLockbox(_) => "DataLockbox",
+        Validator::IdentityLockbox(_) => "IdentityLockbox",
+        Validator::StreamLockbox(_) => "StreamLockbox",
+        Validator::LockLockbox(_) => "LockLockbox",
+        Validator::Multi(_) => "Multi",
+        Validator::Enum(_) => "Enum",
+    }
+}
+
+/// Code making a `Name` that displays as `name`, if it's one that a `Name`
+/// can display as.
+fn name_expr(name: &str) -> Option<String> {
+    match parse_name(name)? {
+        (expr, "") => Some(expr),
+        _ => None,
+    }
+}
+
+fn parse_name(s: &str) -> Option<(String, &str)> {
+    let end = s.find([',', '<', '>']).unwrap_or(s.len());
+    let (full, mut rest) = s.split_at(end);
+    let (path, ident) = full.rsplit_once("::").unwrap_or(("", full));
+    if ident.is_empty() || ident.contains(':') || path.ends_with(':') {
+        return None;
+    }
+    let mut params = Vec::new();
+    if let Some(r) = rest.strip_prefix('<') {
+        rest = r;
+        loop {
+            let (param, r) = parse_name(rest)?;
+            params.push(param);
+            match r.strip_prefix(',') {
+                Some(r) => rest = r,
+                None => {
+                    rest = r.strip_prefix('>')?;
+                    break;
+                }
+            }
+        }
+    }
+    let expr = if params.is_empty() {
+        format!("Name::new({:?}, {:?})", path, ident)
+    } else {
+        format!(
+            "Name::with_types({:?}, {:?}, vec![{}])",
+            path,
+            ident,
+            params.join(", ")
+        )
+    };
+    Some((expr, rest))
+}
+
+/// An integer literal, using the bounds of the integer types where they fit.
+fn int(i: Integer) -> String {
+    const BOUNDS: &[(i64, &str)] = &[
+        (i8::MIN as i64, "i8::MIN"),
+        (i8::MAX as i64, "i8::MAX"),
+        (i16::MIN as i64, "i16::MIN"),
+        (i16::MAX as i64, "i16::MAX"),
+        (i32::MIN as i64, "i32::MIN"),
+        (i32::MAX as i64, "i32::MAX"),
+        (i64::MIN, "i64::MIN"),
+        (i64::MAX, "i64::MAX"),
+        (u8::MAX as i64, "u8::MAX"),
+        (u16::MAX as i64, "u16::MAX"),
+        (u32::MAX as i64, "u32::MAX"),
+    ];
+    match i.as_i64() {
+        Some(i) => match BOUNDS.iter().find(|(b, _)| *b == i) {
+            Some((_, name)) => (*name).to_owned(),
+            None if i32::try_from(i).is_ok() => i.to_string(),
+            None => format!("{}i64", i),
+        },
+        None => match i.as_u64() {
+            Some(u64::MAX) => "u64::MAX".to_owned(),
+            _ => format!("{}u64", i),
+        },
+    }
+}
+
+fn float(f: f64, ty: &str) -> String {
+    if f.is_nan() {
+        format!("{}::NAN", ty)
+    } else if f == f64::INFINITY {
+        format!("{}::INFINITY", ty)
+    } else if f == f64::NEG_INFINITY {
+        format!("{}::NEG_INFINITY", ty)
+    } else if ty == "f32" {
+        format!("{:?}", f as f32)
+    } else {
+        format!("{:?}", f)
+    }
+}
+
+fn bytes(b: &[u8]) -> String {
+    match b.split_first() {
+        None => "Vec::<u8>::new()".to_owned(),
+        Some((first, rest)) => {
+            let mut s = format!("vec![{:#04x}u8", first);
+            for b in rest {
+                write!(s, ", {:#04x}", b).unwrap();
+            }
+            s.push(']');
+            s
+        }
+    }
+}
+
+/// Turn a name into a field or function identifier, in snake case.
+fn snake(name: &str) -> String {
+    let mut ident = String::new();
+    let mut lower = false;
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && lower {
+                ident.push('_');
+            }
+            lower = !c.is_ascii_uppercase();
+            ident.push(c.to_ascii_lowercase());
+        } else {
+            if !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            lower = false;
+        }
+    }
+    while ident.ends_with('_') {
+        ident.pop();
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+/// Type names that the generated code already uses.
+const RESERVED: &[&str] = &[
+    "ArrayValidator",
+    "BTreeMap",
+    "BinValidator",
+    "BoolValidator",
+    "Box",
+    "Compress",
+    "DataLockbox",
+    "DataLockboxValidator",
+    "Deserialize",
+    "EnumValidator",
+    "Err",
+    "F32Validator",
+    "F64Validator",
+    "FogValidate",
+    "Hash",
+    "HashValidator",
+    "Identity",
+    "IdentityLockbox",
+    "IdentityLockboxValidator",
+    "IdentityValidator",
+    "IntValidator",
+    "Integer",
+    "LockId",
+    "LockIdValidator",
+    "LockLockbox",
+    "LockLockboxValidator",
+    "MapValidator",
+    "MultiValidator",
+    "Name",
+    "NonZeroI16",
+    "NonZeroI32",
+    "NonZeroI64",
+    "NonZeroI8",
+    "NonZeroU16",
+    "NonZeroU32",
+    "NonZeroU64",
+    "NonZeroU8",
+    "None",
+    "Normalize",
+    "Ok",
+    "Option",
+    "Result",
+    "SchemaGenerator",
+    "Self",
+    "Serialize",
+    "Some",
+    "StrValidator",
+    "StreamId",
+    "StreamIdValidator",
+    "StreamLockbox",
+    "StreamLockboxValidator",
+    "String",
+    "TimeValidator",
+    "Timestamp",
+    "Validator",
+    "Value",
+    "Vec",
+];
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
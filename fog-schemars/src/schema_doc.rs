@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+
+use fog_pack::{document::Document, schema::Compress, types::Integer, validator::Validator};
+use serde::{Deserialize, Serialize};
+
+/// The contents of a schema document, mirroring how fog-pack encodes a
+/// schema. fog-pack doesn't expose this, so we keep our own copy for looking
+/// inside already built schemas.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SchemaDoc {
+    pub doc: Validator,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub doc_compress: Compress,
+    #[serde(default)]
+    pub entries: BTreeMap<String, SchemaEntry>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub types: BTreeMap<String, Validator>,
+    #[serde(default)]
+    pub version: Integer,
+    #[serde(default)]
+    pub max_regex: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SchemaEntry {
+    pub entry: Validator,
+    #[serde(default)]
+    pub compress: Compress,
+}
+
+impl SchemaDoc {
+    pub fn from_doc(doc: &Document) -> fog_pack::error::Result<Self> {
+        doc.deserialize()
+    }
+}
+
+/// Check if two compression settings are the same. `Compress` doesn't
+/// implement `PartialEq`, but its debug output covers all of its settings.
+pub(crate) fn compress_eq(a: &Compress, b: &Compress) -> bool {
+    format!("{:?}", a) == format!("{:?}", b)
+}
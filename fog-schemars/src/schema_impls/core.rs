@@ -1,6 +1,6 @@
 use std::ops::Bound;
 
-use crate::{SchemaGenerator, FogValidate, Name};
+use crate::{FogValidate, Name, SchemaGenerator};
 use fog_pack::types::Value;
 use fog_pack::validator::*;
 
@@ -46,16 +46,17 @@ impl<T: FogValidate> FogValidate for Option<T> {
 
     fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
         if opt {
-            gen.type_add::<T>()
+            // This shares the inner type's name, so it has to produce exactly
+            // the same validator.
+            gen.type_validator::<T>(false)
         } else {
             let v = gen.type_add::<T>();
             if let Validator::Null = v {
-                panic!(
+                gen.report_error(
                     "Started generating an Option<()>, which cannot roundtrip \
-                    correctly in fog-pack. Consider making a boolean instead. \
-                    This happened in the type {}",
-                    std::any::type_name::<T>()
+                    correctly in fog-pack. Consider making a boolean instead.",
                 );
+                return v;
             }
             MultiValidator::new().push(Validator::Null).push(v).build()
         }
@@ -112,8 +113,8 @@ where
         if opt {
             let default = Value::from(Idx::default());
             MapValidator::new()
-                .req_add("start", Idx::validator(gen, false))
-                .req_add("end", Idx::validator(gen, false))
+                .req_add("start", gen.type_validator::<Idx>(false))
+                .req_add("end", gen.type_validator::<Idx>(false))
                 .nin_add([
                     (String::from("start"), default.clone()),
                     (String::from("end"), default),
@@ -121,8 +122,8 @@ where
                 .build()
         } else {
             MapValidator::new()
-                .req_add("start", Idx::validator(gen, false))
-                .req_add("end", Idx::validator(gen, false))
+                .req_add("start", gen.type_validator::<Idx>(false))
+                .req_add("end", gen.type_validator::<Idx>(false))
                 .build()
         }
     }
@@ -143,8 +144,8 @@ where
 
     fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
         MapValidator::new()
-            .req_add("start", Idx::validator(gen, false))
-            .req_add("end", Idx::validator(gen, false))
+            .req_add("start", gen.type_validator::<Idx>(false))
+            .req_add("end", gen.type_validator::<Idx>(false))
             .build()
     }
 }
@@ -160,7 +161,7 @@ where
 
     fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
         MapValidator::new()
-            .req_add("start", Idx::validator(gen, false))
+            .req_add("start", gen.type_validator::<Idx>(false))
             .build()
     }
 }
@@ -176,7 +177,7 @@ where
 
     fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
         MapValidator::new()
-            .req_add("end", Idx::validator(gen, false))
+            .req_add("end", gen.type_validator::<Idx>(false))
             .build()
     }
 }
@@ -4,6 +4,7 @@ use crate::{FogValidate, Name, SchemaGenerator};
 use fog_pack::types::*;
 use fog_pack::validator::*;
 
+#[allow(dead_code)]
 struct ByteBuf(Vec<u8>);
 impl FogValidate for ByteBuf {
     no_ref_validator!();
@@ -31,12 +32,13 @@ impl FogValidate for Integer {
         )
     }
 
-    fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-        if opt {
+    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+        let validator = if opt {
             IntValidator::new().nin_add(0).build()
         } else {
             IntValidator::new().build()
-        }
+        };
+        gen.queries().apply(validator)
     }
 }
 
@@ -49,8 +51,8 @@ macro_rules! fogpack_simple {
                 Name::new("fog_pack::types", stringify!($type))
             }
 
-            fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
-                <$validator>::new().build()
+            fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+                gen.queries().apply(<$validator>::new().build())
             }
         }
     };
@@ -155,7 +157,7 @@ macro_rules! float_nan_default {
                         .max(<$float>::INFINITY)
                         .build()
                 } else {
-                    <$float>::validator(gen, false)
+                    gen.type_validator::<$float>(false)
                 }
             }
         }
@@ -206,7 +208,7 @@ macro_rules! type_skip_default {
                 if opt {
                     <$v>::new().nin_add($skip).build()
                 } else {
-                    <$type>::validator(gen, false)
+                    gen.type_validator::<$type>(false)
                 }
             }
         }
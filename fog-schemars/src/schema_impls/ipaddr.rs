@@ -21,7 +21,7 @@ impl FogValidate for std::net::IpAddr {
 
 impl FogValidate for std::net::SocketAddr {
     fn validator_name(_: bool) -> Name {
-        Name::new("std::net", "IpAddr")
+        Name::new("std::net", "SocketAddr")
     }
 
     fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
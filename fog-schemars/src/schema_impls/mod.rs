@@ -35,7 +35,7 @@ macro_rules! forward_impl {
             }
 
             fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
-                <$target>::validator(gen, opt)
+                gen.type_validator::<$target>(opt)
             }
         }
     };
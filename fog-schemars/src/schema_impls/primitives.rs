@@ -11,12 +11,13 @@ impl FogValidate for bool {
         Name::new("", if opt { "true" } else { "bool" })
     }
 
-    fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-        if opt {
+    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+        let validator = if opt {
             BoolValidator::new().nin_add(false).build()
         } else {
             BoolValidator::new().build()
-        }
+        };
+        gen.queries().apply(validator)
     }
 }
 
@@ -28,12 +29,13 @@ impl FogValidate for str {
         Name::new("", if opt { "OptStr" } else { "str" })
     }
 
-    fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-        if opt {
+    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+        let validator = if opt {
             StrValidator::new().min_len(1).build()
         } else {
             StrValidator::new().build()
-        }
+        };
+        gen.queries().apply(validator)
     }
 }
 
@@ -47,12 +49,13 @@ impl FogValidate for f32 {
         Name::new("", if opt { "OptF32" } else { "f32" })
     }
 
-    fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-        if opt {
+    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+        let validator = if opt {
             F32Validator::new().nin_add(f32::default()).build()
         } else {
             F32Validator::new().build()
-        }
+        };
+        gen.queries().apply(validator)
     }
 }
 
@@ -64,12 +67,13 @@ impl FogValidate for f64 {
         Name::new("", if opt { "OptF64" } else { "f64" })
     }
 
-    fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-        if opt {
+    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+        let validator = if opt {
             F64Validator::new().nin_add(f64::default()).build()
         } else {
             F64Validator::new().build()
-        }
+        };
+        gen.queries().apply(validator)
     }
 }
 
@@ -81,8 +85,8 @@ impl FogValidate for char {
         Name::new("", if opt { "OptChar" } else { "char" })
     }
 
-    fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-        if opt {
+    fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+        let validator = if opt {
             StrValidator::new()
                 .nin_add(char::default())
                 .min_char(1)
@@ -90,7 +94,8 @@ impl FogValidate for char {
                 .build()
         } else {
             StrValidator::new().min_char(1).max_char(1).build()
-        }
+        };
+        gen.queries().apply(validator)
     }
 }
 
@@ -109,8 +114,8 @@ macro_rules! unsigned_impl {
                 Name::new("", name)
             }
 
-            fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-                if opt {
+            fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+                let validator = if opt {
                     IntValidator::new()
                         .min(<$type>::MIN + 1)
                         .max(<$type>::MAX)
@@ -120,7 +125,8 @@ macro_rules! unsigned_impl {
                         .min(<$type>::MIN)
                         .max(<$type>::MAX)
                         .build()
-                }
+                };
+                gen.queries().apply(validator)
             }
         }
     };
@@ -141,8 +147,8 @@ macro_rules! signed_impl {
                 Name::new("", name)
             }
 
-            fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
-                if opt {
+            fn validator(gen: &mut SchemaGenerator, opt: bool) -> Validator {
+                let validator = if opt {
                     IntValidator::new()
                         .nin_add(0)
                         .min(<$type>::MIN)
@@ -153,7 +159,8 @@ macro_rules! signed_impl {
                         .min(<$type>::MIN)
                         .max(<$type>::MAX)
                         .build()
-                }
+                };
+                gen.queries().apply(validator)
             }
         }
     };
@@ -178,4 +185,4 @@ forward_impl!(AtomicI64 => i64);
 forward_impl!(AtomicU8 => u8);
 forward_impl!(AtomicU16 => u16);
 forward_impl!(AtomicU32 => u32);
-forward_impl!(AtomicU64 => u64);
\ No newline at end of file
+forward_impl!(AtomicU64 => u64);
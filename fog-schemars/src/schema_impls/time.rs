@@ -41,7 +41,7 @@ impl FogValidate for SystemTime {
     has_opt!();
 
     fn validator_name(opt: bool) -> Name {
-        Name::new("std::time", if opt { "OptDuration" } else { "Duration" })
+        Name::new("std::time", if opt { "OptSystemTime" } else { "SystemTime" })
     }
 
     fn validator(_: &mut SchemaGenerator, opt: bool) -> Validator {
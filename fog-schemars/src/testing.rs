@@ -0,0 +1,118 @@
+//! Helpers for checking that generated schemas don't change unexpectedly.
+//!
+//! Any change to a type that's part of a schema changes the schema's hash,
+//! which in turn means existing documents no longer refer to it. It's
+//! recommended to pin every schema with [`assert_schema_stable`] in a unit
+//! test, so such changes are always deliberate.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use fog_pack::{document::Document, schema::NoSchema};
+
+use crate::diff::SchemaDiff;
+use crate::schema_doc::SchemaDoc;
+use crate::{render, SchemaGenerator};
+
+/// Setting this environment variable (to anything) makes
+/// [`assert_schema_stable`] overwrite existing snapshots instead of comparing
+/// against them.
+pub const UPDATE_ENV_VAR: &str = "FOG_SCHEMARS_UPDATE";
+
+/// Check that a schema hasn't changed since its snapshot was taken.
+///
+/// The snapshot at `path` holds the hash of the schema, the encoded schema
+/// document, and the schema as rendered by [`render`], for
+/// reviewing changes. If it doesn't exist yet, or if the `FOG_SCHEMARS_UPDATE`
+/// environment variable is set, the snapshot is written and the check passes.
+///
+/// Panics
+/// ------
+/// This function panics if the schema differs from the snapshot, listing what
+/// changed and whether the change is breaking (see [`SchemaDiff`]). It also
+/// panics if the schema can't be built, or the snapshot can't be read or
+/// written.
+pub fn assert_schema_stable(generator: SchemaGenerator, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let doc = generator.build().expect("Schema should be buildable");
+
+    if std::env::var_os(UPDATE_ENV_VAR).is_some() || !path.exists() {
+        write_snapshot(path, doc);
+        return;
+    }
+
+    let old = read_snapshot(path);
+    if old.hash() == doc.hash() {
+        return;
+    }
+
+    let diff = SchemaDiff::from_schema_docs(
+        &SchemaDoc::from_doc(&old).expect("Snapshot should hold a schema"),
+        &SchemaDoc::from_doc(&doc).expect("Generated schema should be loadable"),
+    );
+    let mut msg = format!(
+        "Schema no longer matches the snapshot at {}\n  old hash: {}\n  new hash: {}\n",
+        path.display(),
+        old.hash(),
+        doc.hash()
+    );
+    if diff.is_breaking() {
+        writeln!(msg, "  This is a breaking change:").unwrap();
+    }
+    for change in diff.changes() {
+        writeln!(msg, "  {}", change).unwrap();
+    }
+    write!(
+        msg,
+        "If this change is intended, rerun with {} set to update the snapshot.",
+        UPDATE_ENV_VAR
+    )
+    .unwrap();
+    panic!("{}", msg);
+}
+
+fn write_snapshot(path: &Path, doc: Document) {
+    let rendered = render(&doc).expect("Generated schema should be loadable");
+    let (hash, bytes) = NoSchema::encode_doc(doc).expect("Schema should be encodable");
+
+    let mut contents = String::new();
+    writeln!(contents, "hash: {}", hash).unwrap();
+    writeln!(contents, "doc: {}", to_hex(&bytes)).unwrap();
+    writeln!(contents).unwrap();
+    writeln!(contents, "{}", rendered).unwrap();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).expect("Should be able to create the snapshot directory");
+    }
+    std::fs::write(path, contents).expect("Should be able to write the snapshot");
+}
+
+fn read_snapshot(path: &Path) -> Document {
+    let contents = std::fs::read_to_string(path).expect("Should be able to read the snapshot");
+    let bytes = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("doc: "))
+        .and_then(from_hex)
+        .expect("Snapshot should have a hex-encoded `doc: ` line");
+    NoSchema::decode_doc(bytes).expect("Snapshot should hold a valid document")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        write!(s, "{:02x}", b).unwrap();
+    }
+    s
+}
+
+// `usize::is_multiple_of` needs Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
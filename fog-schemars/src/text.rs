@@ -0,0 +1,68 @@
+//! Helpers shared by the modules that write schemas out as text: [`render`],
+//! [`typescript`], and [`rust`].
+//!
+//! [`render`]: crate::render
+//! [`typescript`]: crate::typescript
+//! [`rust`]: crate::rust
+
+use std::collections::BTreeSet;
+
+use fog_pack::validator::Validator;
+
+/// The comment on a validator, if it has a non-empty one.
+pub(crate) fn comment(v: &Validator) -> Option<&str> {
+    let comment = match v {
+        Validator::Bool(v) => &v.comment,
+        Validator::Int(v) => &v.comment,
+        Validator::F32(v) => &v.comment,
+        Validator::F64(v) => &v.comment,
+        Validator::Bin(v) => &v.comment,
+        Validator::Str(v) => &v.comment,
+        Validator::Array(v) => &v.comment,
+        Validator::Map(v) => &v.comment,
+        Validator::Time(v) => &v.comment,
+        Validator::Hash(v) => &v.comment,
+        Validator::Identity(v) => &v.comment,
+        Validator::StreamId(v) => &v.comment,
+        Validator::LockId(v) => &v.comment,
+        Validator::DataLockbox(v) => &v.comment,
+        Validator::IdentityLockbox(v) => &v.comment,
+        Validator::StreamLockbox(v) => &v.comment,
+        Validator::LockLockbox(v) => &v.comment,
+        _ => return None,
+    };
+    (!comment.is_empty()).then_some(comment.as_str())
+}
+
+/// Turn a name into a type identifier, capitalizing each part of it.
+pub(crate) fn identifier(name: &str) -> String {
+    let mut ident = String::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            ident.push(first.to_ascii_uppercase());
+            ident.extend(chars);
+        }
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Take a name, adding `separator` and a number to the end if it's already
+/// been taken or is one of the `reserved` names.
+pub(crate) fn unique(
+    taken: &mut BTreeSet<String>,
+    reserved: &[&str],
+    separator: &str,
+    name: String,
+) -> String {
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while reserved.contains(&candidate.as_str()) || !taken.insert(candidate.clone()) {
+        candidate = format!("{}{}{}", name, separator, n);
+        n += 1;
+    }
+    candidate
+}
@@ -0,0 +1,357 @@
+//! Generation of TypeScript type declarations from schemas.
+//!
+//! [`export`] writes a `.d.ts` file with a type for each type in a schema's
+//! type list, one for the document, and one for each entry, matching the
+//! values a fog-pack decoder gives for them:
+//!
+//! | Validator | TypeScript |
+//! |-----------|------------|
+//! | `Null` | `null` |
+//! | `Bool` | `boolean` |
+//! | `Int` | `number`, or `number \| bigint` if it may not fit in a double |
+//! | `F32`, `F64` | `number` |
+//! | `Bin` | `Uint8Array` |
+//! | `Str` | `string` |
+//! | `Array` | `T[]`, or a tuple if it has `prefix` validators |
+//! | `Map` | an interface or object type, with optional keys for `opt` |
+//! | `Enum` | a union of the unit variants' names and `{ Variant: T }` objects |
+//! | `Multi` | a union |
+//! | `Any` | `unknown` |
+//!
+//! `Bool`, `Int`, and `Str` validators with an `in` list become unions of
+//! the allowed values instead. A `Map` with a `values` validator and no keys
+//! in `req` or `opt` is a `Record<string, T>` of them. With keys, it's an
+//! object type with an index signature that also covers the keys' types.
+//!
+//! fog-pack's own types, `Time`, `Hash`, `Identity`, `StreamId`, `LockId`,
+//! and the lockboxes, become branded types, like `Hash`, so they can't be
+//! mixed up with each other or with plain bytes. Each is declared as a brand
+//! on `Uint8Array`, holding the type's encoded bytes; a decoder that gives
+//! something else for them can change the declarations at the top of the
+//! file.
+//!
+//! Type names are turned into TypeScript identifiers by dropping anything
+//! that can't be in one, so `Option<Post>` becomes `OptionPost`. Names that
+//! would clash get a number at the end. The document's type is `Document`,
+//! and each entry's type is its key followed by `Entry`, as in
+//! `CommentsEntry`. Comments become doc comments.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use fog_pack::{document::Document, types::Integer, validator::*};
+
+use crate::schema_doc::SchemaDoc;
+use crate::text::{comment, identifier, unique};
+
+/// Generate TypeScript type declarations for a schema, returning the contents
+/// of a `.d.ts` file. See the [module documentation](self) for how validators
+/// are mapped.
+///
+/// ```
+/// use fog_schemars::{typescript, FogValidate, SchemaGenerator};
+///
+/// #[derive(FogValidate)]
+/// struct Post {
+///     title: String,
+/// }
+///
+/// let schema = SchemaGenerator::new::<Post>().build().unwrap();
+/// let ts = typescript::export(&schema).unwrap();
+/// assert!(ts.contains("export interface Document {\n  title: string;\n}"));
+/// ```
+///
+/// This fails if the document isn't a schema.
+pub fn export(schema: &Document) -> fog_pack::error::Result<String> {
+    let schema = SchemaDoc::from_doc(schema)?;
+
+    // Pick the names first, so references can use them.
+    let mut taken = BTreeSet::new();
+    let mut take = |name: String| unique(&mut taken, RESERVED, "", name);
+    let mut names = BTreeMap::new();
+    for name in schema.types.keys() {
+        names.insert(name.clone(), take(identifier(name)));
+    }
+    let doc_name = take("Document".to_owned());
+    let entry_names: Vec<String> = schema
+        .entries
+        .keys()
+        .map(|key| take(format!("{}Entry", identifier(key))))
+        .collect();
+
+    let mut w = Writer {
+        out: String::new(),
+        names: &names,
+        brands: BTreeSet::new(),
+    };
+    for (name, v) in schema.types.iter() {
+        w.declaration(&names[name], v);
+    }
+    w.declaration(&doc_name, &schema.doc);
+    for (name, entry) in entry_names.iter().zip(schema.entries.values()) {
+        w.declaration(name, &entry.entry);
+    }
+
+    let mut out = String::new();
+    if !schema.name.is_empty() {
+        writeln!(out, "// Types for the {:?} schema.", schema.name).unwrap();
+    }
+    if !schema.description.is_empty() {
+        for line in schema.description.lines() {
+            writeln!(out, "// {}", line).unwrap();
+        }
+    }
+    if !w.brands.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str("declare const fogBrand: unique symbol;\n");
+        out.push_str("export type Brand<T, B extends string> = T & { readonly [fogBrand]: B };\n");
+        for brand in w.brands.iter() {
+            writeln!(
+                out,
+                "export type {} = Brand<Uint8Array, {:?}>;",
+                brand, brand
+            )
+            .unwrap();
+        }
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&w.out);
+    Ok(out)
+}
+
+/// The largest integer a double holds exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+struct Writer<'a> {
+    out: String,
+    names: &'a BTreeMap<String, String>,
+    // The fog-pack types that have been used, which need declaring.
+    brands: BTreeSet<&'static str>,
+}
+
+impl<'a> Writer<'a> {
+    /// Declare a named type, as an interface if it's an object type.
+    fn declaration(&mut self, name: &str, v: &Validator) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        doc_comment(&mut self.out, 0, comment(v));
+        let decl = match v {
+            Validator::Map(map)
+                if map.values.is_none() || !map.req.is_empty() || !map.opt.is_empty() =>
+            {
+                format!("export interface {} {}", name, self.object(map, 0))
+            }
+            v => format!("export type {} = {};", name, self.ty(v, 0)),
+        };
+        self.out.push_str(&decl);
+        self.out.push('\n');
+    }
+
+    /// The TypeScript type for a validator, indented to `indent` levels if it
+    /// spans multiple lines.
+    fn ty(&mut self, v: &Validator, indent: usize) -> String {
+        match v {
+            Validator::Null => "null".to_owned(),
+            Validator::Any => "unknown".to_owned(),
+            Validator::Ref(name) => match self.names.get(name) {
+                Some(name) => name.clone(),
+                None => "unknown".to_owned(),
+            },
+            Validator::Bool(v) if !v.in_list.is_empty() => union(&v.in_list, |v| v.to_string()),
+            Validator::Bool(_) => "boolean".to_owned(),
+            Validator::Int(v) if !v.in_list.is_empty() => union(&v.in_list, |v| v.to_string()),
+            Validator::Int(v) => {
+                let safe = v.min >= Integer::from(-MAX_SAFE_INTEGER)
+                    && v.max <= Integer::from(MAX_SAFE_INTEGER);
+                if safe {
+                    "number".to_owned()
+                } else {
+                    "number | bigint".to_owned()
+                }
+            }
+            Validator::F32(_) | Validator::F64(_) => "number".to_owned(),
+            Validator::Bin(_) => "Uint8Array".to_owned(),
+            Validator::Str(v) if !v.in_list.is_empty() => union(&v.in_list, |v| format!("{:?}", v)),
+            Validator::Str(_) => "string".to_owned(),
+            Validator::Array(v) => self.array(v, indent),
+            Validator::Map(v) => match &v.values {
+                Some(values) if v.req.is_empty() && v.opt.is_empty() => {
+                    format!("Record<string, {}>", self.ty(values, indent))
+                }
+                _ => self.object(v, indent),
+            },
+            Validator::Time(_) => self.brand("Timestamp"),
+            Validator::Hash(_) => self.brand("Hash"),
+            Validator::Identity(_) => self.brand("Identity"),
+            Validator::StreamId(_) => self.brand("StreamId"),
+            Validator::LockId(_) => self.brand("LockId"),
+            Validator::DataLockbox(_) => self.brand("DataLockbox"),
+            Validator::IdentityLockbox(_) => self.brand("IdentityLockbox"),
+            Validator::StreamLockbox(_) => self.brand("StreamLockbox"),
+            Validator::LockLockbox(_) => self.brand("LockLockbox"),
+            Validator::Multi(v) => {
+                let types: Vec<String> = v.iter().map(|v| self.ty(v, indent)).collect();
+                dedup_union(types)
+            }
+            Validator::Enum(v) => {
+                let variants: Vec<String> = v
+                    .iter()
+                    .map(|(name, v)| match v {
+                        Some(v) => format!("{{ {}: {} }}", key(name), self.ty(v, indent)),
+                        None => format!("{:?}", name),
+                    })
+                    .collect();
+                dedup_union(variants)
+            }
+        }
+    }
+
+    fn brand(&mut self, brand: &'static str) -> String {
+        self.brands.insert(brand);
+        brand.to_owned()
+    }
+
+    fn array(&mut self, v: &ArrayValidator, indent: usize) -> String {
+        let items = self.ty(&v.items, indent);
+        if v.prefix.is_empty() {
+            return if is_simple(&items) {
+                format!("{}[]", items)
+            } else {
+                format!("Array<{}>", items)
+            };
+        }
+        let mut parts: Vec<String> = v.prefix.iter().map(|v| self.ty(v, indent)).collect();
+        // A tuple that can't be longer than its prefix has no rest element.
+        if (v.max_len as usize) > parts.len() {
+            if is_simple(&items) {
+                parts.push(format!("...{}[]", items));
+            } else {
+                parts.push(format!("...Array<{}>", items));
+            }
+        }
+        format!("[{}]", parts.join(", "))
+    }
+
+    fn object(&mut self, v: &MapValidator, indent: usize) -> String {
+        if v.req.is_empty() && v.opt.is_empty() {
+            return "{}".to_owned();
+        }
+        let mut out = String::from("{\n");
+        let mut field_types = Vec::new();
+        let fields = v
+            .req
+            .iter()
+            .map(|(k, v)| (k, v, ""))
+            .chain(v.opt.iter().map(|(k, v)| (k, v, "?")));
+        for (k, v, opt) in fields {
+            doc_comment(&mut out, indent + 1, comment(v));
+            let ty = self.ty(v, indent + 1);
+            writeln!(
+                out,
+                "{:width$}{}{}: {};",
+                "",
+                key(k),
+                opt,
+                ty,
+                width = (indent + 1) * 2
+            )
+            .unwrap();
+            field_types.push(ty);
+        }
+        // Every field has to fit the index signature too, so it covers all of
+        // their types along with the other values'.
+        if let Some(values) = &v.values {
+            field_types.insert(0, self.ty(values, indent + 1));
+            writeln!(
+                out,
+                "{:width$}[key: string]: {};",
+                "",
+                dedup_union(field_types),
+                width = (indent + 1) * 2
+            )
+            .unwrap();
+        }
+        write!(out, "{:width$}}}", "", width = indent * 2).unwrap();
+        out
+    }
+}
+
+/// Whether a type can have `[]` put after it without parentheses.
+fn is_simple(ty: &str) -> bool {
+    ty.chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '[' | ']'))
+}
+
+fn union<T>(items: &[T], f: impl Fn(&T) -> String) -> String {
+    dedup_union(items.iter().map(f).collect())
+}
+
+fn dedup_union(types: Vec<String>) -> String {
+    let mut seen = BTreeSet::new();
+    let types: Vec<String> = types
+        .into_iter()
+        .filter(|t| seen.insert(t.clone()))
+        .collect();
+    if types.is_empty() {
+        "never".to_owned()
+    } else {
+        types.join(" | ")
+    }
+}
+
+/// A property name, quoted unless it's a valid identifier.
+fn key(name: &str) -> String {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        name.to_owned()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+/// Names that are already used by the declarations at the top of the file,
+/// or by TypeScript itself.
+const RESERVED: &[&str] = &[
+    "Array",
+    "Brand",
+    "DataLockbox",
+    "Hash",
+    "Identity",
+    "IdentityLockbox",
+    "LockId",
+    "LockLockbox",
+    "Record",
+    "StreamId",
+    "StreamLockbox",
+    "Timestamp",
+    "Uint8Array",
+];
+
+fn doc_comment(out: &mut String, indent: usize, comment: Option<&str>) {
+    let Some(comment) = comment else {
+        return;
+    };
+    let pad = indent * 2;
+    let mut lines = comment.lines();
+    match (lines.next(), lines.next()) {
+        (Some(line), None) => {
+            writeln!(out, "{:pad$}/** {} */", "", line.replace("*/", "*\\/")).unwrap()
+        }
+        _ => {
+            writeln!(out, "{:pad$}/**", "").unwrap();
+            for line in comment.lines() {
+                writeln!(out, "{:pad$} * {}", "", line.replace("*/", "*\\/")).unwrap();
+            }
+            writeln!(out, "{:pad$} */", "").unwrap();
+        }
+    }
+}
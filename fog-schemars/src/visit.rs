@@ -0,0 +1,50 @@
+//! The validators inside other validators.
+//!
+//! Passes over a whole validator tree should find the validators to recurse
+//! into through these, so they all agree on where child validators can be.
+
+use fog_pack::validator::Validator;
+
+/// Every validator directly inside `v`.
+pub(crate) fn children(v: &Validator) -> Vec<&Validator> {
+    match v {
+        Validator::Array(v) => v
+            .contains
+            .iter()
+            .chain(v.prefix.iter())
+            .chain(std::iter::once(v.items.as_ref()))
+            .collect(),
+        Validator::Map(v) => v
+            .req
+            .values()
+            .chain(v.opt.values())
+            .chain(v.values.as_deref())
+            .collect(),
+        Validator::Hash(v) => v.link.as_deref().into_iter().collect(),
+        Validator::Enum(v) => v.0.values().flatten().collect(),
+        Validator::Multi(v) => v.0.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Every validator directly inside `v`, in the same order as [`children`].
+pub(crate) fn children_mut(v: &mut Validator) -> Vec<&mut Validator> {
+    match v {
+        Validator::Array(v) => v
+            .contains
+            .iter_mut()
+            .chain(v.prefix.iter_mut())
+            .chain(std::iter::once(v.items.as_mut()))
+            .collect(),
+        Validator::Map(v) => v
+            .req
+            .values_mut()
+            .chain(v.opt.values_mut())
+            .chain(v.values.as_deref_mut())
+            .collect(),
+        Validator::Hash(v) => v.link.as_deref_mut().into_iter().collect(),
+        Validator::Enum(v) => v.0.values_mut().flatten().collect(),
+        Validator::Multi(v) => v.0.iter_mut().collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,167 @@
+mod util;
+
+use fog_schemars::{FogValidate, SchemaGenError, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use util::*;
+
+#[derive(Serialize, Deserialize, FogValidate)]
+struct Renamed {
+    #[serde(alias = "old_id")]
+    id: u8,
+    #[serde(alias = "old_note", default, skip_serializing_if = "String::is_empty")]
+    note: String,
+}
+
+#[test]
+fn required_field_alias() {
+    let schema = schema_for::<Renamed>();
+    assert!(passes(&schema, map(&[("id", 1)])));
+    assert!(passes(&schema, map(&[("old_id", 1)])));
+    assert!(!passes(&schema, map(&[("id", 1), ("old_id", 1)])));
+    assert!(!passes(&schema, map::<u8>(&[])));
+}
+
+#[test]
+fn optional_field_alias() {
+    let schema = schema_for::<Renamed>();
+    #[derive(Serialize)]
+    struct Old {
+        old_id: u8,
+        old_note: &'static str,
+    }
+    assert!(passes(
+        &schema,
+        Old {
+            old_id: 1,
+            old_note: "hello"
+        }
+    ));
+}
+
+#[derive(Serialize, Deserialize, FogValidate)]
+#[fog(allow_unknown_fields)]
+struct OpenRenamed {
+    #[serde(alias = "old_id")]
+    id: u8,
+}
+
+// Having a field under more than one of its names is only caught where
+// nothing else accepts the extra name.
+#[test]
+fn both_names_only_rejected_when_required_and_closed() {
+    let schema = schema_for::<Renamed>();
+    let mut doc = map(&[("note", "x"), ("old_note", "y")]);
+    doc.extend(map(&[("id", 1)]));
+    assert!(passes(&schema, doc));
+
+    let schema = schema_for::<OpenRenamed>();
+    assert!(passes(&schema, map(&[("id", 1), ("old_id", 1)])));
+}
+
+#[derive(Serialize, Deserialize, FogValidate)]
+enum External {
+    #[serde(alias = "Begin")]
+    Start,
+    Stop(u8),
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, FogValidate)]
+#[serde(tag = "type")]
+enum Internal {
+    #[serde(alias = "Begin")]
+    Start,
+    Stop { at: u8 },
+    #[serde(other)]
+    Unknown,
+}
+
+#[test]
+fn external_enum_alias_and_other() {
+    let schema = schema_for::<External>();
+    assert!(passes(&schema, "Start"));
+    assert!(passes(&schema, "Begin"));
+    assert!(passes(&schema, "Pause"));
+    assert!(passes(&schema, External::Stop(1)));
+    assert!(!passes(&schema, map(&[("Pause", 1)])));
+}
+
+#[test]
+fn internal_enum_alias_and_other() {
+    let schema = schema_for::<Internal>();
+    let mut doc = BTreeMap::new();
+    doc.insert("type", "Begin");
+    assert!(passes(&schema, &doc));
+    doc.insert("type", "Pause");
+    assert!(passes(&schema, &doc));
+    doc.insert("type", "Stop");
+    assert!(!passes(&schema, &doc));
+    assert!(passes(&schema, Internal::Stop { at: 3 }));
+}
+
+#[derive(Serialize, FogValidate)]
+struct MaybeRenamed {
+    inner: Option<Renamed>,
+}
+
+#[test]
+fn optional_aliased_struct() {
+    let schema = schema_for::<MaybeRenamed>();
+    let inner = |m| BTreeMap::from([("inner", m)]);
+    assert!(passes(&schema, inner(map(&[("id", 1)]))));
+    assert!(passes(&schema, inner(map(&[("old_id", 1)]))));
+    assert!(passes(&schema, MaybeRenamed { inner: None }));
+    assert!(!passes(&schema, inner(map(&[("other", 1)]))));
+}
+
+#[derive(Serialize, FogValidate)]
+struct MaybeEnums {
+    external: Option<External>,
+    internal: Option<Internal>,
+}
+
+#[test]
+fn optional_enums_with_other() {
+    let schema = schema_for::<MaybeEnums>();
+    let mut doc = BTreeMap::new();
+    doc.insert("external", map(&[("Stop", 1)]));
+    doc.insert("internal", map(&[("type", "Pause")]));
+    assert!(passes(&schema, &doc));
+    doc.insert("internal", map(&[("type", "Stop"), ("at", "x")]));
+    assert!(!passes(&schema, &doc));
+    doc.insert("internal", map(&[("type", "Start")]));
+    doc.insert("external", map(&[("Pause", 1)]));
+    assert!(!passes(&schema, &doc));
+}
+
+#[derive(Serialize, FogValidate)]
+struct ManyAliases {
+    #[serde(alias = "a2")]
+    a: u8,
+    #[serde(alias = "b2")]
+    b: u8,
+    #[serde(alias = "c2")]
+    c: u8,
+    #[serde(alias = "d2")]
+    d: u8,
+    #[serde(alias = "e2")]
+    e: u8,
+    #[serde(alias = "f2")]
+    f: u8,
+    #[serde(alias = "g2")]
+    g: u8,
+}
+
+#[test]
+fn too_many_alias_branches() {
+    let err = SchemaGenerator::new::<ManyAliases>()
+        .try_build()
+        .unwrap_err();
+    let SchemaGenError::Type { message, .. } = err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert!(message.contains("`g`"), "{}", message);
+    assert!(message.contains("128"), "{}", message);
+}
@@ -0,0 +1,97 @@
+#![allow(dead_code)]
+
+mod util;
+
+use fog_pack::types::{Identity, Value};
+use fog_schemars::{FogValidate, SchemaGenerator};
+use util::*;
+
+#[derive(FogValidate)]
+struct UserId(Identity);
+
+#[derive(FogValidate)]
+struct GroupId(Identity);
+
+#[derive(FogValidate)]
+struct Ids {
+    owner: UserId,
+    group: GroupId,
+}
+
+#[derive(FogValidate)]
+struct Owned {
+    owner: UserId,
+}
+
+#[derive(FogValidate)]
+struct ListA {
+    next: Option<Box<ListA>>,
+}
+
+#[derive(FogValidate)]
+struct ListB {
+    next: Option<Box<ListB>>,
+}
+
+#[derive(FogValidate)]
+struct Lists {
+    a: ListA,
+    b: ListB,
+}
+
+#[test]
+fn off_by_default() {
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Ids>()),
+        ["GroupId", "UserId"]
+    );
+}
+
+#[test]
+fn merges_identical_types() {
+    let (doc, report) = SchemaGenerator::new::<Ids>()
+        .dedup_types(true)
+        .build_with_report()
+        .unwrap();
+    let schema = doc.deserialize::<Value>().unwrap();
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Ids>().dedup_types(true)),
+        ["GroupId"]
+    );
+    assert_eq!(
+        report.merged.into_iter().collect::<Vec<_>>(),
+        [("UserId".to_owned(), "GroupId".to_owned())]
+    );
+    let Value::Map(doc) = &schema["doc"]["Map"]["req"] else {
+        panic!("doc isn't a map validator");
+    };
+    assert_eq!(doc["owner"], doc["group"]);
+}
+
+#[test]
+fn merges_identical_recursive_types() {
+    let (_, report) = SchemaGenerator::new::<Lists>()
+        .dedup_types(true)
+        .build_with_report()
+        .unwrap();
+    assert_eq!(
+        report.merged.get("ListB").map(String::as_str),
+        Some("ListA")
+    );
+}
+
+#[test]
+fn keeps_imported_types() {
+    let published = SchemaGenerator::new::<Owned>().build().unwrap();
+    let (_, report) = SchemaGenerator::new::<Ids>()
+        .dedup_types(true)
+        .import_types(&published)
+        .unwrap()
+        .build_with_report()
+        .unwrap();
+    assert_eq!(report.types.into_iter().collect::<Vec<_>>(), ["UserId"]);
+    assert_eq!(
+        report.merged.into_iter().collect::<Vec<_>>(),
+        [("GroupId".to_owned(), "UserId".to_owned())]
+    );
+}
@@ -0,0 +1,68 @@
+#![allow(dead_code)]
+
+mod util;
+
+use fog_pack::types::Value;
+use fog_schemars::FogValidate;
+use util::*;
+
+#[derive(FogValidate)]
+struct Record {
+    current: u8,
+    #[deprecated]
+    old: u8,
+}
+
+#[derive(FogValidate)]
+#[serde(tag = "type")]
+enum Tagged {
+    Current {
+        a: u8,
+    },
+    #[deprecated]
+    Old {
+        a: u8,
+    },
+}
+
+#[derive(FogValidate)]
+enum External {
+    Current,
+    #[deprecated]
+    Old,
+}
+
+#[test]
+fn deprecated_fields_are_optional() {
+    let schema = schema_for::<Record>();
+    assert!(passes(&schema, map(&[("current", Value::from(1u8))])));
+    assert!(passes(
+        &schema,
+        map(&[("current", Value::from(1u8)), ("old", Value::from(1u8))])
+    ));
+    assert!(!passes(
+        &schema,
+        map(&[("current", Value::from(1u8)), ("old", Value::from("text"))])
+    ));
+}
+
+#[test]
+fn strict_schema_drops_deprecated_fields() {
+    let schema = strict_schema_for::<Record>();
+    assert!(passes(&schema, map(&[("current", Value::from(1u8))])));
+    assert!(!passes(
+        &schema,
+        map(&[("current", Value::from(1u8)), ("old", Value::from(1u8))])
+    ));
+}
+
+#[test]
+fn strict_schema_drops_deprecated_variants() {
+    let old = map(&[("type", Value::from("Old")), ("a", Value::from(1u8))]);
+    assert!(passes(&schema_for::<Tagged>(), old.clone()));
+    assert!(!passes(&strict_schema_for::<Tagged>(), old));
+
+    assert!(passes(&schema_for::<External>(), "Old"));
+    assert!(!passes(&strict_schema_for::<External>(), "Old"));
+    assert!(passes(&strict_schema_for::<External>(), "Current"));
+}
@@ -0,0 +1,267 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::{StrValidator, Validator};
+use fog_schemars::diff::{ChangeKind, SchemaDiff};
+use fog_schemars::{FogValidate, Name, QueryPolicy, SchemaGenerator};
+use serde::Serialize;
+
+fn diff<Old: FogValidate, New: FogValidate>() -> SchemaDiff {
+    SchemaDiff::from_generators(SchemaGenerator::new::<Old>(), SchemaGenerator::new::<New>())
+        .unwrap()
+}
+
+fn kinds(diff: &SchemaDiff) -> Vec<(&str, ChangeKind)> {
+    diff.changes()
+        .iter()
+        .map(|c| (c.path.as_str(), c.kind))
+        .collect()
+}
+
+/// A string of at most `N` bytes.
+pub struct ShortStr<const N: u32>;
+
+impl<const N: u32> FogValidate for ShortStr<N> {
+    fn should_reference(_: bool) -> bool {
+        false
+    }
+
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "ShortStr")
+    }
+
+    fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
+        StrValidator::new().max_len(N).build()
+    }
+}
+
+mod v1 {
+    use super::*;
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Doc")]
+    pub struct Doc {
+        pub a: u8,
+        pub name: ShortStr<8>,
+        pub inner: Inner,
+        pub kind: Kind,
+    }
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Inner")]
+    pub struct Inner {
+        pub x: u32,
+    }
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Kind")]
+    pub enum Kind {
+        A,
+        B,
+    }
+}
+
+mod same {
+    pub use super::v1::*;
+}
+
+mod new_required {
+    use super::*;
+    pub use v1::{Inner, Kind};
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Doc")]
+    pub struct Doc {
+        pub a: u8,
+        pub b: u8,
+        pub name: ShortStr<8>,
+        pub inner: Inner,
+        pub kind: Kind,
+    }
+}
+
+mod raised_len {
+    use super::*;
+    pub use v1::{Inner, Kind};
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Doc")]
+    pub struct Doc {
+        pub a: u8,
+        pub name: ShortStr<16>,
+        pub inner: Inner,
+        pub kind: Kind,
+    }
+}
+
+mod fewer_variants {
+    use super::*;
+    pub use v1::Inner;
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Doc")]
+    pub struct Doc {
+        pub a: u8,
+        pub name: ShortStr<8>,
+        pub inner: Inner,
+        pub kind: Kind,
+    }
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Kind")]
+    pub enum Kind {
+        A,
+    }
+}
+
+mod inner_optional {
+    use super::*;
+    pub use v1::Kind;
+
+    #[derive(FogValidate)]
+    #[fog(rename = "Doc")]
+    pub struct Doc {
+        pub a: u8,
+        pub name: ShortStr<8>,
+        pub inner: Inner,
+        pub kind: Kind,
+    }
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Inner")]
+    pub struct Inner {
+        #[serde(default, skip_serializing_if = "is_zero")]
+        pub x: u32,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        pub y: u32,
+    }
+
+    fn is_zero(v: &u32) -> bool {
+        *v == 0
+    }
+}
+
+#[test]
+fn identical_schemas() {
+    let diff = diff::<v1::Doc, same::Doc>();
+    assert!(diff.is_empty(), "{}", diff);
+    assert_eq!(diff.kind(), ChangeKind::Neutral);
+}
+
+#[test]
+fn new_required_field_is_breaking() {
+    let diff = diff::<v1::Doc, new_required::Doc>();
+    assert_eq!(kinds(&diff), vec![("doc.b", ChangeKind::Incompatible)]);
+    assert!(diff.is_breaking());
+}
+
+#[test]
+fn raised_max_len_is_widening() {
+    let diff = diff::<v1::Doc, raised_len::Doc>();
+    assert_eq!(kinds(&diff), vec![("doc.name", ChangeKind::Widening)]);
+    assert!(!diff.is_breaking());
+}
+
+#[test]
+fn removed_variant_is_narrowing() {
+    let diff = diff::<v1::Doc, fewer_variants::Doc>();
+    assert_eq!(diff.kind(), ChangeKind::Narrowing, "{}", diff);
+}
+
+#[test]
+fn follows_refs() {
+    let diff = diff::<v1::Doc, inner_optional::Doc>();
+    assert_eq!(
+        kinds(&diff),
+        vec![
+            ("type \"Inner\".x", ChangeKind::Widening),
+            // Skipping zeroes means a zero can no longer be written out.
+            ("type \"Inner\".x", ChangeKind::Narrowing),
+            ("type \"Inner\".y", ChangeKind::Widening),
+        ],
+        "{}",
+        diff
+    );
+}
+
+#[test]
+fn removed_optional_field_is_narrowing() {
+    let diff = diff::<inner_optional::Doc, v1::Doc>();
+    assert!(diff
+        .changes()
+        .iter()
+        .any(|c| c.path == "type \"Inner\".y" && c.kind == ChangeKind::Narrowing));
+    assert!(diff.is_breaking());
+}
+
+#[test]
+fn query_changes_are_neutral() {
+    let queried = || SchemaGenerator::new::<v1::Doc>().query_policy(QueryPolicy::new().ord(true));
+    let turned_off =
+        SchemaDiff::from_generators(queried(), SchemaGenerator::new::<v1::Doc>()).unwrap();
+    assert!(!turned_off.is_empty());
+    assert_eq!(turned_off.kind(), ChangeKind::Neutral, "{}", turned_off);
+    let turned_on =
+        SchemaDiff::from_generators(SchemaGenerator::new::<v1::Doc>(), queried()).unwrap();
+    assert_eq!(turned_on.kind(), ChangeKind::Neutral, "{}", turned_on);
+}
+
+mod either_v1 {
+    use super::*;
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Doc")]
+    pub struct Doc {
+        pub either: Either,
+    }
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Either", untagged)]
+    pub enum Either {
+        I(Inner),
+        N(u8),
+    }
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Inner")]
+    pub struct Inner {
+        pub x: u32,
+    }
+}
+
+mod either_v2 {
+    use super::*;
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Doc")]
+    pub struct Doc {
+        pub either: Either,
+    }
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Either", untagged)]
+    pub enum Either {
+        I(Inner),
+        N(u8),
+        S(String),
+    }
+
+    #[derive(Serialize, FogValidate)]
+    #[serde(rename = "Inner")]
+    pub struct Inner {
+        pub x: u32,
+        pub y: u32,
+    }
+}
+
+#[test]
+fn follows_refs_in_shared_alternatives() {
+    let diff = diff::<either_v1::Doc, either_v2::Doc>();
+    assert!(
+        diff.changes()
+            .iter()
+            .any(|c| c.path == "type \"Inner\".y" && c.kind == ChangeKind::Incompatible),
+        "{}",
+        diff
+    );
+    assert!(diff.is_breaking(), "{}", diff);
+}
@@ -0,0 +1,156 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::Validator;
+use fog_schemars::{FogValidate, Name, SchemaGenError, SchemaGenerator};
+
+#[derive(FogValidate)]
+struct Doc {
+    a: u8,
+}
+
+#[derive(FogValidate)]
+struct HasUnitOption {
+    a: Option<()>,
+}
+
+struct BadName;
+
+impl FogValidate for BadName {
+    fn validator_name(_: bool) -> Name {
+        Name::new("bad", "Bad<Name>")
+    }
+
+    fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
+        Validator::Null
+    }
+}
+
+#[derive(FogValidate)]
+struct HasBadName {
+    a: BadName,
+}
+
+#[test]
+fn duplicate_entry() {
+    let result = SchemaGenerator::new::<Doc>()
+        .try_entry_add::<u8>("a", None)
+        .unwrap()
+        .try_entry_add::<u16>("a", None);
+    assert_eq!(
+        result.unwrap_err(),
+        SchemaGenError::DuplicateEntry("a".into())
+    );
+}
+
+#[test]
+fn reported_error_has_type_path() {
+    let err = SchemaGenerator::new::<HasUnitOption>()
+        .try_build()
+        .unwrap_err();
+    let SchemaGenError::Type { type_path, message } = err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert!(message.contains("Option<()>"), "{}", message);
+    assert!(type_path.contains("HasUnitOption"), "{}", type_path);
+    assert!(
+        type_path.ends_with("core::option::Option<()>"),
+        "{}",
+        type_path
+    );
+}
+
+#[test]
+fn invalid_name() {
+    let err = SchemaGenerator::new::<HasBadName>()
+        .try_build()
+        .unwrap_err();
+    assert!(
+        matches!(&err, SchemaGenError::InvalidName { name, .. } if name == "bad::Bad<Name>"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+#[should_panic(expected = "Option<()>")]
+fn build_panics_on_generation_error() {
+    let _ = SchemaGenerator::new::<HasUnitOption>().build();
+}
+
+mod first {
+    #[derive(fog_schemars::FogValidate)]
+    #[fog(rename = "Shared")]
+    pub struct Shared {
+        pub a: u8,
+    }
+}
+
+mod second {
+    #[derive(fog_schemars::FogValidate)]
+    #[fog(rename = "Shared")]
+    pub struct Shared {
+        pub b: u8,
+    }
+}
+
+#[derive(FogValidate)]
+struct BothShared {
+    first: first::Shared,
+    second: second::Shared,
+}
+
+#[derive(FogValidate)]
+struct Node {
+    next: Option<Box<Node>>,
+    boxed: Box<first::Shared>,
+    plain: first::Shared,
+}
+
+#[derive(FogValidate)]
+struct Times {
+    duration: std::time::Duration,
+    system: std::time::SystemTime,
+    ip: std::net::IpAddr,
+    socket: std::net::SocketAddr,
+}
+
+#[test]
+fn duplicate_name() {
+    let err = SchemaGenerator::new::<BothShared>()
+        .try_build()
+        .unwrap_err();
+    let SchemaGenError::DuplicateName {
+        name,
+        first,
+        second,
+    } = err
+    else {
+        panic!("unexpected error: {}", err);
+    };
+    assert!(name.ends_with("Shared"), "{}", name);
+    assert_eq!(first, std::any::type_name::<first::Shared>());
+    assert_eq!(second, std::any::type_name::<second::Shared>());
+}
+
+#[test]
+fn duplicate_name_as_doc() {
+    let err = SchemaGenerator::new::<second::Shared>()
+        .entry_add::<Node>("node", None)
+        .try_build()
+        .unwrap_err();
+    assert!(
+        matches!(&err, SchemaGenError::DuplicateName { name, .. } if name.ends_with("Shared")),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn forwarded_names_are_shared() {
+    SchemaGenerator::new::<Node>().try_build().unwrap();
+}
+
+#[test]
+fn std_types_have_distinct_names() {
+    SchemaGenerator::new::<Times>().try_build().unwrap();
+}
@@ -0,0 +1,107 @@
+// Rust types for the "Blog" schema.
+// Posts with comments.
+
+use fog_pack::schema::Compress;
+use fog_pack::types::{Hash, Identity, Timestamp};
+use fog_pack::validator::*;
+use fog_schemars::{FogValidate, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Start generating the schema these types describe.
+pub fn schema() -> SchemaGenerator {
+    SchemaGenerator::new::<Post>()
+        .name("Blog")
+        .description("Posts with comments.")
+        .version(3)
+        .regexes(4)
+        .doc_compress(Compress::None)
+        .entry_add::<CommentsEntry>("comments", Some(Compress::new_zstd_general(5)))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(rename = "Author")]
+pub struct Author {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    pub key: Identity,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(rename = "Block")]
+#[serde(tag = "kind")]
+pub enum Block {
+    Text { body: String },
+    Quote { body: String, source: String },
+    Break,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(rename = "Embed")]
+#[serde(tag = "t", content = "c")]
+pub enum Embed {
+    Image(Hash),
+    Post(Box<Post>),
+    Nothing,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(rename = "Post")]
+pub struct Post {
+    pub author: Author,
+    pub blocks: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<Embed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub likes: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub links: BTreeMap<String, Hash>,
+    pub parent: Option<Box<Post>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    pub status: Status,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+    #[fog(schema_with = "post_title")]
+    pub title: String,
+    #[serde(rename = "type")]
+    pub type_: u8,
+}
+
+fn post_title(_: &mut SchemaGenerator) -> Validator {
+    StrValidator::new().max_len(200).min_len(1).build()
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(rename = "Reaction")]
+#[serde(untagged)]
+pub enum Reaction {
+    Null,
+    Str(char),
+    Str2(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(rename = "Status")]
+pub enum Status {
+    Draft,
+    Published(Timestamp),
+    Retracted { reason: String },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+#[fog(allow_unknown_fields)]
+pub struct CommentsEntry {
+    #[fog(schema_with = "comments_entry_rating")]
+    pub rating: u8,
+    pub reactions: Vec<Reaction>,
+    #[serde(default)]
+    #[fog(allow_missing_skip)]
+    pub reply_to: Option<Hash>,
+    pub text: String,
+}
+
+fn comments_entry_rating(_: &mut SchemaGenerator) -> Validator {
+    IntValidator::new().max(5).min(1).build()
+}
@@ -0,0 +1,118 @@
+// Rust types for a fog-pack schema.
+
+use fog_pack::types::{Hash, Integer, Timestamp, Value};
+use fog_pack::validator::*;
+use fog_schemars::{FogValidate, Name, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Start generating the schema these types describe.
+pub fn schema() -> SchemaGenerator {
+    SchemaGenerator::new::<Document>()
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PairU8(pub Vec<u8>);
+
+impl FogValidate for PairU8 {
+    fn validator_name(_: bool) -> Name {
+        Name::with_types("", "Pair", vec![Name::new("", "u8")])
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        ArrayValidator::new()
+            .items(gen.type_add::<u8>())
+            .max_len(2)
+            .min_len(2)
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<PairU8>,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl FogValidate for GeoPoint {
+    fn validator_name(_: bool) -> Name {
+        Name::new("geo", "Point")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        MapValidator::new()
+            .comment("A point on the globe.")
+            .req_add("lat", F64Validator::new().max(90.0).min(-90.0).build())
+            .req_add("lon", F64Validator::new().max(180.0).min(-180.0).build())
+            .opt_add("label", gen.type_add::<PairU8>())
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridPoint(pub Value);
+
+impl FogValidate for GridPoint {
+    fn validator_name(_: bool) -> Name {
+        Name::new("grid", "Point")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        ArrayValidator::new()
+            .prefix_add(gen.type_add::<Integer>())
+            .prefix_add(gen.type_add::<Integer>())
+            .build()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, FogValidate)]
+pub struct Document {
+    pub geo: GeoPoint,
+    pub grid: GridPoint,
+    #[fog(schema_with = "document_layers")]
+    pub layers: Vec<String>,
+    #[fog(schema_with = "document_scale")]
+    pub scale: Integer,
+    #[fog(schema_with = "document_source")]
+    pub source: Hash,
+    #[fog(schema_with = "document_tiles")]
+    pub tiles: BTreeMap<String, String>,
+    #[fog(schema_with = "document_updated")]
+    pub updated: Timestamp,
+}
+
+fn document_layers(gen: &mut SchemaGenerator) -> Validator {
+    ArrayValidator::new()
+        .items(gen.type_add::<String>())
+        .nin_add(vec![Value::Str("hidden".to_owned())])
+        .unique(true)
+        .build()
+}
+
+fn document_scale(_: &mut SchemaGenerator) -> Validator {
+    IntValidator::new().in_add(1).in_add(10).in_add(100).build()
+}
+
+fn document_source(gen: &mut SchemaGenerator) -> Validator {
+    HashValidator {
+        comment: "Where the map came from.".into(),
+        ..HashValidator::new()
+    }
+    .link(gen.type_add::<GeoPoint>())
+    .build()
+}
+
+fn document_tiles(_: &mut SchemaGenerator) -> Validator {
+    MapValidator::new()
+        .values(StrValidator::new().max_len(64).build())
+        .keys(StrValidator::new().max_len(8))
+        .build()
+}
+
+fn document_updated(_: &mut SchemaGenerator) -> Validator {
+    TimeValidator::new()
+        .min(Timestamp::from_utc(1600000000, 0).unwrap())
+        .build()
+}
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use fog_pack::document::NewDocument;
+use fog_pack::types::Value;
+use fog_schemars::{FogValidate, SchemaGenError, SchemaGenerator};
+
+mod theirs {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Comment {
+        pub text: String,
+    }
+
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Author {
+        pub name: String,
+    }
+
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Published {
+        pub comment: Comment,
+        pub author: Author,
+    }
+}
+
+mod ours {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Comment {
+        pub text: String,
+    }
+}
+
+mod changed {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Comment {
+        pub text: String,
+        pub likes: u32,
+    }
+}
+
+#[derive(FogValidate)]
+struct Post {
+    comments: Vec<ours::Comment>,
+}
+
+#[derive(FogValidate)]
+struct ChangedPost {
+    comments: Vec<changed::Comment>,
+}
+
+fn published() -> fog_pack::document::Document {
+    SchemaGenerator::new::<theirs::Published>().build().unwrap()
+}
+
+#[test]
+fn import_and_bind() {
+    let schema = SchemaGenerator::new::<Post>()
+        .import_types(&published())
+        .unwrap()
+        .try_build()
+        .unwrap()
+        .deserialize::<Value>()
+        .unwrap();
+    let Value::Map(types) = &schema["types"] else {
+        panic!("schema has no types");
+    };
+    assert_eq!(types.keys().collect::<Vec<_>>(), ["Author", "Comment"]);
+}
+
+#[test]
+fn import_mismatch() {
+    let err = SchemaGenerator::new::<ChangedPost>()
+        .import_types(&published())
+        .unwrap()
+        .try_build()
+        .unwrap_err();
+    let SchemaGenError::ImportMismatch { name, rust_type } = err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(name, "Comment");
+    assert_eq!(rust_type, std::any::type_name::<changed::Comment>());
+}
+
+#[test]
+fn import_not_a_schema() {
+    let doc = NewDocument::new(None, 1u8).unwrap();
+    let doc = fog_pack::schema::NoSchema::validate_new_doc(doc).unwrap();
+    let err = SchemaGenerator::new::<Post>()
+        .import_types(&doc)
+        .unwrap_err();
+    assert!(matches!(err, SchemaGenError::Build(_)), "{}", err);
+}
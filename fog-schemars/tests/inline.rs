@@ -0,0 +1,82 @@
+#![allow(dead_code)]
+
+mod util;
+
+use fog_pack::types::Value;
+use fog_pack::validator::{StrValidator, Validator};
+use fog_schemars::{FogValidate, Name, SchemaGenerator};
+use util::*;
+
+#[derive(FogValidate)]
+struct Once {
+    x: u8,
+}
+
+#[derive(FogValidate)]
+struct Twice {
+    y: u8,
+}
+
+#[derive(FogValidate)]
+struct Orphan {
+    z: u8,
+}
+
+#[derive(FogValidate)]
+struct Node {
+    children: Vec<Node>,
+}
+
+#[derive(FogValidate)]
+struct Doc {
+    once: Once,
+    twice_a: Twice,
+    twice_b: Twice,
+    tree: Node,
+    unused: AddsUnused,
+}
+
+/// Adds a type to the schema, then doesn't use it.
+struct AddsUnused;
+
+impl FogValidate for AddsUnused {
+    fn should_reference(_: bool) -> bool {
+        false
+    }
+
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "AddsUnused")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        gen.type_add::<Orphan>();
+        StrValidator::new().build()
+    }
+}
+
+#[test]
+fn off_by_default() {
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Doc>()),
+        ["Node", "Once", "Orphan", "Twice"]
+    );
+}
+
+#[test]
+fn inlines_and_prunes() {
+    let (doc, report) = SchemaGenerator::new::<Doc>()
+        .inline_types(true)
+        .build_with_report()
+        .unwrap();
+    let schema = doc.deserialize::<Value>().unwrap();
+    let Value::Map(types) = &schema["types"] else {
+        panic!("schema has no types");
+    };
+    assert_eq!(types.keys().collect::<Vec<_>>(), ["Node", "Twice"]);
+    assert_eq!(report.inlined.into_iter().collect::<Vec<_>>(), ["Once"]);
+    assert_eq!(report.pruned.into_iter().collect::<Vec<_>>(), ["Orphan"]);
+    assert!(matches!(
+        &schema["doc"]["Map"]["req"]["once"],
+        Value::Map(once) if once.contains_key("Map")
+    ));
+}
@@ -0,0 +1,70 @@
+#![allow(dead_code)]
+
+use fog_pack::schema::Compress;
+use fog_pack::validator::Validator;
+use fog_schemars::{FogValidate, SchemaGenerator};
+
+#[derive(FogValidate)]
+struct Inner {
+    x: u8,
+}
+
+#[derive(FogValidate)]
+struct Unused {
+    y: u8,
+}
+
+#[derive(FogValidate)]
+struct Doc {
+    inner: Inner,
+    name: String,
+}
+
+#[test]
+fn inspect_types() {
+    let gen = SchemaGenerator::new::<Doc>();
+    let names: Vec<&str> = gen.types().map(|(name, _)| name).collect();
+    assert_eq!(names.len(), 1);
+    assert!(names[0].ends_with("Inner"), "{:?}", names);
+
+    let inner = gen.validator_for::<Inner>().unwrap();
+    let Validator::Map(map) = inner else {
+        panic!("expected a map validator, got {:?}", inner);
+    };
+    assert!(map.req.contains_key("x"));
+    assert_eq!(gen.type_get(names[0]), Some(inner));
+    assert!(gen.validator_for::<Unused>().is_none());
+    assert!(gen.validator_for::<String>().is_none());
+}
+
+#[test]
+fn inspect_doc_and_resolve() {
+    let gen = SchemaGenerator::new::<Doc>();
+    let Validator::Map(doc) = gen.doc() else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    let field = &doc.req["inner"];
+    assert!(matches!(field, Validator::Ref(_)));
+    assert_eq!(gen.resolve(field), gen.validator_for::<Inner>().unwrap());
+    assert_eq!(gen.resolve(&doc.req["name"]), &doc.req["name"]);
+}
+
+#[test]
+fn inspect_entries() {
+    let gen = SchemaGenerator::new::<Doc>()
+        .entry_add::<Inner>("inner", Some(Compress::None))
+        .entry_add::<u8>("num", None);
+    let entries: Vec<_> = gen.entries().collect();
+    assert_eq!(entries.len(), 2);
+    let (key, validator, compress) = entries[0];
+    assert_eq!(key, "inner");
+    assert_eq!(
+        gen.resolve(validator),
+        gen.validator_for::<Inner>().unwrap()
+    );
+    assert!(matches!(compress, Some(Compress::None)));
+    let (key, validator, compress) = entries[1];
+    assert_eq!(key, "num");
+    assert!(matches!(validator, Validator::Int(_)));
+    assert!(compress.is_none());
+}
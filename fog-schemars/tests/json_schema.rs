@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::{MapValidator, StrValidator, Validator};
+use fog_schemars::{json_schema, FogValidate, Name, SchemaGenerator};
+use serde::Serialize;
+
+#[derive(FogValidate, Serialize)]
+struct Author {
+    name: String,
+    key: fog_pack::types::Identity,
+}
+
+#[derive(FogValidate, Serialize)]
+enum Status {
+    Draft,
+    Published(fog_pack::types::Timestamp),
+}
+
+#[derive(FogValidate, Serialize)]
+struct Post {
+    #[fog(length(max = 64))]
+    title: String,
+    author: Author,
+    status: Status,
+    likes: u8,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    parent: Option<Box<Post>>,
+}
+
+/// Checks strings with both byte and character lengths.
+struct Code;
+
+impl FogValidate for Code {
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "Code")
+    }
+
+    fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
+        let code = StrValidator::new().min_len(8).max_len(64).max_char(10);
+        MapValidator::new()
+            .comment("A \"code\"\nand its notes")
+            .req_add("code", code.build())
+            .values(StrValidator::new().build())
+            .build()
+    }
+}
+
+#[test]
+fn exports_schema() {
+    let schema = SchemaGenerator::new::<Post>()
+        .name("Blog")
+        .entry_add::<Author>("authors", None)
+        .build()
+        .unwrap();
+    let json = json_schema::export(&schema).unwrap();
+    let expected = [
+        r##""$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "Blog",
+  "$ref": "#/$defs/Post",
+  "$defs": {"##,
+        r#""required": ["author", "likes", "parent", "status", "title"],
+      "additionalProperties": false"#,
+        r#""likes": {
+          "type": "integer",
+          "minimum": 0,
+          "maximum": 255
+        }"#,
+        r#""title": {
+          "type": "string",
+          "maxLength": 64
+        }"#,
+        r##""$ref": "#/$defs/Option%3CPost%3E""##,
+        r#""const": "Draft""#,
+        r#""required": ["Published"]"#,
+        r#""required": ["Identity"]"#,
+        r##""x-fog-entries": {
+    "authors": {
+      "$ref": "#/$defs/Author"
+    }
+  }"##,
+    ];
+    for expected in expected {
+        assert!(json.contains(expected), "missing {}\nin {}", expected, json);
+    }
+}
+
+#[test]
+fn lossy_mappings() {
+    let schema = SchemaGenerator::new::<Code>().build().unwrap();
+    let json = json_schema::export(&schema).unwrap();
+    let expected = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "type": "object",
+  "description": "A \"code\"\nand its notes",
+  "properties": {
+    "code": {
+      "type": "string",
+      "minLength": 2,
+      "maxLength": 10
+    }
+  },
+  "required": ["code"],
+  "additionalProperties": {
+    "type": "string"
+  }
+}
+"#;
+    assert_eq!(json, expected);
+}
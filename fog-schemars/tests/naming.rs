@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+
+mod util;
+
+use fog_schemars::{FogValidate, NamingStrategy, SchemaGenError, SchemaGenerator};
+use util::*;
+
+mod a {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Config {
+        pub x: u8,
+    }
+}
+
+mod b {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Config {
+        pub x: u8,
+    }
+
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Moved {
+        pub config: Config,
+    }
+}
+
+mod c {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Moved {
+        pub config: super::a::Config,
+    }
+}
+
+#[derive(FogValidate)]
+struct Both {
+    a: a::Config,
+    b: b::Config,
+}
+
+#[derive(FogValidate)]
+struct Tree {
+    children: Vec<Tree>,
+    config: a::Config,
+}
+
+#[test]
+fn shortest_unique() {
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Both>()),
+        ["a::Config", "b::Config"]
+    );
+}
+
+#[test]
+fn full_path() {
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Both>().naming(NamingStrategy::FullPath)),
+        ["naming::a::Config", "naming::b::Config"]
+    );
+}
+
+#[test]
+fn bare_name() {
+    assert_eq!(
+        type_names(SchemaGenerator::new::<b::Moved>().naming(NamingStrategy::BareName)),
+        ["Config"]
+    );
+    let err = SchemaGenerator::new::<Both>()
+        .naming(NamingStrategy::BareName)
+        .try_build()
+        .unwrap_err();
+    assert_eq!(err, SchemaGenError::NameConflict("Config".into()));
+}
+
+#[test]
+fn structural_hash() {
+    let hash = |gen: SchemaGenerator| gen.try_build().unwrap().hash().clone();
+    assert_ne!(
+        hash(SchemaGenerator::new::<b::Moved>().naming(NamingStrategy::FullPath)),
+        hash(SchemaGenerator::new::<c::Moved>().naming(NamingStrategy::FullPath)),
+    );
+    assert_eq!(
+        hash(SchemaGenerator::new::<b::Moved>().naming(NamingStrategy::StructuralHash)),
+        hash(SchemaGenerator::new::<c::Moved>().naming(NamingStrategy::StructuralHash)),
+    );
+    // Identical types share a name.
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Both>().naming(NamingStrategy::StructuralHash)).len(),
+        1
+    );
+    // Recursive types still get names.
+    assert_eq!(
+        type_names(SchemaGenerator::new::<Tree>().naming(NamingStrategy::StructuralHash)).len(),
+        2
+    );
+}
+
+#[test]
+fn custom() {
+    let strategy = NamingStrategy::custom(|name| format!("{}_v1", name.ident()));
+    assert_eq!(
+        type_names(SchemaGenerator::new::<b::Moved>().naming(strategy.clone())),
+        ["Config_v1"]
+    );
+    // Identical types can share a name.
+    SchemaGenerator::new::<Both>()
+        .naming(NamingStrategy::custom(|_| "Same".into()))
+        .try_build()
+        .unwrap();
+}
@@ -0,0 +1,68 @@
+#![allow(dead_code)]
+
+use std::time::SystemTime;
+
+use fog_pack::validator::{TimeValidator, Validator};
+use fog_schemars::{FogValidate, Name, SchemaGenerator};
+
+#[derive(FogValidate)]
+struct Event {
+    at: SystemTime,
+    boxed: Box<SystemTime>,
+}
+
+#[derive(FogValidate)]
+struct Log {
+    events: Vec<Event>,
+}
+
+fn timestamp() -> Name {
+    Name::new("app", "Timestamp")
+}
+
+fn time_validator(_: &mut SchemaGenerator) -> Validator {
+    TimeValidator::new().build()
+}
+
+#[test]
+fn override_replaces_type() {
+    let gen =
+        SchemaGenerator::new::<Event>().override_type::<SystemTime>(timestamp(), time_validator);
+    let Validator::Map(doc) = gen.doc() else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    assert_eq!(doc.req["at"], Validator::new_ref("app::Timestamp"));
+    assert_eq!(
+        gen.validator_for::<SystemTime>(),
+        Some(&TimeValidator::new().build())
+    );
+    // Box keeps its own name, but uses the override's validator.
+    assert!(matches!(gen.resolve(&doc.req["boxed"]), Validator::Time(_)));
+    assert!(gen.types().all(|(_, v)| !matches!(v, Validator::Map(_))));
+    gen.try_build().unwrap();
+}
+
+#[test]
+fn override_applies_to_entries() {
+    let gen = SchemaGenerator::new::<Log>()
+        .entry_add::<Event>("before", None)
+        .override_type::<SystemTime>(timestamp(), time_validator)
+        .entry_add::<SystemTime>("after", None);
+    let entries: Vec<_> = gen.entries().collect();
+    let Validator::Map(before) = gen.resolve(entries[1].1) else {
+        panic!("expected a map validator");
+    };
+    assert_eq!(before.req["at"], Validator::new_ref("app::Timestamp"));
+    assert_eq!(entries[0].1, &TimeValidator::new().build());
+    gen.try_build().unwrap();
+}
+
+#[test]
+fn override_doc_type() {
+    let gen = SchemaGenerator::new::<Event>()
+        .override_type::<Event>(Name::new("app", "Event"), |_| Validator::Null);
+    assert_eq!(gen.doc(), &Validator::Null);
+    let gen = SchemaGenerator::new::<Log>()
+        .override_type::<Event>(Name::new("app", "Event"), |_| Validator::Null);
+    assert_eq!(gen.type_get("app::Event"), Some(&Validator::Null));
+}
@@ -0,0 +1,167 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::Validator;
+use fog_schemars::{FogValidate, QueryPolicy, SchemaGenError, SchemaGenerator};
+
+#[derive(FogValidate)]
+struct Comment {
+    text: String,
+    likes: u32,
+    at: fog_pack::types::Timestamp,
+}
+
+fn fields(gen: &SchemaGenerator) -> (Validator, Validator, Validator) {
+    let Validator::Map(doc) = gen.resolve(gen.doc()) else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    (
+        doc.req["text"].clone(),
+        doc.req["likes"].clone(),
+        doc.req["at"].clone(),
+    )
+}
+
+#[test]
+fn no_queries_by_default() {
+    let gen = SchemaGenerator::new::<Comment>();
+    let (Validator::Str(text), Validator::Int(likes), Validator::Time(at)) = fields(&gen) else {
+        panic!("unexpected validators");
+    };
+    assert!(!text.size && !text.regex && !text.query);
+    assert!(!likes.ord && !likes.query);
+    assert!(!at.ord);
+}
+
+#[test]
+fn policy_applies_to_builtins() {
+    let gen = SchemaGenerator::new::<Comment>()
+        .entry_add::<Comment>("comments", None)
+        .query_policy(QueryPolicy::new().ord(true).size(true));
+    let (Validator::Str(text), Validator::Int(likes), Validator::Time(at)) = fields(&gen) else {
+        panic!("unexpected validators");
+    };
+    assert!(text.size && !text.regex && !text.query);
+    assert!(likes.ord && !likes.bit);
+    assert!(at.ord);
+    let (_, entry, _) = gen.entries().next().unwrap();
+    assert_eq!(gen.resolve(entry), gen.resolve(gen.doc()));
+    gen.try_build().unwrap();
+}
+
+#[test]
+fn regex_needs_budget() {
+    let err = SchemaGenerator::new::<Comment>()
+        .query_policy(QueryPolicy::new().regex(true))
+        .try_build()
+        .unwrap_err();
+    assert!(matches!(err, SchemaGenError::RegexNotAllowed(_)), "{}", err);
+
+    SchemaGenerator::new::<Comment>()
+        .query_policy(QueryPolicy::new().regex(true))
+        .regexes(1)
+        .try_build()
+        .unwrap();
+}
+
+#[test]
+fn apply_leaves_others_alone() {
+    let policy = QueryPolicy::new().values(true).ord(true);
+    assert_eq!(policy.apply(Validator::Null), Validator::Null);
+    assert!(QueryPolicy::new().is_empty());
+    assert!(!policy.is_empty());
+}
+
+#[derive(FogValidate)]
+struct Post {
+    pinned: Comment,
+    flags: Flags,
+}
+
+#[derive(FogValidate)]
+struct Flags {
+    hidden: bool,
+}
+
+#[derive(FogValidate)]
+struct Node {
+    hidden: bool,
+    children: Vec<Node>,
+}
+
+fn ref_name(validator: &Validator) -> &str {
+    match validator {
+        Validator::Ref(name) => name,
+        v => panic!("expected a reference, got {:?}", v),
+    }
+}
+
+#[test]
+fn entry_query_variants() {
+    let gen = SchemaGenerator::new::<Post>().entry_add_with_query::<Post>(
+        "posts",
+        None,
+        QueryPolicy::new().ord(true),
+    );
+    let Validator::Map(doc) = gen.doc() else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    let (_, Validator::Map(entry), _) = gen.entries().next().unwrap() else {
+        panic!("expected a map validator");
+    };
+
+    // The document's comment stays strict, while the entry's allows queries.
+    let strict = ref_name(&doc.req["pinned"]);
+    let variant = ref_name(&entry.req["pinned"]);
+    assert_eq!(variant, format!("{}#query-ord", strict));
+    let likes = |name: &str| match gen.type_get(name) {
+        Some(Validator::Map(v)) => v.req["likes"].clone(),
+        v => panic!("expected a map validator, got {:?}", v),
+    };
+    assert!(matches!(likes(strict), Validator::Int(v) if !v.ord));
+    assert!(matches!(likes(variant), Validator::Int(v) if v.ord));
+
+    // Flags has nothing to order, so there's only one of it.
+    assert_eq!(doc.req["flags"], entry.req["flags"]);
+
+    let schema = gen.try_build().unwrap();
+    let schema = schema.deserialize::<fog_pack::types::Value>().unwrap();
+    let fog_pack::types::Value::Map(types) = &schema["types"] else {
+        panic!("expected a type list");
+    };
+    let names: Vec<&String> = types.keys().collect();
+    assert_eq!(names, ["Comment", "Comment#query-ord", "Flags"]);
+}
+
+#[test]
+fn unchanged_variants_merge() {
+    let gen = SchemaGenerator::new::<Node>().entry_add_with_query::<Node>(
+        "nodes",
+        None,
+        QueryPolicy::new().ord(true),
+    );
+    assert_eq!(gen.types().count(), 1);
+    assert!(gen.types().all(|(name, _)| !name.contains('#')));
+    gen.try_build().unwrap();
+
+    // The same policy as the schema's needs no variants at all.
+    let gen = SchemaGenerator::new::<Post>()
+        .query_policy(QueryPolicy::new().ord(true))
+        .entry_add_with_query::<Post>("posts", None, QueryPolicy::new().ord(true));
+    assert!(gen.types().all(|(name, _)| !name.contains('#')));
+}
+
+#[test]
+fn entry_query_survives_regeneration() {
+    let gen = SchemaGenerator::new::<Post>()
+        .entry_add_with_query::<Post>("posts", None, QueryPolicy::new().ord(true))
+        .query_policy(QueryPolicy::new().size(true));
+    let (_, Validator::Map(entry), _) = gen.entries().next().unwrap() else {
+        panic!("expected a map validator");
+    };
+    let variant = ref_name(&entry.req["pinned"]);
+    let Some(Validator::Map(comment)) = gen.type_get(variant) else {
+        panic!("missing {}", variant);
+    };
+    assert!(matches!(&comment.req["likes"], Validator::Int(v) if v.ord));
+    assert!(matches!(&comment.req["text"], Validator::Str(v) if !v.size));
+}
@@ -0,0 +1,128 @@
+#![allow(dead_code)]
+
+mod util;
+
+use fog_pack::validator::Validator;
+use fog_schemars::{FogValidate, Name, SchemaGenError, SchemaGenerator};
+use util::*;
+
+#[derive(FogValidate)]
+struct Items(Vec<u8>);
+
+#[derive(FogValidate)]
+struct Doc {
+    #[fog(length(max = 16))]
+    bytes: Vec<u8>,
+    #[fog(length(max = 4))]
+    short: Items,
+    #[fog(length(max = 4))]
+    also_short: Items,
+    #[fog(length(max = 8))]
+    longer: Items,
+    plain: Items,
+}
+
+/// Refines a few types by hand.
+struct Refined;
+
+impl FogValidate for Refined {
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "Refined")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        let name = gen.refine_str::<String>(|v| v.max_len(64));
+        let items = gen.refine_array::<Items>(|v| v.min_len(1));
+        fog_pack::validator::MapValidator::new()
+            .req_add("name", name)
+            .req_add("items", items)
+            .build()
+    }
+}
+
+/// Tries to refine an integer as a string.
+struct Mismatched;
+
+impl FogValidate for Mismatched {
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "Mismatched")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        gen.refine_str::<u8>(|v| v.max_len(4))
+    }
+}
+
+#[test]
+fn inline_refinement() {
+    let gen = SchemaGenerator::new::<Doc>();
+    let Validator::Map(doc) = gen.doc() else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    let Validator::Array(bytes) = &doc.req["bytes"] else {
+        panic!("expected an array validator, got {:?}", doc.req["bytes"]);
+    };
+    assert_eq!(bytes.max_len, 16);
+}
+
+#[test]
+fn named_refinements() {
+    let gen = SchemaGenerator::new::<Doc>();
+    let Validator::Map(doc) = gen.doc() else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    let name_of = |field: &str| match &doc.req[field] {
+        Validator::Ref(name) => name.clone(),
+        v => panic!("expected a reference, got {:?}", v),
+    };
+    assert_eq!(name_of("short"), name_of("also_short"));
+    assert_ne!(name_of("short"), name_of("longer"));
+    assert!(!name_of("plain").contains('#'));
+    let short = name_of("short");
+    let (base, suffix) = short.split_once('#').unwrap();
+    assert_eq!(base, name_of("plain"));
+    assert_eq!(suffix.len(), 8);
+    let Validator::Array(short) = gen.type_get(&short).unwrap() else {
+        panic!("expected an array validator");
+    };
+    assert_eq!(short.max_len, 4);
+
+    let names = type_names(gen);
+    assert_eq!(names.len(), 3);
+    assert!(names.iter().all(|n| n.starts_with("Items")), "{:?}", names);
+}
+
+#[test]
+fn refinements_follow_naming() {
+    let names = type_names(SchemaGenerator::new::<Doc>().naming(
+        fog_schemars::NamingStrategy::custom(|name| format!("my_{}", name.ident())),
+    ));
+    assert!(
+        names.iter().all(|n| n.starts_with("my_Items")),
+        "{:?}",
+        names
+    );
+}
+
+#[test]
+fn refine_helpers() {
+    let gen = SchemaGenerator::new::<Refined>();
+    let Validator::Map(doc) = gen.doc() else {
+        panic!("expected a map validator, got {:?}", gen.doc());
+    };
+    assert!(matches!(&doc.req["name"], Validator::Str(v) if v.max_len == 64));
+    let items = gen.resolve(&doc.req["items"]);
+    assert!(matches!(items, Validator::Array(v) if v.min_len == 1));
+    gen.try_build().unwrap();
+}
+
+#[test]
+fn refine_mismatch() {
+    let err = SchemaGenerator::new::<Mismatched>()
+        .try_build()
+        .unwrap_err();
+    let SchemaGenError::Type { message, .. } = err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert!(message.contains("string"), "{}", message);
+}
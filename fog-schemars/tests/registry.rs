@@ -0,0 +1,131 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::Validator;
+use fog_schemars::{FogValidate, QueryPolicy, SchemaGenError, SchemaGenerator, SchemaRegistry};
+
+mod a {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Shared {
+        pub x: u8,
+    }
+}
+
+mod b {
+    #[derive(fog_schemars::FogValidate)]
+    pub struct Shared {
+        pub y: u8,
+    }
+}
+
+#[derive(FogValidate)]
+struct One {
+    shared: a::Shared,
+}
+
+#[derive(FogValidate)]
+struct Two {
+    a: a::Shared,
+    b: b::Shared,
+}
+
+fn registry() -> SchemaRegistry {
+    SchemaRegistry::new()
+        .schema_add("one", SchemaGenerator::new::<One>())
+        .schema_add("two", SchemaGenerator::new::<Two>())
+}
+
+#[test]
+fn consistent_names() {
+    let schemas = registry().build_with_report().unwrap();
+    let (_, _, one) = &schemas["one"];
+    let (_, _, two) = &schemas["two"];
+    assert_eq!(one.types.iter().collect::<Vec<_>>(), ["a::Shared"]);
+    assert_eq!(
+        two.types.iter().collect::<Vec<_>>(),
+        ["a::Shared", "b::Shared"]
+    );
+
+    // On its own, the first schema would shorten the name further.
+    let (_, report) = SchemaGenerator::new::<One>().build_with_report().unwrap();
+    assert_eq!(report.types.iter().collect::<Vec<_>>(), ["Shared"]);
+}
+
+#[test]
+fn hashes_match() {
+    let schemas = registry().build().unwrap();
+    assert_eq!(schemas.len(), 2);
+    for (doc, hash) in schemas.values() {
+        assert_eq!(doc.hash(), hash);
+    }
+}
+
+#[test]
+fn duplicate_schema() {
+    let err = registry()
+        .try_schema_add("one", SchemaGenerator::new::<Two>())
+        .unwrap_err();
+    assert_eq!(err, SchemaGenError::DuplicateSchema("one".into()));
+}
+
+#[test]
+fn conflicting_types() {
+    let err = SchemaRegistry::new()
+        .schema_add("one", SchemaGenerator::new::<One>())
+        .schema_add(
+            "two",
+            SchemaGenerator::new::<Two>()
+                .override_type::<a::Shared>(a::Shared::validator_name(false), |_| Validator::Null),
+        )
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(&err, SchemaGenError::NameConflict(name) if name.ends_with("a::Shared")),
+        "{}",
+        err
+    );
+}
+
+#[derive(FogValidate)]
+struct WithOld {
+    current: u8,
+    #[deprecated]
+    old: u8,
+}
+
+#[derive(FogValidate)]
+struct HasOld {
+    with_old: WithOld,
+}
+
+#[test]
+fn strict_and_permissive_schemas() {
+    let schemas = SchemaRegistry::new()
+        .schema_add("permissive", SchemaGenerator::new::<HasOld>())
+        .schema_add("strict", SchemaGenerator::new_strict::<HasOld>())
+        .build_with_report()
+        .unwrap();
+    let (_, _, permissive) = &schemas["permissive"];
+    let (_, _, strict) = &schemas["strict"];
+    assert_eq!(permissive.types.iter().collect::<Vec<_>>(), ["WithOld"]);
+    assert_eq!(strict.types.iter().collect::<Vec<_>>(), ["WithOld#strict"]);
+}
+
+#[test]
+fn query_policies_get_their_own_names() {
+    let schemas = SchemaRegistry::new()
+        .schema_add("plain", SchemaGenerator::new::<One>())
+        .schema_add(
+            "ord",
+            SchemaGenerator::new::<One>().query_policy(QueryPolicy::new().ord(true)),
+        )
+        .schema_add(
+            "strict",
+            SchemaGenerator::new_strict::<One>().query_policy(QueryPolicy::new().ord(true)),
+        )
+        .build_with_report()
+        .unwrap();
+    let names = |schema: &str| schemas[schema].2.types.iter().cloned().collect::<Vec<_>>();
+    assert_eq!(names("plain"), ["Shared"]);
+    assert_eq!(names("ord"), ["Shared#query-ord"]);
+    assert_eq!(names("strict"), ["Shared#strict-query-ord"]);
+}
@@ -0,0 +1,88 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::{IntValidator, Validator};
+use fog_schemars::{render, FogValidate, Name, QueryPolicy, SchemaGenerator};
+use serde::Serialize;
+
+#[derive(FogValidate, Serialize)]
+struct Author {
+    name: String,
+}
+
+#[derive(FogValidate, Serialize)]
+enum Status {
+    Draft,
+    Published(fog_pack::types::Timestamp),
+}
+
+/// A score with a comment, as the derive doesn't add any.
+#[derive(Serialize)]
+struct Score(u8);
+
+impl FogValidate for Score {
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "Score")
+    }
+
+    fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
+        IntValidator::new()
+            .min(0)
+            .max(100)
+            .comment("Out of 100.\nHigher is better.")
+            .build()
+    }
+}
+
+#[derive(FogValidate, Serialize)]
+struct Post {
+    #[fog(length(max = 64))]
+    title: String,
+    author: Author,
+    status: Status,
+    score: Score,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+#[test]
+fn renders_schema() {
+    let schema = SchemaGenerator::new::<Post>()
+        .name("Blog")
+        .version(2)
+        .entry_add_with_query::<Author>("authors", None, QueryPolicy::new().size(true))
+        .build()
+        .unwrap();
+    let expected = r#"name: "Blog"
+version: 2
+
+doc: Map
+  req "author": ref "Author"
+  req "score": ref "Score"
+  req "status": ref "Status"
+  req "title": Str (len <= 64)
+  opt "tags": Array (len >= 1)
+    items: Str
+
+entry "authors": Map
+  req "name": Str (queries: size)
+
+type "Author": Map
+  req "name": Str
+
+type "Score": Int (>= 0, <= 100)
+  // Out of 100.
+  // Higher is better.
+
+type "Status": Enum
+  "Draft"
+  "Published": Time
+"#;
+    assert_eq!(render(&schema).unwrap(), expected);
+}
+
+#[test]
+fn only_renders_schemas() {
+    let doc = fog_pack::document::NewDocument::new(None, "not a schema").unwrap();
+    let doc = fog_pack::schema::NoSchema::validate_new_doc(doc).unwrap();
+    assert!(render(&doc).is_err());
+}
@@ -0,0 +1,254 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use fog_pack::types::{Hash, Identity, Timestamp, Value};
+use fog_pack::validator::*;
+use fog_schemars::{rust, FogValidate, Name, SchemaGenerator};
+use serde::Serialize;
+
+#[derive(FogValidate, Serialize)]
+struct Author {
+    name: String,
+    key: Identity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+}
+
+#[derive(FogValidate, Serialize)]
+enum Status {
+    Draft,
+    Published(Timestamp),
+    Retracted { reason: String },
+}
+
+#[derive(FogValidate, Serialize)]
+#[serde(tag = "kind")]
+enum Block {
+    Text { body: String },
+    Quote { body: String, source: String },
+    Break,
+}
+
+#[derive(FogValidate, Serialize)]
+#[serde(tag = "t", content = "c")]
+enum Embed {
+    Image(Hash),
+    Post(Box<Post>),
+    Nothing,
+}
+
+#[derive(FogValidate, Serialize)]
+#[serde(untagged)]
+enum Reaction {
+    Like,
+    Emoji(char),
+    Custom(String),
+}
+
+fn title(_: &mut SchemaGenerator) -> Validator {
+    StrValidator::new().min_len(1).max_len(200).build()
+}
+
+fn rating(_: &mut SchemaGenerator) -> Validator {
+    IntValidator::new().min(1).max(5).build()
+}
+
+#[derive(FogValidate, Serialize)]
+#[fog(rename = "Post")]
+struct Post {
+    #[fog(schema_with = "title")]
+    title: String,
+    author: Author,
+    status: Status,
+    blocks: Vec<Block>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    embeds: Vec<Embed>,
+    parent: Option<Box<Post>>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    likes: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    links: BTreeMap<String, Hash>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
+    #[serde(rename = "type")]
+    type_: u8,
+}
+
+#[derive(FogValidate, Serialize)]
+#[fog(allow_unknown_fields)]
+struct Comment {
+    text: String,
+    reactions: Vec<Reaction>,
+    #[fog(schema_with = "rating")]
+    rating: u8,
+    #[serde(default)]
+    #[fog(allow_missing_skip)]
+    reply_to: Option<Hash>,
+}
+
+/// Two types with the same name in different modules, one of which holds a
+/// generic type.
+struct GeoPoint;
+
+impl FogValidate for GeoPoint {
+    fn validator_name(_: bool) -> Name {
+        Name::new("app::geo", "Point")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        MapValidator::new()
+            .comment("A point on the globe.")
+            .req_add("lat", F64Validator::new().min(-90.0).max(90.0).build())
+            .req_add("lon", F64Validator::new().min(-180.0).max(180.0).build())
+            .opt_add("label", gen.type_add::<Pair<u8>>())
+            .build()
+    }
+}
+
+struct GridPoint;
+
+impl FogValidate for GridPoint {
+    fn validator_name(_: bool) -> Name {
+        Name::new("app::grid", "Point")
+    }
+
+    fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
+        ArrayValidator::new()
+            .prefix_add(IntValidator::new().build())
+            .prefix_add(IntValidator::new().build())
+            .build()
+    }
+}
+
+struct Pair<T>(T);
+
+impl<T: FogValidate> FogValidate for Pair<T> {
+    fn validator_name(_: bool) -> Name {
+        Name::with_types("app", "Pair", vec![T::validator_name(false)])
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        ArrayValidator::new()
+            .items(gen.type_add::<T>())
+            .max_len(2)
+            .min_len(2)
+            .build()
+    }
+}
+
+struct Map;
+
+impl FogValidate for Map {
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "Map")
+    }
+
+    fn validator(gen: &mut SchemaGenerator, _: bool) -> Validator {
+        MapValidator::new()
+            .req_add("geo", gen.type_add::<GeoPoint>())
+            .req_add("grid", gen.type_add::<GridPoint>())
+            .req_add(
+                "source",
+                HashValidator {
+                    comment: "Where the map came from.".into(),
+                    ..HashValidator::new()
+                }
+                .link(gen.type_add::<GeoPoint>())
+                .build(),
+            )
+            .req_add(
+                "scale",
+                IntValidator::new().in_add(1).in_add(10).in_add(100).build(),
+            )
+            .req_add(
+                "updated",
+                TimeValidator::new()
+                    .min(Timestamp::from_utc(1_600_000_000, 0).unwrap())
+                    .build(),
+            )
+            .req_add(
+                "layers",
+                ArrayValidator::new()
+                    .items(StrValidator::new().build())
+                    .nin_add(vec![Value::Str("hidden".into())])
+                    .unique(true)
+                    .build(),
+            )
+            .req_add(
+                "tiles",
+                MapValidator::new()
+                    .keys(StrValidator::new().max_len(8))
+                    .values(StrValidator::new().max_len(64).build())
+                    .build(),
+            )
+            .build()
+    }
+}
+
+fn blog() -> fog_pack::document::Document {
+    SchemaGenerator::new::<Post>()
+        .name("Blog")
+        .description("Posts with comments.")
+        .version(3)
+        .regexes(4)
+        .doc_compress(fog_pack::schema::Compress::None)
+        .entry_add::<Comment>(
+            "comments",
+            Some(fog_pack::schema::Compress::new_zstd_general(5)),
+        )
+        .build()
+        .unwrap()
+}
+
+fn maps() -> fog_pack::document::Document {
+    SchemaGenerator::new::<Map>().build().unwrap()
+}
+
+#[path = "generated/blog.rs"]
+mod generated_blog;
+#[path = "generated/maps.rs"]
+mod generated_maps;
+
+#[test]
+fn exports_derived_types() {
+    let code = rust::export(&blog()).unwrap();
+    assert_eq!(code, include_str!("generated/blog.rs"));
+}
+
+#[test]
+fn exports_handwritten_types() {
+    let code = rust::export(&maps()).unwrap();
+    assert_eq!(code, include_str!("generated/maps.rs"));
+}
+
+#[test]
+fn generated_types_give_back_the_schema() {
+    let schema = generated_blog::schema().build().unwrap();
+    assert_eq!(schema.hash(), blog().hash());
+    let schema = generated_maps::schema().build().unwrap();
+    assert_eq!(schema.hash(), maps().hash());
+}
+
+#[test]
+fn notes_what_cannot_be_generated() {
+    let base = SchemaGenerator::new::<Post>().build().unwrap();
+    let schema = SchemaGenerator::new::<Comment>()
+        .import_types(&base)
+        .unwrap()
+        .build()
+        .unwrap();
+    let code = rust::export(&schema).unwrap();
+    assert!(
+        code.contains("// NOTE: nothing refers to \"Author\", so it won't be in the type list\n")
+    );
+}
+
+#[test]
+fn rejects_other_documents() {
+    let doc = fog_pack::document::NewDocument::new(None, 1u8).unwrap();
+    let doc = fog_pack::schema::NoSchema::validate_new_doc(doc).unwrap();
+    assert!(rust::export(&doc).is_err());
+}
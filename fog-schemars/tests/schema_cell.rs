@@ -0,0 +1,49 @@
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use fog_schemars::{FogSchema, FogValidate, SchemaCell, SchemaGenerator};
+
+#[derive(FogValidate, FogSchema)]
+#[fog_schema(name = "Config")]
+struct Config {
+    name: String,
+}
+
+static FROM_GENERATOR: SchemaCell =
+    SchemaCell::new(|| SchemaGenerator::new::<Config>().name("Config"));
+static FROM_TYPE: SchemaCell = SchemaCell::of::<Config>();
+
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+static COUNTED: SchemaCell = SchemaCell::new(|| {
+    BUILDS.fetch_add(1, Ordering::SeqCst);
+    SchemaGenerator::new::<Config>()
+});
+
+#[test]
+fn schema_is_only_built_once() {
+    let threads: Vec<_> = (0..8)
+        .map(|_| std::thread::spawn(|| COUNTED.schema() as *const _ as usize))
+        .collect();
+    let first: *const _ = COUNTED.schema();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), first as usize);
+    }
+    let second: *const _ = COUNTED.schema();
+    assert_eq!(first, second);
+    COUNTED.document();
+    COUNTED.hash();
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn cell_matches_generated_schema() {
+    let doc = SchemaGenerator::new::<Config>()
+        .name("Config")
+        .build()
+        .unwrap();
+    assert_eq!(FROM_GENERATOR.hash(), doc.hash());
+    assert_eq!(FROM_GENERATOR.document().hash(), doc.hash());
+    assert_eq!(FROM_GENERATOR.schema().hash(), doc.hash());
+    assert_eq!(FROM_TYPE.hash(), doc.hash());
+}
@@ -0,0 +1,72 @@
+#![allow(dead_code)]
+
+use fog_pack::schema::{Compress, Schema};
+use fog_schemars::{FogSchema, FogValidate, SchemaGenError, SchemaGenerator};
+
+#[derive(FogValidate)]
+struct Comment {
+    text: String,
+}
+
+#[derive(FogValidate)]
+struct Reaction {
+    emoji: String,
+}
+
+#[derive(FogValidate, FogSchema)]
+#[fog_schema(name = "Post", description = "A post with comments")]
+#[fog_schema(version = 2, doc_compress = "none", regexes = 1)]
+#[fog_schema(
+    entry("comments" = Comment, compress = "zstd"),
+    entry("reactions" = Reaction, compress = "zstd:9")
+)]
+struct Post {
+    title: String,
+}
+
+#[derive(FogValidate, FogSchema)]
+struct Bare {
+    value: u8,
+}
+
+#[derive(FogValidate, FogSchema)]
+struct BadRegex {
+    #[fog(regex(pattern = "[0-9"))]
+    digits: String,
+}
+
+#[test]
+fn derived_schema_matches_generator() {
+    let expected = SchemaGenerator::new::<Post>()
+        .name("Post")
+        .description("A post with comments")
+        .version(2)
+        .regexes(1)
+        .doc_compress(Compress::None)
+        .entry_add::<Comment>("comments", Some(Compress::default()))
+        .entry_add::<Reaction>("reactions", Some(Compress::new_zstd_general(9)))
+        .build()
+        .unwrap();
+    let actual = Post::schema_document();
+    assert_eq!(actual.hash(), expected.hash());
+    assert!(Schema::from_doc(&actual).is_ok());
+    assert_eq!(Post::ENTRIES, &["comments", "reactions"]);
+}
+
+#[test]
+fn derived_schema_without_attributes() {
+    let expected = SchemaGenerator::new::<Bare>().build().unwrap();
+    assert_eq!(Bare::schema_document().hash(), expected.hash());
+    assert!(Bare::ENTRIES.is_empty());
+}
+
+#[test]
+fn derived_schema_reports_errors() {
+    assert_eq!(
+        Bare::try_schema_document().unwrap().hash(),
+        Bare::schema_document().hash()
+    );
+    let err = BadRegex::try_schema_document().unwrap_err();
+    assert!(matches!(err, SchemaGenError::Type { .. }), "{}", err);
+    assert!(std::panic::catch_unwind(BadRegex::schema_document).is_err());
+}
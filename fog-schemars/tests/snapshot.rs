@@ -0,0 +1,83 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use fog_schemars::testing::{assert_schema_stable, UPDATE_ENV_VAR};
+use fog_schemars::{FogValidate, QueryPolicy, SchemaGenerator};
+
+#[derive(FogValidate)]
+struct Before {
+    a: u8,
+}
+
+#[derive(FogValidate)]
+#[fog(rename = "Before")]
+struct After {
+    a: u8,
+    b: String,
+}
+
+/// A directory for one test's snapshot, removed again when the test ends.
+struct SnapshotDir(PathBuf);
+
+impl SnapshotDir {
+    fn new(test: &str) -> Self {
+        // These tests expect snapshots to be checked, not overwritten.
+        std::env::remove_var(UPDATE_ENV_VAR);
+        let dir =
+            std::env::temp_dir().join(format!("fog-schemars-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        Self(dir)
+    }
+
+    fn path(&self) -> PathBuf {
+        self.0.join("schema.fogschema")
+    }
+}
+
+impl Drop for SnapshotDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn snapshot_is_written_then_checked() {
+    let dir = SnapshotDir::new("stable");
+    let path = dir.path();
+    assert_schema_stable(SchemaGenerator::new::<Before>(), &path);
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("\ndoc: Map\n  req \"a\": Int"), "{}", contents);
+    assert_schema_stable(SchemaGenerator::new::<Before>(), &path);
+}
+
+#[test]
+fn changed_schema_fails_with_diff() {
+    let dir = SnapshotDir::new("changed");
+    let path = dir.path();
+    assert_schema_stable(SchemaGenerator::new::<Before>().name("Before"), &path);
+    let result = std::panic::catch_unwind(|| {
+        assert_schema_stable(SchemaGenerator::new::<After>().name("After"), &path)
+    });
+    let msg = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(msg.contains("name: \"Before\" -> \"After\""), "{}", msg);
+    assert!(msg.contains("This is a breaking change"), "{}", msg);
+    assert!(
+        msg.contains("incompatible: doc.b: required field added"),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn query_change_is_not_breaking() {
+    let dir = SnapshotDir::new("query");
+    let path = dir.path();
+    let policy = QueryPolicy::new().ord(true);
+    assert_schema_stable(SchemaGenerator::new::<Before>().query_policy(policy), &path);
+    let result =
+        std::panic::catch_unwind(|| assert_schema_stable(SchemaGenerator::new::<Before>(), &path));
+    let msg = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(msg.contains("neutral: doc.a"), "{}", msg);
+    assert!(!msg.contains("breaking"), "{}", msg);
+}
@@ -0,0 +1,134 @@
+mod util;
+
+use fog_pack::types::Value;
+use fog_schemars::{FogValidate, SchemaGenError, SchemaGenerator};
+use serde::Serialize;
+use util::*;
+
+#[derive(Serialize, FogValidate)]
+struct Fields {
+    required: u8,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    skipped: u8,
+    #[serde(default)]
+    #[fog(allow_missing_skip)]
+    default_only: u8,
+}
+
+fn is_zero(v: &u8) -> bool {
+    *v == 0
+}
+
+#[derive(Serialize, FogValidate)]
+#[allow(dead_code)]
+struct Triple(u8, #[serde(skip_serializing)] String, u8);
+
+const NAME_PATTERN: &str = "^[a-z]+$";
+
+#[derive(Serialize, FogValidate)]
+struct Validated {
+    #[fog(range(min = 0.5, max = 1.5))]
+    ratio: f64,
+    #[fog(range(min = 1, max = 5))]
+    score: f32,
+    #[fog(regex(pattern = "^[0-9]+$"))]
+    digits: String,
+    #[fog(regex = "NAME_PATTERN", length(max = 4))]
+    name: String,
+}
+
+#[test]
+fn derived_struct_roundtrips() {
+    let schema = schema_for::<Fields>();
+    assert!(passes(
+        &schema,
+        Fields {
+            required: 0,
+            skipped: 0,
+            default_only: 0
+        }
+    ));
+    assert!(passes(
+        &schema,
+        Fields {
+            required: 1,
+            skipped: 2,
+            default_only: 3
+        }
+    ));
+}
+
+#[test]
+fn required_fields_must_be_present() {
+    let schema = schema_for::<Fields>();
+    assert!(!passes(&schema, map(&[("skipped", 1)])));
+}
+
+#[test]
+fn default_only_fields_are_optional() {
+    let schema = schema_for::<Fields>();
+    assert!(passes(&schema, map(&[("required", 1)])));
+    assert!(passes(
+        &schema,
+        map(&[("required", 1), ("default_only", 0)])
+    ));
+}
+
+#[test]
+fn skipped_fields_use_opt_validator() {
+    let schema = schema_for::<Fields>();
+    assert!(passes(&schema, map(&[("required", 1), ("skipped", 1)])));
+    assert!(!passes(&schema, map(&[("required", 1), ("skipped", 0)])));
+}
+
+#[test]
+fn tuple_structs_leave_out_skipped_fields() {
+    let schema = schema_for::<Triple>();
+    assert!(passes(&schema, Triple(1, "unwritten".into(), 2)));
+    assert!(passes(&schema, (1u8, 2u8)));
+    assert!(!passes(&schema, (1u8, "unwritten", 2u8)));
+}
+
+#[test]
+fn float_ranges() {
+    let schema = schema_for::<Validated>();
+    let doc = |ratio: f64, score: f32| {
+        let mut doc = map(&[("digits", "1"), ("name", "ab")]);
+        doc.insert("ratio".into(), Value::from(ratio));
+        doc.insert("score".into(), Value::from(score));
+        doc
+    };
+    assert!(passes(&schema, doc(1.0, 3.0)));
+    assert!(!passes(&schema, doc(0.25, 3.0)));
+    assert!(!passes(&schema, doc(1.0, 6.0)));
+}
+
+#[test]
+fn regex_patterns() {
+    let schema = schema_for::<Validated>();
+    let doc = |digits: &str, name: &str| {
+        let mut doc = map(&[("digits", digits), ("name", name)]);
+        doc.insert("ratio".into(), Value::from(1.0));
+        doc.insert("score".into(), Value::from(1.0f32));
+        doc
+    };
+    assert!(passes(&schema, doc("123", "abcd")));
+    assert!(!passes(&schema, doc("12a", "abcd")));
+    assert!(!passes(&schema, doc("123", "ABC")));
+    assert!(!passes(&schema, doc("123", "abcde")));
+}
+
+#[derive(Serialize, FogValidate)]
+struct BadRegex {
+    #[fog(regex(pattern = "[0-9"))]
+    digits: String,
+}
+
+#[test]
+fn invalid_regex_is_reported() {
+    let err = SchemaGenerator::new::<BadRegex>().try_build().unwrap_err();
+    let SchemaGenError::Type { message, .. } = err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert!(message.contains("invalid regex `[0-9`"), "{}", message);
+}
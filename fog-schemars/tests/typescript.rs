@@ -0,0 +1,139 @@
+#![allow(dead_code)]
+
+use fog_pack::validator::{IntValidator, MapValidator, StrValidator, Validator};
+use fog_schemars::{typescript, FogValidate, Name, SchemaGenerator};
+use serde::Serialize;
+
+#[derive(FogValidate, Serialize)]
+struct Author {
+    name: String,
+    key: fog_pack::types::Identity,
+}
+
+#[derive(FogValidate, Serialize)]
+enum Status {
+    Draft,
+    Published(fog_pack::types::Timestamp),
+}
+
+#[derive(FogValidate, Serialize)]
+struct Post {
+    title: String,
+    author: Author,
+    status: Status,
+    likes: u32,
+    views: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    parent: Option<Box<Post>>,
+    pair: (u8, String),
+    links: std::collections::BTreeMap<String, fog_pack::types::Hash>,
+}
+
+/// A type whose name and keys aren't valid TypeScript identifiers.
+struct Odd;
+
+impl FogValidate for Odd {
+    fn validator_name(_: bool) -> Name {
+        Name::new("", "Document")
+    }
+
+    fn validator(_: &mut SchemaGenerator, _: bool) -> Validator {
+        MapValidator::new()
+            .comment("An odd one.\nWith two lines.")
+            .req_add(
+                "my-key",
+                StrValidator::new().in_add("a").in_add("b").build(),
+            )
+            .opt_add("ok", IntValidator::new().comment("Just fine").build())
+            .build()
+    }
+}
+
+#[derive(FogValidate)]
+struct HasOdd {
+    odd: Odd,
+}
+
+#[test]
+fn exports_declarations() {
+    let schema = SchemaGenerator::new::<Post>()
+        .name("Blog")
+        .entry_add::<Author>("authors", None)
+        .build()
+        .unwrap();
+    let expected = r#"// Types for the "Blog" schema.
+
+declare const fogBrand: unique symbol;
+export type Brand<T, B extends string> = T & { readonly [fogBrand]: B };
+export type Hash = Brand<Uint8Array, "Hash">;
+export type Identity = Brand<Uint8Array, "Identity">;
+export type Timestamp = Brand<Uint8Array, "Timestamp">;
+
+export interface Author {
+  key: Identity;
+  name: string;
+}
+
+export type OptionPost = null | Post;
+
+export interface Post {
+  author: Author;
+  likes: number;
+  links: Record<string, Hash>;
+  pair: [number, string];
+  parent: OptionPost;
+  status: Status;
+  title: string;
+  views: number | bigint;
+  tags?: string[];
+}
+
+export type Status = "Draft" | { Published: Timestamp };
+
+export type Document = Post;
+
+export type AuthorsEntry = Author;
+"#;
+    assert_eq!(typescript::export(&schema).unwrap(), expected);
+}
+
+#[test]
+fn names_and_keys() {
+    let schema = SchemaGenerator::new::<HasOdd>().build().unwrap();
+    let expected = r#"/**
+ * An odd one.
+ * With two lines.
+ */
+export interface Document {
+  "my-key": "a" | "b";
+  /** Just fine */
+  ok?: number | bigint;
+}
+
+export interface Document2 {
+  odd: Document;
+}
+"#;
+    assert_eq!(typescript::export(&schema).unwrap(), expected);
+}
+
+#[derive(FogValidate)]
+#[fog(extra_values = "String")]
+struct Extra {
+    count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<bool>,
+}
+
+#[test]
+fn extra_values() {
+    let schema = SchemaGenerator::new::<Extra>().build().unwrap();
+    let expected = r#"export interface Document {
+  count: number;
+  note?: boolean;
+  [key: string]: string | number | boolean;
+}
+"#;
+    assert_eq!(typescript::export(&schema).unwrap(), expected);
+}
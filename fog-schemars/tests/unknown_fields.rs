@@ -0,0 +1,79 @@
+mod util;
+
+use fog_pack::types::Value;
+use fog_schemars::FogValidate;
+use serde::Serialize;
+use util::*;
+
+#[derive(Serialize, FogValidate)]
+#[serde(deny_unknown_fields)]
+struct Strict {
+    a: u8,
+}
+
+#[derive(Serialize, FogValidate)]
+#[fog(allow_unknown_fields)]
+struct Open {
+    a: u8,
+}
+
+#[derive(Serialize, FogValidate)]
+#[fog(extra_values = "u8")]
+struct Extra {
+    a: u8,
+}
+
+#[derive(Serialize, FogValidate)]
+#[serde(tag = "type")]
+#[fog(allow_unknown_fields)]
+enum Tagged {
+    A { a: u8 },
+}
+
+#[test]
+fn unknown_fields_are_denied_by_default() {
+    let schema = schema_for::<Strict>();
+    assert!(passes(&schema, map(&[("a", Value::from(1u8))])));
+    assert!(!passes(
+        &schema,
+        map(&[("a", Value::from(1u8)), ("b", Value::from(1u8))])
+    ));
+}
+
+#[test]
+fn allow_unknown_fields_accepts_anything() {
+    let schema = schema_for::<Open>();
+    assert!(passes(
+        &schema,
+        map(&[("a", Value::from(1u8)), ("b", Value::from("text"))])
+    ));
+    // Known fields are still checked.
+    assert!(!passes(&schema, map(&[("a", Value::from("text"))])));
+}
+
+#[test]
+fn extra_values_checks_unknown_fields() {
+    let schema = schema_for::<Extra>();
+    assert!(passes(
+        &schema,
+        map(&[("a", Value::from(1u8)), ("b", Value::from(2u8))])
+    ));
+    assert!(!passes(
+        &schema,
+        map(&[("a", Value::from(1u8)), ("b", Value::from("text"))])
+    ));
+}
+
+#[test]
+fn allow_unknown_fields_carries_over_to_variants() {
+    let schema = schema_for::<Tagged>();
+    assert!(passes(&schema, Tagged::A { a: 1 }));
+    assert!(passes(
+        &schema,
+        map(&[
+            ("type", Value::from("A")),
+            ("a", Value::from(1u8)),
+            ("b", Value::from(true))
+        ])
+    ));
+}
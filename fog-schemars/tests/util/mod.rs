@@ -0,0 +1,42 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use fog_pack::{document::NewDocument, schema::Schema, types::Value};
+use fog_schemars::{FogValidate, SchemaGenerator};
+use serde::Serialize;
+
+/// Build a schema whose documents are validated by `T`.
+pub fn schema_for<T: FogValidate>() -> Schema {
+    let doc = SchemaGenerator::new::<T>().build().unwrap();
+    Schema::from_doc(&doc).unwrap()
+}
+
+/// Build a strict schema whose documents are validated by `T`.
+pub fn strict_schema_for<T: FogValidate>() -> Schema {
+    let doc = SchemaGenerator::new_strict::<T>().build().unwrap();
+    Schema::from_doc(&doc).unwrap()
+}
+
+/// Check if `data` passes as a document for the given schema.
+pub fn passes<S: Serialize>(schema: &Schema, data: S) -> bool {
+    let doc = NewDocument::new(Some(schema.hash()), data).unwrap();
+    schema.validate_new_doc(doc).is_ok()
+}
+
+/// Build a map to use as a document, from keys and values.
+pub fn map<V: Clone + Into<Value>>(items: &[(&str, V)]) -> BTreeMap<String, Value> {
+    items
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone().into()))
+        .collect()
+}
+
+/// The names in the type list of the schema a generator builds.
+pub fn type_names(gen: SchemaGenerator) -> Vec<String> {
+    let schema = gen.try_build().unwrap().deserialize::<Value>().unwrap();
+    match &schema["types"] {
+        Value::Map(types) => types.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}